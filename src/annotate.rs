@@ -0,0 +1,175 @@
+use crate::{entry_overhead, own_size};
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+/// A copy of a `serde_json::Value` where every node carries its estimated size, created by
+/// [`annotate`].
+///
+/// Serializes as `{"__size": n, "__percent": p, "value": ...}` around every node, where `value`
+/// holds the node itself with its children annotated in turn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotatedValue {
+    /// Estimated size of the subtree, including the key and map entry overhead of a member.
+    pub size: usize,
+    /// `size` as a percentage of the size of the whole document, between 0 and 100.
+    pub percent: f64,
+    pub node: AnnotatedNode,
+}
+
+/// The content of an [`AnnotatedValue`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotatedNode {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    Array(Vec<AnnotatedValue>),
+    /// The members, in the order of the source object.
+    Object(Vec<(String, AnnotatedValue)>),
+}
+
+impl AnnotatedValue {
+    /// Returns the annotated node at the JSON Pointer `pointer`, as [`Value::pointer`] would.
+    pub fn pointer(&self, pointer: &str) -> Option<&AnnotatedValue> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        let mut node = self;
+        for token in pointer.strip_prefix('/')?.split('/') {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            node = match &node.node {
+                AnnotatedNode::Array(items) => items.get(token.parse::<usize>().ok()?)?,
+                AnnotatedNode::Object(members) => &members.iter().find(|(k, _)| *k == token)?.1,
+                _ => return None,
+            };
+        }
+        Some(node)
+    }
+}
+
+/// Builds an [`AnnotatedValue`] mirroring `v`, with the estimated size of every node and its share
+/// of the whole document.
+///
+/// Sizes follow [`crate::SizeTree`]: the root's size is [`crate::sizeof_val`], and each node's
+/// size is its own cost plus that of its children.
+///
+/// ## Example
+/// ```
+/// use json_size::{annotate, sizeof_val};
+/// use serde_json::json;
+///
+/// let val = json!({"id": 7, "avatar": "iVBORw0KGgo".repeat(100)});
+/// let annotated = annotate(&val);
+/// assert_eq!(annotated.size, sizeof_val(&val));
+/// assert!(annotated.pointer("/avatar").unwrap().percent > 50.0);
+/// let out = serde_json::to_value(&annotated).unwrap();
+/// assert_eq!(out["__percent"], 100.0);
+/// assert_eq!(out["value"]["id"]["value"], 7);
+/// ```
+pub fn annotate(v: &Value) -> AnnotatedValue {
+    let mut root = build(v, 0);
+    let total = root.size;
+    fill_percent(&mut root, total);
+    root
+}
+
+fn build(v: &Value, extra: usize) -> AnnotatedValue {
+    let mut size = own_size(v) + extra;
+    let node = match v {
+        Value::Null => AnnotatedNode::Null,
+        Value::Bool(b) => AnnotatedNode::Bool(*b),
+        Value::Number(n) => AnnotatedNode::Number(n.clone()),
+        Value::String(s) => AnnotatedNode::String(s.clone()),
+        Value::Array(a) => {
+            let items: Vec<_> = a.iter().map(|item| build(item, 0)).collect();
+            size = items
+                .iter()
+                .fold(size, |acc, item| acc.saturating_add(item.size));
+            AnnotatedNode::Array(items)
+        }
+        Value::Object(o) => {
+            let members: Vec<_> = o
+                .iter()
+                .map(|(k, item)| (k.clone(), build(item, entry_overhead(k))))
+                .collect();
+            size = members
+                .iter()
+                .fold(size, |acc, (_, item)| acc.saturating_add(item.size));
+            AnnotatedNode::Object(members)
+        }
+    };
+    AnnotatedValue {
+        size,
+        percent: 0.0,
+        node,
+    }
+}
+
+fn fill_percent(v: &mut AnnotatedValue, total: usize) {
+    v.percent = 100.0 * v.size as f64 / total.max(1) as f64;
+    match &mut v.node {
+        AnnotatedNode::Array(items) => items.iter_mut().for_each(|item| fill_percent(item, total)),
+        AnnotatedNode::Object(members) => members
+            .iter_mut()
+            .for_each(|(_, item)| fill_percent(item, total)),
+        _ => {}
+    }
+}
+
+impl Serialize for AnnotatedValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("__size", &self.size)?;
+        map.serialize_entry("__percent", &self.percent)?;
+        map.serialize_entry("value", &self.node)?;
+        map.end()
+    }
+}
+
+impl Serialize for AnnotatedNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Number(n) => n.serialize(serializer),
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Array(items) => serializer.collect_seq(items),
+            Self::Object(members) => {
+                serializer.collect_map(members.iter().map(|(k, item)| (k, item)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sizeof_val, SizeTree};
+    use serde_json::json;
+
+    #[test]
+    fn test_sizes_match_tree() {
+        let val = json!({"a": [1, "two", {"x": null}], "b": {"c": true}});
+        let annotated = annotate(&val);
+        for node in SizeTree::new(&val).iter() {
+            let found = annotated.pointer(&node.path.to_string()).unwrap();
+            assert_eq!(found.size, node.cumulative_size, "{}", node.path);
+        }
+        assert_eq!(annotated.percent, 100.0);
+        let b = annotated.pointer("/b").unwrap();
+        assert_eq!(b.percent, 100.0 * b.size as f64 / sizeof_val(&val) as f64);
+        assert_eq!(annotated.pointer("/a/9"), None);
+        assert_eq!(annotated.pointer("/a/0/x"), None);
+    }
+
+    #[test]
+    fn test_serializes_with_wrappers() {
+        let annotated = annotate(&json!({"k~/": ["v"]}));
+        let out = serde_json::to_value(&annotated).unwrap();
+        let member = &out["value"]["k~/"];
+        assert_eq!(member["__size"], annotated.pointer("/k~0~1").unwrap().size);
+        assert_eq!(member["value"][0]["value"], "v");
+        assert_eq!(member["value"][0].as_object().unwrap().len(), 3, "{member}");
+    }
+}
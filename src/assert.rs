@@ -0,0 +1,78 @@
+//! Development-time size checks, compiled out of release builds.
+
+/// Asserts that the estimated size of a value, as computed by [`crate::sizeof_val`], is at most
+/// a number of bytes.
+///
+/// Like `debug_assert!`, the check only runs when debug assertions are enabled: in release
+/// builds neither the value expression nor the estimate is evaluated. An optional message in
+/// `format!` syntax is added to the panic message.
+///
+/// ## Example
+/// ```
+/// use json_size::debug_assert_json_size;
+/// use serde_json::json;
+///
+/// let response = json!({"items": [1, 2, 3]});
+/// debug_assert_json_size!(response, 64 * 1024);
+/// debug_assert_json_size!(response, 64 * 1024, "response for {}", "/items");
+/// ```
+#[macro_export]
+macro_rules! debug_assert_json_size {
+    ($value:expr, $max_bytes:expr $(,)?) => {
+        if ::core::cfg!(debug_assertions) {
+            let size = $crate::sizeof_val(&$value);
+            let max_bytes: usize = $max_bytes;
+            if size > max_bytes {
+                ::core::panic!(
+                    "`{}` is estimated at {} bytes, over the limit of {} bytes",
+                    ::core::stringify!($value),
+                    size,
+                    max_bytes
+                );
+            }
+        }
+    };
+    ($value:expr, $max_bytes:expr, $($arg:tt)+) => {
+        if ::core::cfg!(debug_assertions) {
+            let size = $crate::sizeof_val(&$value);
+            let max_bytes: usize = $max_bytes;
+            if size > max_bytes {
+                ::core::panic!(
+                    "`{}` is estimated at {} bytes, over the limit of {} bytes: {}",
+                    ::core::stringify!($value),
+                    size,
+                    max_bytes,
+                    ::core::format_args!($($arg)+)
+                );
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sizeof_val;
+    use serde_json::json;
+
+    #[test]
+    fn test_passes_at_limit() {
+        let val = json!({"a": [1, 2]});
+        debug_assert_json_size!(val, sizeof_val(&val));
+        debug_assert_json_size!(&val, sizeof_val(&val), "with {}", "a message");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_panics_over_limit() {
+        let val = json!("text");
+        let size = sizeof_val(&val);
+        let limit = size - 1;
+        let err = std::panic::catch_unwind(|| {
+            debug_assert_json_size!(val, limit, "{}", "body");
+        })
+        .unwrap_err();
+        let expected =
+            format!("`val` is estimated at {size} bytes, over the limit of {limit} bytes: body");
+        assert_eq!(err.downcast_ref::<String>(), Some(&expected));
+    }
+}
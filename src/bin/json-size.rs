@@ -0,0 +1,125 @@
+//! Prints the estimated in-memory size, the serialized size and the heaviest paths of a JSON
+//! document read from a file or stdin, or aggregate statistics over a directory of JSON files.
+
+use json_size::{profile_dir, ProfileOptions, SizeReport};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: json-size [OPTIONS] [FILE]
+
+Reads a JSON document from FILE, or from stdin if FILE is omitted or `-`. If FILE is a
+directory, every .json and .jsonl file below it is profiled in parallel instead.
+
+Options:
+  -n, --top <N>  Number of heaviest paths, or keys for a directory, to list [default: 10]
+  -H, --human    Print sizes in KiB/MiB/...
+      --json     Print the report as JSON
+  -h, --help     Print this help";
+
+struct Args {
+    top: usize,
+    human: bool,
+    json: bool,
+    file: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args {
+        top: 10,
+        human: false,
+        json: false,
+        file: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-H" | "--human" => parsed.human = true,
+            "--json" => parsed.json = true,
+            "-n" | "--top" => {
+                let n = args.next().ok_or(format!("{arg} expects a value"))?;
+                parsed.top = n
+                    .parse()
+                    .map_err(|_| format!("invalid value for {arg}: {n}"))?;
+            }
+            "-" => parsed.file = None,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {arg}")),
+            _ if parsed.file.is_some() => return Err(format!("unexpected argument: {arg}")),
+            _ => parsed.file = Some(arg),
+        }
+    }
+    Ok(Some(parsed))
+}
+
+fn read_value(file: Option<&str>) -> Result<Value, String> {
+    let reader: Box<dyn Read> = match file {
+        Some(path) => Box::new(File::open(path).map_err(|e| format!("{path}: {e}"))?),
+        None => Box::new(io::stdin().lock()),
+    };
+    serde_json::from_reader(BufReader::new(reader))
+        .map_err(|e| format!("{}: {e}", file.unwrap_or("<stdin>")))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("json-size: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if let Some(dir) = args.file.as_deref().filter(|f| Path::new(f).is_dir()) {
+        return print_dir(dir, &args);
+    }
+    let value = match read_value(args.file.as_deref()) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("json-size: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let report = SizeReport::new(&value, args.top);
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string(&report).expect("reports always serialize")
+        );
+    } else if args.human {
+        println!("{report:#}");
+    } else {
+        println!("{report}");
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_dir(dir: &str, args: &Args) -> ExitCode {
+    let options = ProfileOptions {
+        top_keys: args.top,
+        ..ProfileOptions::default()
+    };
+    let profile = match profile_dir(dir, &options) {
+        Ok(profile) => profile,
+        Err(e) => {
+            eprintln!("json-size: {dir}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string(&profile).expect("profiles always serialize")
+        );
+    } else if args.human {
+        println!("{profile:#}");
+    } else {
+        println!("{profile}");
+    }
+    ExitCode::SUCCESS
+}
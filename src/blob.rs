@@ -0,0 +1,272 @@
+use crate::{serialized_size, JsonPath};
+use serde::Serialize;
+use serde_json::Value;
+
+/// How a binary blob is encoded into a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobEncoding {
+    /// Standard or URL-safe base64, padded or not, possibly inside a `data:` URL.
+    Base64,
+    Hex,
+}
+
+/// A string value that looks like an encoded binary blob.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Blob {
+    pub path: JsonPath,
+    pub encoding: BlobEncoding,
+    /// Serialized size of the string, quotes included.
+    pub encoded_size: usize,
+    /// Number of bytes the string decodes to.
+    pub decoded_size: usize,
+}
+
+impl Blob {
+    /// Serialized size of the blob as a binary byte string, with a MessagePack `bin` header.
+    pub fn binary_size(&self) -> usize {
+        byte_string_header(self.decoded_size) + self.decoded_size
+    }
+
+    /// Bytes storing the blob as a byte string would save.
+    pub fn savings(&self) -> isize {
+        self.encoded_size as isize - self.binary_size() as isize
+    }
+}
+
+/// The encoded blobs in a document, created by [`blob_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BlobReport {
+    /// Serialized size of all the blobs.
+    pub encoded_size: usize,
+    /// Number of bytes all of them decode to.
+    pub decoded_size: usize,
+    /// Serialized size of all of them as binary byte strings.
+    pub binary_size: usize,
+    /// Every blob, the largest first.
+    pub blobs: Vec<Blob>,
+}
+
+impl BlobReport {
+    /// Bytes storing every blob as a byte string would save.
+    pub fn savings(&self) -> isize {
+        self.encoded_size as isize - self.binary_size as isize
+    }
+}
+
+/// Shortest encoded text, in bytes, reported as a blob. Shorter strings are too often words or
+/// identifiers, and too small to matter.
+const MIN_BLOB_LEN: usize = 32;
+
+/// Finds the string values of `v` that look like base64 or hex encoded binary data, and sizes them
+/// as serialized text and as the binary byte strings CBOR or MessagePack would store.
+///
+/// A string is taken for hex if it has an even number of hex digits, all in the same case. It is
+/// taken for base64 if it uses a single base64 alphabet, with padding only at the end and line
+/// breaks ignored, and mixes upper case, lower case and digits; the mix is not required inside
+/// a `data:...;base64,` URL, whose prefix is dropped from the binary size. Either way the text
+/// must be at least 32 bytes long. Keys are never reported.
+///
+/// ## Example
+/// ```
+/// use json_size::{blob_report, BlobEncoding};
+/// use serde_json::json;
+///
+/// let message = json!({
+///     "subject": "Quarterly numbers",
+///     "attachment": "UEsDBBQAAAAIAFd0S1nKjKZpQwAAAE4AAAAIAAAAbnVtYmVycy5jc3Y=",
+///     "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+/// });
+/// let report = blob_report(&message);
+/// assert_eq!(report.blobs[0].path.to_string(), "/sha256");
+/// assert_eq!(report.blobs[0].encoding, BlobEncoding::Hex);
+/// assert_eq!(report.blobs[0].decoded_size, 32);
+/// assert_eq!(report.blobs[1].encoding, BlobEncoding::Base64);
+/// assert!(report.savings() > 0);
+/// ```
+pub fn blob_report(v: &Value) -> BlobReport {
+    let mut report = BlobReport::default();
+    collect(v, &mut JsonPath::root(), &mut report);
+    report.blobs.sort_by(|a, b| {
+        b.encoded_size
+            .cmp(&a.encoded_size)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    report
+}
+
+fn collect(v: &Value, path: &mut JsonPath, report: &mut BlobReport) {
+    match v {
+        Value::String(s) => {
+            let Some((encoding, decoded_size)) = detect(s) else {
+                return;
+            };
+            let blob = Blob {
+                path: path.clone(),
+                encoding,
+                encoded_size: serialized_size(v),
+                decoded_size,
+            };
+            report.encoded_size += blob.encoded_size;
+            report.decoded_size += blob.decoded_size;
+            report.binary_size += blob.binary_size();
+            report.blobs.push(blob);
+        }
+        Value::Array(a) => {
+            for (i, item) in a.iter().enumerate() {
+                path.push_index(i);
+                collect(item, path, report);
+                path.pop();
+            }
+        }
+        Value::Object(o) => {
+            for (k, item) in o {
+                path.push_key(k.as_str());
+                collect(item, path, report);
+                path.pop();
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+}
+
+/// The encoding of `s` and the number of bytes it decodes to, if it looks like a blob.
+fn detect(s: &str) -> Option<(BlobEncoding, usize)> {
+    if s.len() < MIN_BLOB_LEN {
+        return None;
+    }
+    if let Some(decoded) = hex_len(s) {
+        return Some((BlobEncoding::Hex, decoded));
+    }
+    let (payload, in_data_url) = match data_url_payload(s) {
+        Some(payload) => (payload, true),
+        None => (s, false),
+    };
+    base64_len(payload, in_data_url).map(|decoded| (BlobEncoding::Base64, decoded))
+}
+
+fn hex_len(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let is_hex = bytes.iter().all(u8::is_ascii_hexdigit);
+    let mixed_case =
+        bytes.iter().any(u8::is_ascii_lowercase) && bytes.iter().any(u8::is_ascii_uppercase);
+    (is_hex && !mixed_case && bytes.len().is_multiple_of(2)).then_some(bytes.len() / 2)
+}
+
+/// The part of a `data:` URL after its `;base64,` marker.
+fn data_url_payload(s: &str) -> Option<&str> {
+    let rest = s.strip_prefix("data:")?;
+    let (_, payload) = rest.split_once(";base64,")?;
+    Some(payload)
+}
+
+fn base64_len(s: &str, in_data_url: bool) -> Option<usize> {
+    let (mut standard, mut url_safe) = (false, false);
+    let (mut upper, mut lower, mut digit) = (false, false, false);
+    let (mut symbols, mut padding) = (0usize, 0);
+    for b in s.bytes() {
+        match b {
+            b'\r' | b'\n' => continue,
+            b'=' => padding += 1,
+            _ if padding > 0 => return None,
+            b'A'..=b'Z' => upper = true,
+            b'a'..=b'z' => lower = true,
+            b'0'..=b'9' => digit = true,
+            b'+' | b'/' => standard = true,
+            b'-' | b'_' => url_safe = true,
+            _ => return None,
+        }
+        if b != b'=' {
+            symbols += 1;
+        }
+    }
+    let well_formed = match padding {
+        0 => symbols % 4 != 1,
+        1 | 2 => (symbols + padding).is_multiple_of(4),
+        _ => false,
+    };
+    let mixed = in_data_url || (upper && lower && digit);
+    (well_formed && mixed && !(standard && url_safe) && symbols > 0).then_some(symbols * 3 / 4)
+}
+
+/// Length of the header MessagePack puts in front of a `bin` byte string of `len` bytes.
+fn byte_string_header(len: usize) -> usize {
+    match len {
+        0..=0xff => 2,
+        0x100..=0xffff => 3,
+        _ => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sizes_base64_and_hex() {
+        // 48 bytes of binary data, each way.
+        let base64 = "q83vASNFZ4mrze8BI0VniavN7wEjRWeJq83vASNFZ4mrze8BI0VniavN7wEjRWeJ";
+        let hex = "ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789ABCDEF0123456789";
+        let val = json!({"b": base64, "h": [hex]});
+        let report = blob_report(&val);
+        let found: Vec<_> = report
+            .blobs
+            .iter()
+            .map(|b| {
+                (
+                    b.path.to_string(),
+                    b.encoding,
+                    b.encoded_size,
+                    b.decoded_size,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("/h/0".to_string(), BlobEncoding::Hex, 98, 48),
+                ("/b".to_string(), BlobEncoding::Base64, 66, 48),
+            ]
+        );
+        assert_eq!(report.binary_size, 2 * (2 + 48));
+        assert_eq!(report.savings(), 98 + 66 - 100);
+    }
+
+    #[test]
+    fn test_padding_line_breaks_and_data_urls() {
+        assert_eq!(
+            detect("QUJDREVGR0hJSktMTU5PUFFSU1RVVldYWVo0MjQy\nZm9vYmFyMQ=="),
+            Some((BlobEncoding::Base64, 37))
+        );
+        assert_eq!(
+            detect("QUJDREVGR0hJSktMTU5PUFFSU1RVVldYWVo0MjQyZm9vYmFyMQ"),
+            Some((BlobEncoding::Base64, 37))
+        );
+        assert_eq!(
+            detect("QUJDREVGR0hJSktMTU5PUFFSU1RVVldYWVo0MjQyZm9vYmFyMQ="),
+            None
+        );
+        assert_eq!(
+            detect("QUJDREVGR0hJSktMTU5PUFFSU1RVVldYWVo0MjQy=Zm9vYmFyMQ=="),
+            None
+        );
+        assert_eq!(
+            detect("data:image/png;base64,AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
+            Some((BlobEncoding::Base64, 24))
+        );
+    }
+
+    #[test]
+    fn test_ignores_text_and_identifiers() {
+        let val = json!([
+            "short",
+            "ThisIsALongCamelCaseIdentifierWithoutDigits",
+            "transaction_id_for_the_batch_2024_export",
+            "deadbeefDEADBEEFdeadbeefDEADBEEFdeadbeef",
+            "a sentence with spaces in it, 1 of many sentences",
+            "A1b2+C3d4-E5f6+G7h8-I9j0+K1l2-M3n4+O5p6"
+        ]);
+        assert_eq!(blob_report(&val), BlobReport::default());
+    }
+}
@@ -0,0 +1,96 @@
+//! A model of the standard library's `BTreeMap` allocations, which back `serde_json::Map`.
+
+use crate::{AllocatorModel, TargetModel};
+
+/// Keys per node (`2 * B - 1` with `B = 6`).
+pub(crate) const CAPACITY: usize = 11;
+
+/// Average keys per node once a tree has split: nodes split into halves of 5 and 6 keys and fill
+/// up again, averaging roughly 70% of their capacity.
+pub(crate) const AVERAGE_FILL: usize = 8;
+
+/// Sizes of a leaf and an internal node holding keys of `key_size` and values of `value_size`
+/// bytes on `target`.
+///
+/// Every node holds arrays for [`CAPACITY`] keys and values plus a parent pointer and two `u16`s;
+/// internal nodes also hold one more edge pointer than keys.
+pub(crate) const fn node_sizes(
+    key_size: usize,
+    value_size: usize,
+    target: TargetModel,
+) -> (usize, usize) {
+    let word = target.pointer_size();
+    let align = target.value_align();
+    let leaf = (word + 4 + CAPACITY * (key_size + value_size)).next_multiple_of(align);
+    (leaf, leaf + (CAPACITY + 1) * word)
+}
+
+/// Number of leaf and internal nodes of a tree with `len` entries.
+///
+/// A tree of up to [`CAPACITY`] entries is a single leaf; larger trees are modeled with
+/// [`AVERAGE_FILL`] entries per node.
+pub(crate) const fn node_counts(len: usize) -> (usize, usize) {
+    match len {
+        0 => (0, 0),
+        1..=CAPACITY => (1, 0),
+        _ => {
+            let leaves = len.div_ceil(AVERAGE_FILL);
+            let mut internals = 0;
+            let mut level = leaves;
+            while level > 1 {
+                level = level.div_ceil(AVERAGE_FILL + 1);
+                internals += level;
+            }
+            (leaves, internals)
+        }
+    }
+}
+
+/// Bytes allocated for the nodes of a tree with `len` entries on `target`, with every node
+/// allocation rounded by `allocator`.
+pub(crate) const fn nodes_size(
+    len: usize,
+    key_size: usize,
+    value_size: usize,
+    allocator: AllocatorModel,
+    target: TargetModel,
+) -> usize {
+    let (leaves, internals) = node_counts(len);
+    let (leaf, internal) = node_sizes(key_size, value_size, target);
+    let word = target.pointer_size();
+    leaves
+        .saturating_mul(allocator.allocation_size(leaf, word))
+        .saturating_add(internals.saturating_mul(allocator.allocation_size(internal, word)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_sizes() {
+        assert_eq!(
+            node_sizes(24, 32, TargetModel::Bits64),
+            (16 + 11 * 56, 16 + 11 * 56 + 12 * 8)
+        );
+        // On 32-bit targets nodes are still 8-byte aligned, for the 64-bit numbers in `Value`.
+        assert_eq!(node_sizes(12, 24, TargetModel::Bits32), (408, 408 + 12 * 4));
+    }
+
+    #[test]
+    fn test_node_counts() {
+        assert_eq!(node_counts(0), (0, 0));
+        assert_eq!(node_counts(11), (1, 0));
+        // 12 entries already need two leaves and a root.
+        assert_eq!(node_counts(12), (2, 1));
+        assert_eq!(node_counts(100_000), (12_500, 1389 + 155 + 18 + 2 + 1));
+    }
+
+    #[test]
+    fn test_nodes_hold_every_entry() {
+        for len in [1, 11, 12, 100, 10_000] {
+            let (leaves, internals) = node_counts(len);
+            assert!((leaves + internals) * CAPACITY >= len);
+        }
+    }
+}
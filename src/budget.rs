@@ -0,0 +1,292 @@
+use crate::{charge, serialized_size, sizeof_val, SizeTrackedMap};
+use serde_json::Value;
+use std::fmt;
+use std::mem::size_of;
+
+/// An addition refused by a [`BudgetedBuilder`], handing the item back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejected<T> {
+    pub item: T,
+    /// Bytes the addition would have added to the result.
+    pub size: usize,
+    /// Bytes left in the budget.
+    pub remaining: usize,
+}
+
+impl<T> fmt::Display for Rejected<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "adding {} bytes would exceed the budget, {} bytes remaining",
+            self.size, self.remaining
+        )
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for Rejected<T> {}
+
+/// Builds a JSON array or object whose estimated size never exceeds a byte budget.
+///
+/// The kind of the result is chosen at construction: [`BudgetedBuilder::array`] builds an array
+/// with [`BudgetedBuilder::try_push`] and [`BudgetedBuilder::object`] an object with
+/// [`BudgetedBuilder::try_insert`]. Additions that would take [`crate::sizeof_val`] of the result
+/// over the budget are refused and handed back, and the builder stays usable, so smaller items
+/// can still be added.
+///
+/// ## Example
+/// ```
+/// use json_size::{sizeof_val, BudgetedBuilder};
+/// use serde_json::json;
+///
+/// let mut page = BudgetedBuilder::array(1024);
+/// let mut rejected = Vec::new();
+/// for i in 0..100 {
+///     if let Err(r) = page.try_push(json!(format!("item-{i}"))) {
+///         rejected.push(r.item);
+///     }
+/// }
+/// let page = page.finish();
+/// assert!(sizeof_val(&page) <= 1024);
+/// assert_eq!(page.as_array().unwrap().len() + rejected.len(), 100);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BudgetedBuilder<C> {
+    max_bytes: usize,
+    contents: C,
+    size: usize,
+}
+
+impl<C> BudgetedBuilder<C> {
+    /// Estimated size of the value built so far.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Bytes left in the budget.
+    pub fn remaining(&self) -> usize {
+        self.max_bytes.saturating_sub(self.size)
+    }
+}
+
+impl BudgetedBuilder<Vec<Value>> {
+    pub fn array(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            contents: Vec::new(),
+            size: size_of::<Value>(),
+        }
+    }
+
+    /// Appends an array element if it fits in the budget.
+    pub fn try_push(&mut self, value: Value) -> Result<(), Rejected<Value>> {
+        let remaining = self.remaining();
+        let size = sizeof_val(&value);
+        if size > remaining {
+            return Err(Rejected {
+                item: value,
+                size,
+                remaining,
+            });
+        }
+        self.contents.push(value);
+        self.size += size;
+        Ok(())
+    }
+
+    /// Returns the array built, shrunk to fit, as the budget only accounts for the elements in
+    /// use.
+    pub fn finish(mut self) -> Value {
+        self.contents.shrink_to_fit();
+        Value::Array(self.contents)
+    }
+}
+
+impl BudgetedBuilder<SizeTrackedMap> {
+    pub fn object(max_bytes: usize) -> Self {
+        let contents = SizeTrackedMap::new();
+        Self {
+            max_bytes,
+            size: contents.total_size(),
+            contents,
+        }
+    }
+
+    /// Inserts an object member if it fits in the budget. Replacing a member only needs room for
+    /// the difference between the two values.
+    pub fn try_insert(
+        &mut self,
+        key: String,
+        value: Value,
+    ) -> Result<(), Rejected<(String, Value)>> {
+        let after = self.contents.size_after_insert(&key, &value);
+        if after > self.max_bytes {
+            return Err(Rejected {
+                item: (key, value),
+                size: after.saturating_sub(self.size),
+                remaining: self.remaining(),
+            });
+        }
+        self.contents.insert(key, value);
+        self.size = self.contents.total_size();
+        Ok(())
+    }
+
+    /// Returns the object built.
+    pub fn finish(self) -> Value {
+        self.contents.into_value()
+    }
+}
+
+/// Returns how many elements of the array `v`, starting at index `start`, fit in a new array of
+/// at most `budget` bytes as estimated by [`crate::sizeof_val`].
+///
+/// `v` is not modified; elements are sized one at a time until the budget runs out. Returns 0 if
+/// `v` is not an array or `start` is past its end.
+///
+/// ## Example
+/// ```
+/// use json_size::{elements_fitting, sizeof_val};
+/// use serde_json::{json, Value};
+///
+/// let items = json!((0..1000).map(|i| format!("item-{i}")).collect::<Vec<_>>());
+/// let n = elements_fitting(&items, 4096, 100);
+/// let page = Value::Array(items.as_array().unwrap()[100..100 + n].to_vec());
+/// assert!(sizeof_val(&page) <= 4096);
+/// ```
+pub fn elements_fitting(v: &Value, budget: usize, start: usize) -> usize {
+    let Some(mut remaining) = budget.checked_sub(size_of::<Value>()) else {
+        return 0;
+    };
+    elements_from(v, start)
+        .iter()
+        .take_while(|item| charge(item, &mut remaining))
+        .count()
+}
+
+/// Same as [`elements_fitting`], with the budget applying to the compact JSON serialization of
+/// the new array, as measured by [`crate::serialized_size`].
+///
+/// ## Example
+/// ```
+/// use json_size::elements_fitting_serialized;
+/// use serde_json::json;
+///
+/// let items = json!(["a", "b", "c"]);
+/// // `["b","c"]` is 9 bytes.
+/// assert_eq!(elements_fitting_serialized(&items, 9, 1), 2);
+/// assert_eq!(elements_fitting_serialized(&items, 8, 1), 1);
+/// ```
+pub fn elements_fitting_serialized(v: &Value, budget: usize, start: usize) -> usize {
+    // The brackets, then each element and the comma before all but the first.
+    match budget.checked_sub(2) {
+        Some(remaining) => serialized_fitting(elements_from(v, start), remaining, 0, 1),
+        None => 0,
+    }
+}
+
+/// Counts the leading `items` whose compact serializations fit in `remaining` bytes, with
+/// `per_item` more bytes for each and `separator` more for each but the first.
+pub(crate) fn serialized_fitting(
+    items: &[Value],
+    mut remaining: usize,
+    per_item: usize,
+    separator: usize,
+) -> usize {
+    let mut count = 0;
+    for item in items {
+        let size = serialized_size(item) + per_item + if count > 0 { separator } else { 0 };
+        match remaining.checked_sub(size) {
+            Some(left) => remaining = left,
+            None => break,
+        }
+        count += 1;
+    }
+    count
+}
+
+pub(crate) fn elements_from(v: &Value, start: usize) -> &[Value] {
+    match v {
+        Value::Array(items) => items.get(start..).unwrap_or_default(),
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rejects_and_keeps_going() {
+        let budget = sizeof_val(&json!(["abc", 1]));
+        let mut builder = BudgetedBuilder::array(budget);
+        builder.try_push(json!("abc")).unwrap();
+        let rejected = builder
+            .try_push(json!("too long for the rest"))
+            .unwrap_err();
+        assert_eq!(rejected.item, json!("too long for the rest"));
+        assert_eq!(rejected.remaining, builder.remaining());
+        builder.try_push(json!(1)).unwrap();
+        assert_eq!(builder.remaining(), 0);
+        let built = builder.finish();
+        assert_eq!(built, json!(["abc", 1]));
+        assert_eq!(sizeof_val(&built), budget);
+    }
+
+    #[test]
+    fn test_object_budget() {
+        let budget = sizeof_val(&json!({"a": "xyz"}));
+        let mut builder = BudgetedBuilder::object(budget);
+        builder.try_insert("a".into(), json!(1)).unwrap();
+        assert!(builder
+            .try_insert("b".into(), json!("does not fit"))
+            .is_err());
+        // Replacing only needs room for the larger value.
+        builder.try_insert("a".into(), json!("xyz")).unwrap();
+        assert_eq!(builder.size(), budget);
+        assert_eq!(builder.finish(), json!({"a": "xyz"}));
+    }
+
+    #[test]
+    fn test_empty_builders() {
+        let array = BudgetedBuilder::array(0);
+        assert_eq!(array.size(), sizeof_val(&json!([])));
+        assert_eq!(array.finish(), json!([]));
+        let object = BudgetedBuilder::object(0);
+        assert_eq!(object.size(), sizeof_val(&json!({})));
+        assert_eq!(object.finish(), json!({}));
+    }
+
+    #[test]
+    fn test_elements_fitting() {
+        let items = json!(["a", 1, "long string value", null]);
+        let all = items.as_array().unwrap();
+        for start in 0..=all.len() {
+            for budget in [0, 32, 64, 100, 200, 1000] {
+                let n = elements_fitting(&items, budget, start);
+                let page = Value::Array(all[start..start + n].to_vec());
+                assert!(n == 0 || sizeof_val(&page) <= budget);
+                if start + n < all.len() {
+                    let bigger = Value::Array(all[start..start + n + 1].to_vec());
+                    assert!(sizeof_val(&bigger) > budget);
+                }
+            }
+        }
+        assert_eq!(elements_fitting(&items, 1000, 10), 0);
+        assert_eq!(elements_fitting(&json!({"a": 1}), 1000, 0), 0);
+    }
+
+    #[test]
+    fn test_elements_fitting_serialized() {
+        let items = json!([1, "two", [3]]);
+        let all = items.as_array().unwrap();
+        for budget in 0..20 {
+            let n = elements_fitting_serialized(&items, budget, 0);
+            let page = Value::Array(all[..n].to_vec());
+            assert!(n == 0 || serialized_size(&page) <= budget);
+            if n < all.len() {
+                assert!(serialized_size(&Value::Array(all[..n + 1].to_vec())) > budget);
+            }
+        }
+    }
+}
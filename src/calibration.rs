@@ -0,0 +1,100 @@
+use crate::{measure_actual, SizeEstimator};
+use serde_json::{Map, Value};
+use std::mem::size_of;
+
+const ENTRIES: usize = 4096;
+const STRING_LEN: usize = 24;
+
+/// Overhead constants measured on the current platform, allocator and `serde_json` build.
+///
+/// Calibration clones synthetic documents under [`crate::CountingAllocator`] and solves the
+/// [`SizeEstimator`] model for the constants that make its estimate match the measured heap
+/// usage. It therefore requires the `measure` feature and [`crate::CountingAllocator`] installed
+/// as the global allocator.
+///
+/// ## Example
+/// ```
+/// use json_size::{Calibration, CountingAllocator};
+/// use serde_json::json;
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static GLOBAL: CountingAllocator = CountingAllocator::new(System);
+///
+/// fn main() {
+///     let estimator = Calibration::run();
+///     println!("{} bytes", estimator.estimate(&json!({"name": "OpenAI"})));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Calibration {
+    /// Bytes per string on top of its buffer, see [`SizeEstimator::string_overhead`].
+    pub string_overhead: usize,
+    /// Bytes per object entry for the map itself, see [`SizeEstimator::map_entry_overhead`].
+    pub map_entry_overhead: usize,
+}
+
+impl Calibration {
+    /// Measures the constants and returns an estimator preloaded with them.
+    pub fn run() -> SizeEstimator {
+        Self::measure().estimator()
+    }
+
+    /// Measures the constants.
+    ///
+    /// ## Panics
+    /// Panics if [`crate::CountingAllocator`] is not installed as the global allocator.
+    pub fn measure() -> Self {
+        let value_size = size_of::<Value>() as isize;
+        let n = ENTRIES as isize;
+        let len = STRING_LEN as isize;
+
+        // An array of strings costs one `Value` per element plus the string buffers.
+        let strings = Value::Array((0..ENTRIES).map(|i| Value::String(text(i))).collect());
+        let (_, heap) = measure_actual(|| strings.clone());
+        let string_overhead = (heap as isize - n * (value_size + len)) / n;
+
+        // An object of nulls costs one entry, one key and one `Value` per member.
+        let object: Map<String, Value> = (0..ENTRIES).map(|i| (text(i), Value::Null)).collect();
+        let object = Value::Object(object);
+        let (_, heap) = measure_actual(|| object.clone());
+        let map_entry_overhead = (heap as isize - n * (string_overhead + len + value_size)) / n;
+
+        Self {
+            string_overhead: string_overhead.max(0) as usize,
+            map_entry_overhead: map_entry_overhead.max(0) as usize,
+        }
+    }
+
+    /// Returns an estimator using these constants.
+    pub fn estimator(&self) -> SizeEstimator {
+        SizeEstimator::new()
+            .string_overhead(self.string_overhead)
+            .map_entry_overhead(self.map_entry_overhead)
+    }
+}
+
+/// A distinct string of exactly `STRING_LEN` bytes.
+fn text(i: usize) -> String {
+    format!("{i:0width$}", width = STRING_LEN)
+}
+
+#[cfg(test)]
+mod tests {
+    // Relies on the `CountingAllocator` installed in `measure::tests`.
+    use super::*;
+
+    #[test]
+    fn test_calibrated_estimate_matches_measurement() {
+        let estimator = Calibration::run();
+        let template = Value::Array((0..100).map(|i| Value::String(text(i))).collect());
+        let (val, heap) = measure_actual(|| template.clone());
+        assert_eq!(estimator.estimate(&val), heap + size_of::<Value>());
+    }
+
+    #[test]
+    fn test_values_in_arrays_need_no_string_overhead() {
+        // `String` lives inline in `Value`, so only the buffer is extra.
+        assert_eq!(Calibration::measure().string_overhead, 0);
+    }
+}
@@ -0,0 +1,283 @@
+//! Deserialization of `serde_json::Value`s with a cap on their estimated in-memory size.
+//!
+//! The size is tracked while the value is being built, so oversized input is rejected as soon as
+//! it crosses the cap, before the whole document has been materialized. Arrays in the result are
+//! shrunk to fit, so they carry no spare capacity.
+//!
+//! ## Example
+//! ```
+//! use json_size::capped::{self, Capped};
+//! use serde::Deserialize;
+//! use serde_json::Value;
+//!
+//! #[derive(Deserialize)]
+//! struct Event {
+//!     kind: String,
+//!     extra: Capped<1024>,
+//!     #[serde(deserialize_with = "capped::deserialize::<_, 4096>")]
+//!     context: Value,
+//! }
+//!
+//! let ok = r#"{"kind": "click", "extra": {"x": 1}, "context": null}"#;
+//! assert!(serde_json::from_str::<Event>(ok).is_ok());
+//!
+//! let huge = format!(r#"{{"kind": "click", "extra": "{}", "context": null}}"#, "x".repeat(2000));
+//! assert!(serde_json::from_str::<Event>(&huge).is_err());
+//! ```
+
+use crate::{entry_overhead, map_overhead, own_size, sizeof_val};
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Number, Value};
+use std::cell::Cell;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// A `Value` whose estimated size, as computed by [`crate::sizeof_val`], is at most `N` bytes.
+///
+/// Serializes exactly like the inner `Value`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Capped<const N: usize>(pub Value);
+
+impl<const N: usize> Capped<N> {
+    pub fn into_inner(self) -> Value {
+        self.0
+    }
+}
+
+impl<const N: usize> Deref for Capped<N> {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        &self.0
+    }
+}
+
+impl<const N: usize> DerefMut for Capped<N> {
+    fn deref_mut(&mut self) -> &mut Value {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> Serialize for Capped<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Capped<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize::<D, N>(deserializer).map(Capped)
+    }
+}
+
+/// Deserializes a `Value` of at most `N` estimated bytes, for use with
+/// `#[serde(deserialize_with = "json_size::capped::deserialize::<_, N>")]`.
+pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<Value, D::Error> {
+    CappedSeed::new(N).deserialize(deserializer)
+}
+
+/// A `DeserializeSeed` producing a `Value` of at most `cap` estimated bytes, for caps only known
+/// at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CappedSeed {
+    cap: usize,
+}
+
+impl CappedSeed {
+    pub fn new(cap: usize) -> Self {
+        Self { cap }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for CappedSeed {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserialize_sized(deserializer, self.cap).map(|(v, _)| v)
+    }
+}
+
+/// Deserializes a `Value` of at most `cap` estimated bytes, along with its estimated size.
+pub(crate) fn deserialize_sized<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    cap: usize,
+) -> Result<(Value, usize), D::Error> {
+    let used = Cell::new(0);
+    let v = deserializer.deserialize_any(CappedVisitor { cap, used: &used })?;
+    Ok((v, used.get()))
+}
+
+/// Builds a `Value`, charging every node against a budget shared by the whole document.
+#[derive(Clone, Copy)]
+struct CappedVisitor<'a> {
+    cap: usize,
+    used: &'a Cell<usize>,
+}
+
+impl CappedVisitor<'_> {
+    fn charge<E: de::Error>(&self, bytes: usize) -> Result<(), E> {
+        let used = self.used.get().saturating_add(bytes);
+        if used > self.cap {
+            return Err(E::custom(format_args!(
+                "JSON value exceeds the size cap of {} bytes",
+                self.cap
+            )));
+        }
+        self.used.set(used);
+        Ok(())
+    }
+
+    /// Updates the charge for the map bookkeeping of an object growing from `from` to `to`
+    /// members. The bookkeeping charged to an object can shrink as its nodes fill up.
+    fn recharge_map<E: de::Error>(&self, from: usize, to: usize) -> Result<(), E> {
+        let (before, after) = (map_overhead(from), map_overhead(to));
+        if after >= before {
+            self.charge(after - before)
+        } else {
+            self.refund(before - after);
+            Ok(())
+        }
+    }
+
+    fn refund(&self, bytes: usize) {
+        self.used.set(self.used.get() - bytes);
+    }
+
+    fn leaf<E: de::Error>(&self, v: Value) -> Result<Value, E> {
+        self.charge(own_size(&v))?;
+        Ok(v)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for CappedVisitor<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CappedVisitor<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        self.leaf(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        self.leaf(Value::Number(v.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        self.leaf(Value::Number(v.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        self.leaf(Number::from_f64(v).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        self.leaf(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        self.leaf(Value::String(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        self.leaf(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        self.leaf(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        self.charge(own_size(&Value::Null))?;
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element_seed(self)? {
+            items.push(item);
+        }
+        // Only the slots in use were charged, so drop the spare capacity.
+        items.shrink_to_fit();
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        self.charge(own_size(&Value::Null))?;
+        let mut members = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let len = members.len();
+            // Settle the map bookkeeping first, so a refund is never charged after the fact.
+            self.recharge_map(len, len + 1)?;
+            let entry = entry_overhead(&key);
+            self.charge(entry)?;
+            let value = map.next_value_seed(self)?;
+            if let Some(replaced) = members.insert(key, value) {
+                // A repeated key keeps a single entry and the last value.
+                self.refund(entry + sizeof_val(&replaced));
+                self.recharge_map(len + 1, len)?;
+            }
+        }
+        Ok(Value::Object(members))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(text: &str, cap: usize) -> Result<Value, serde_json::Error> {
+        CappedSeed::new(cap).deserialize(&mut serde_json::Deserializer::from_str(text))
+    }
+
+    #[test]
+    fn test_accepts_value_at_cap() {
+        let val = json!({"a": [1, "two", null], "b": {"c": true, "d": 1.5}});
+        let text = val.to_string();
+        let size = sizeof_val(&val);
+        assert_eq!(parse(&text, size).unwrap(), val);
+        assert!(parse(&text, size - 1).is_err());
+    }
+
+    #[test]
+    fn test_repeated_keys_are_charged_once() {
+        let text = r#"{"a": "a long string value", "a": 1, "b": [2]}"#;
+        let (val, size) =
+            deserialize_sized(&mut serde_json::Deserializer::from_str(text), usize::MAX).unwrap();
+        assert_eq!(val, json!({"a": 1, "b": [2]}));
+        assert_eq!(size, sizeof_val(&val));
+    }
+
+    #[test]
+    fn test_error_names_the_cap() {
+        let err = parse(r#"["aaaaaaaaaaaaaaaa"]"#, 64).unwrap_err();
+        assert!(err.to_string().contains("size cap of 64 bytes"), "{err}");
+    }
+
+    #[test]
+    fn test_capped_field() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            extra: Capped<256>,
+        }
+        let small: Wrapper = serde_json::from_str(r#"{"extra": [1, 2]}"#).unwrap();
+        assert_eq!(*small.extra, json!([1, 2]));
+        assert!(
+            serde_json::from_str::<Wrapper>(&format!(r#"{{"extra": "{}"}}"#, "x".repeat(300)))
+                .is_err()
+        );
+    }
+}
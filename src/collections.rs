@@ -0,0 +1,159 @@
+use crate::{btree, sizeof_val, AllocatorModel, SizeEstimator, TargetModel};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::BuildHasher;
+use std::mem::size_of;
+
+/// Estimated in-memory size of a container of `serde_json::Value`s.
+///
+/// Like [`crate::sizeof_val`], the size includes the container itself (`size_of::<Self>()`),
+/// its own allocations as laid out by the standard library, and every key and value it holds.
+///
+/// ## Example
+/// ```
+/// use json_size::{sizeof_val, JsonSize};
+/// use serde_json::{json, Value};
+/// use std::collections::HashMap;
+///
+/// let mut sessions: HashMap<String, Value> = HashMap::new();
+/// sessions.insert("s-1".into(), json!({"user": "ada"}));
+/// assert!(sessions.json_size() > sizeof_val(&sessions["s-1"]));
+/// ```
+pub trait JsonSize {
+    fn json_size(&self) -> usize;
+}
+
+impl JsonSize for Value {
+    fn json_size(&self) -> usize {
+        sizeof_val(self)
+    }
+}
+
+/// A `Vec` buffer of `capacity` slots, plus the heap owned by the elements.
+impl JsonSize for Vec<Value> {
+    fn json_size(&self) -> usize {
+        size_of::<Self>()
+            + vec_buffer::<Value>(self.capacity())
+            + self.iter().map(value_heap).sum::<usize>()
+    }
+}
+
+/// A ring buffer of `capacity` slots, plus the heap owned by the elements.
+impl JsonSize for VecDeque<Value> {
+    fn json_size(&self) -> usize {
+        size_of::<Self>()
+            + vec_buffer::<Value>(self.capacity())
+            + self.iter().map(value_heap).sum::<usize>()
+    }
+}
+
+/// A `Vec` buffer of `capacity` slots, plus the key buffers and the heap owned by the values.
+impl JsonSize for Vec<(String, Value)> {
+    fn json_size(&self) -> usize {
+        size_of::<Self>()
+            + vec_buffer::<(String, Value)>(self.capacity())
+            + self.iter().map(|(k, v)| entry_heap(k, v)).sum::<usize>()
+    }
+}
+
+/// A SwissTable: one slot and one control byte per bucket plus a trailing control group, with
+/// the bucket count derived from `capacity()` the way `hashbrown` does.
+impl<S: BuildHasher> JsonSize for HashMap<String, Value, S> {
+    fn json_size(&self) -> usize {
+        size_of::<Self>()
+            + hash_table_size(self.capacity(), size_of::<(String, Value)>())
+            + self.iter().map(|(k, v)| entry_heap(k, v)).sum::<usize>()
+    }
+}
+
+/// B-tree leaf and internal nodes as laid out by the standard library.
+impl JsonSize for BTreeMap<String, Value> {
+    fn json_size(&self) -> usize {
+        size_of::<Self>()
+            + btree::nodes_size(
+                self.len(),
+                size_of::<String>(),
+                size_of::<Value>(),
+                AllocatorModel::Exact,
+                TargetModel::Host,
+            )
+            + self.iter().map(|(k, v)| entry_heap(k, v)).sum::<usize>()
+    }
+}
+
+/// Heap memory owned by `v`, i.e. its size without the `Value` itself.
+fn value_heap(v: &Value) -> usize {
+    sizeof_val(v) - size_of::<Value>()
+}
+
+fn entry_heap(k: &String, v: &Value) -> usize {
+    SizeEstimator::DEFAULT.buffer_size(k) + value_heap(v)
+}
+
+fn vec_buffer<T>(capacity: usize) -> usize {
+    capacity * size_of::<T>()
+}
+
+/// Control bytes trailing a SwissTable, one SIMD group.
+const GROUP_WIDTH: usize = 16;
+
+/// Bytes allocated by a SwissTable with room for `capacity` entries of `slot_size` bytes.
+pub(crate) fn hash_table_size(capacity: usize, slot_size: usize) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+    // hashbrown keeps at least 4 buckets and fills at most 7/8 of tables with 8 or more.
+    let buckets = if capacity < 8 {
+        (capacity + 1).next_power_of_two()
+    } else {
+        (capacity * 8 / 7).next_power_of_two()
+    };
+    buckets * (slot_size + 1) + GROUP_WIDTH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_vec_counts_spare_capacity() {
+        let mut values: Vec<Value> = Vec::with_capacity(10);
+        values.push(json!("abc"));
+        let expected = size_of::<Vec<Value>>() + 10 * size_of::<Value>() + value_heap(&values[0]);
+        assert_eq!(values.json_size(), expected);
+        let deque: VecDeque<Value> = values.into();
+        assert_eq!(
+            deque.json_size() - size_of::<VecDeque<Value>>(),
+            expected - size_of::<Vec<Value>>()
+        );
+    }
+
+    #[test]
+    fn test_pairs_count_keys() {
+        let pairs = vec![("key".to_string(), json!(1))];
+        let expected = size_of::<Vec<(String, Value)>>() + size_of::<(String, Value)>() + 3;
+        assert_eq!(pairs.json_size(), expected);
+    }
+
+    #[test]
+    fn test_hash_map_buckets() {
+        assert_eq!(hash_table_size(0, 1), 0);
+        assert_eq!(hash_table_size(3, 1), 4 * 2 + GROUP_WIDTH);
+        assert_eq!(hash_table_size(14, 1), 16 * 2 + GROUP_WIDTH);
+        let map: HashMap<String, Value> = (0..100).map(|i| (i.to_string(), json!(i))).collect();
+        let lower = size_of::<HashMap<String, Value>>() + 100 * size_of::<(String, Value)>();
+        assert!(map.json_size() > lower);
+    }
+
+    #[test]
+    fn test_btree_map() {
+        let map: BTreeMap<String, Value> = [("a".to_string(), json!(null))].into();
+        let (leaf, _) =
+            btree::node_sizes(size_of::<String>(), size_of::<Value>(), TargetModel::Host);
+        assert_eq!(
+            map.json_size(),
+            size_of::<BTreeMap<String, Value>>() + leaf + 1
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! Apache Arrow footprint estimates, enabled with the `columnar` feature.
+
+use crate::{serialized_size, sizeof_val};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Arrow buffers are allocated in multiples of 64 bytes.
+const BUFFER_ALIGNMENT: usize = 64;
+
+/// The Arrow data type a column is inferred to have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum ColumnType {
+    /// Only nulls; Arrow stores no buffers at all.
+    Null,
+    Boolean,
+    Int64,
+    /// Integers that do not all fit an `i64`.
+    UInt64,
+    /// Floats, or integers mixed with floats.
+    Float64,
+    /// Strings. Columns with nested or mixed values also end up here, holding the JSON text of
+    /// each non-string value.
+    Utf8,
+    /// Same as `Utf8`, with 64-bit offsets because the data exceeds 2 GiB.
+    LargeUtf8,
+}
+
+/// The estimated Arrow buffers of one column.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ColumnEstimate {
+    pub name: String,
+    pub data_type: ColumnType,
+    /// Rows where the key is missing or `null`.
+    pub null_count: usize,
+    /// The validity bitmap, only allocated if the column has nulls.
+    pub validity_bytes: usize,
+    /// The offsets buffer of string columns.
+    pub offsets_bytes: usize,
+    /// The values buffer: bits for booleans, fixed-width values or the string data.
+    pub values_bytes: usize,
+}
+
+impl ColumnEstimate {
+    pub fn total_bytes(&self) -> usize {
+        self.validity_bytes + self.offsets_bytes + self.values_bytes
+    }
+}
+
+/// The estimated footprint of an array of objects as Arrow arrays, created by
+/// [`estimate_columnar`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ColumnarEstimate {
+    pub rows: usize,
+    /// One column per distinct top-level key, in order of first appearance.
+    pub columns: Vec<ColumnEstimate>,
+    /// Sum of all column buffers.
+    pub total_bytes: usize,
+    /// Estimated size of the same data as a `serde_json::Value`, for comparison.
+    pub value_size: usize,
+}
+
+impl ColumnarEstimate {
+    /// How many times smaller the Arrow representation is than the `Value`.
+    pub fn compression_ratio(&self) -> f64 {
+        self.value_size as f64 / self.total_bytes.max(1) as f64
+    }
+}
+
+/// Predicts the memory needed to hold `v`, an array of flat-ish objects, as Arrow arrays with one
+/// column per top-level key.
+///
+/// Column types are inferred from the values, see [`ColumnType`]; nested values are costed as
+/// their JSON text in a string column. Array elements that are not objects count as rows with
+/// every column null. Any other input yields an estimate with no rows.
+///
+/// ## Example
+/// ```
+/// use json_size::{estimate_columnar, ColumnType};
+/// use serde_json::json;
+///
+/// let batch = json!([
+///     {"id": 1, "name": "ada", "score": 9.5},
+///     {"id": 2, "name": "grace"},
+/// ]);
+/// let estimate = estimate_columnar(&batch);
+/// assert_eq!(estimate.rows, 2);
+/// assert_eq!(estimate.columns[0].data_type, ColumnType::Int64);
+/// assert_eq!(estimate.columns[2].null_count, 1);
+/// println!("{:.1}x smaller as Arrow", estimate.compression_ratio());
+/// ```
+pub fn estimate_columnar(v: &Value) -> ColumnarEstimate {
+    let rows: &[Value] = match v {
+        Value::Array(a) => a,
+        _ => &[],
+    };
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut columns: Vec<(&str, ColumnStats)> = Vec::new();
+    for row in rows {
+        let Value::Object(o) = row else { continue };
+        for (k, value) in o {
+            let i = *index.entry(k.as_str()).or_insert_with(|| {
+                columns.push((k.as_str(), ColumnStats::default()));
+                columns.len() - 1
+            });
+            columns[i].1.add(value);
+        }
+    }
+    let columns: Vec<ColumnEstimate> = columns
+        .into_iter()
+        .map(|(name, stats)| stats.estimate(name, rows.len()))
+        .collect();
+    ColumnarEstimate {
+        rows: rows.len(),
+        total_bytes: columns.iter().map(ColumnEstimate::total_bytes).sum(),
+        columns,
+        value_size: sizeof_val(v),
+    }
+}
+
+#[derive(Debug, Default)]
+struct ColumnStats {
+    present: usize,
+    bools: usize,
+    ints: usize,
+    big_uints: usize,
+    negative: bool,
+    floats: usize,
+    strings: usize,
+    /// Bytes of the column as text: raw strings, JSON for everything else.
+    text_bytes: usize,
+}
+
+impl ColumnStats {
+    fn add(&mut self, v: &Value) {
+        match v {
+            Value::Null => return,
+            Value::Bool(_) => self.bools += 1,
+            Value::Number(n) if n.is_i64() => {
+                self.ints += 1;
+                self.negative |= n.as_i64().is_some_and(|i| i < 0);
+            }
+            Value::Number(n) if n.is_u64() => self.big_uints += 1,
+            Value::Number(_) => self.floats += 1,
+            Value::String(s) => {
+                self.strings += 1;
+                self.text_bytes += s.len();
+                self.present += 1;
+                return;
+            }
+            _ => {}
+        }
+        self.present += 1;
+        self.text_bytes += serialized_size(v);
+    }
+
+    fn data_type(&self) -> ColumnType {
+        let numbers = self.ints + self.big_uints + self.floats;
+        if self.present == 0 {
+            ColumnType::Null
+        } else if self.bools == self.present {
+            ColumnType::Boolean
+        } else if numbers == self.present {
+            if self.floats > 0 || (self.big_uints > 0 && self.negative) {
+                ColumnType::Float64
+            } else if self.big_uints > 0 {
+                ColumnType::UInt64
+            } else {
+                ColumnType::Int64
+            }
+        } else if self.text_bytes > i32::MAX as usize {
+            ColumnType::LargeUtf8
+        } else {
+            ColumnType::Utf8
+        }
+    }
+
+    fn estimate(&self, name: &str, rows: usize) -> ColumnEstimate {
+        let data_type = self.data_type();
+        let null_count = rows - self.present;
+        let bitmap = padded(rows.div_ceil(8));
+        let (offsets_bytes, values_bytes) = match data_type {
+            ColumnType::Null => (0, 0),
+            ColumnType::Boolean => (0, bitmap),
+            ColumnType::Int64 | ColumnType::UInt64 | ColumnType::Float64 => (0, padded(rows * 8)),
+            ColumnType::Utf8 => (padded((rows + 1) * 4), padded(self.text_bytes)),
+            ColumnType::LargeUtf8 => (padded((rows + 1) * 8), padded(self.text_bytes)),
+        };
+        ColumnEstimate {
+            name: name.to_owned(),
+            data_type,
+            null_count,
+            validity_bytes: if null_count > 0 && data_type != ColumnType::Null {
+                bitmap
+            } else {
+                0
+            },
+            offsets_bytes,
+            values_bytes,
+        }
+    }
+}
+
+fn padded(bytes: usize) -> usize {
+    bytes.div_ceil(BUFFER_ALIGNMENT) * BUFFER_ALIGNMENT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column<'a>(e: &'a ColumnarEstimate, name: &str) -> &'a ColumnEstimate {
+        e.columns.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_type_inference() {
+        let batch = json!([
+            {"i": 1, "u": 1, "f": 1, "b": true, "s": "x", "m": 1, "n": null, "o": {"k": 1}},
+            {"i": -2, "u": u64::MAX, "f": 2.5, "b": false, "s": "y", "m": "z", "n": null, "o": [1]},
+        ]);
+        let e = estimate_columnar(&batch);
+        assert_eq!(column(&e, "i").data_type, ColumnType::Int64);
+        assert_eq!(column(&e, "u").data_type, ColumnType::UInt64);
+        assert_eq!(column(&e, "f").data_type, ColumnType::Float64);
+        assert_eq!(column(&e, "b").data_type, ColumnType::Boolean);
+        assert_eq!(column(&e, "s").data_type, ColumnType::Utf8);
+        assert_eq!(column(&e, "m").data_type, ColumnType::Utf8);
+        assert_eq!(column(&e, "n").data_type, ColumnType::Null);
+        assert_eq!(column(&e, "o").data_type, ColumnType::Utf8);
+    }
+
+    #[test]
+    fn test_buffer_sizes() {
+        let rows: Vec<Value> = (0..100)
+            .map(|i| {
+                if i % 2 == 0 {
+                    json!({"id": i, "name": "abcd"})
+                } else {
+                    json!({"id": i})
+                }
+            })
+            .collect();
+        let e = estimate_columnar(&Value::Array(rows));
+        let id = column(&e, "id");
+        assert_eq!(
+            (id.validity_bytes, id.offsets_bytes, id.values_bytes),
+            (0, 0, 832)
+        );
+        let name = column(&e, "name");
+        assert_eq!(name.null_count, 50);
+        assert_eq!(name.validity_bytes, 64);
+        assert_eq!(name.offsets_bytes, padded(101 * 4));
+        assert_eq!(name.values_bytes, padded(50 * 4));
+        assert_eq!(e.total_bytes, id.total_bytes() + name.total_bytes());
+        assert!(e.compression_ratio() > 1.0);
+    }
+
+    #[test]
+    fn test_non_array_input() {
+        let e = estimate_columnar(&json!({"id": 1}));
+        assert_eq!(e.rows, 0);
+        assert!(e.columns.is_empty());
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use crate::serialized::CountingWriter;
+use serde_json::Value;
+use std::io;
+
+/// Compression used by [`compressed_size_estimate`].
+///
+/// Non-exhaustive, as the codecs available depend on the crate's features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Codec {
+    /// A heuristic needing no compression library: the order-0 entropy of the serialized bytes,
+    /// i.e. the output of an ideal byte-wise entropy coder. Real codecs also exploit repeated
+    /// substrings, so this overestimates documents with many repeated keys and values.
+    Entropy,
+    /// gzip via `flate2`, with levels 0 to 9. Requires the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard, with levels 1 to 22 (0 selects the library default). Requires the `zstd`
+    /// feature.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+/// Estimates the stored size of the compact JSON serialization of `v` under `codec`.
+///
+/// With a real codec the serialization is streamed through the compressor into a counting sink,
+/// so the result is exact and neither the JSON text nor the compressed output is buffered.
+/// `level` is ignored by [`Codec::Entropy`].
+///
+/// ## Example
+/// ```
+/// use json_size::{compressed_size_estimate, serialized_size, Codec};
+/// use serde_json::json;
+///
+/// let val = json!({"status": "ok", "items": vec!["repeated"; 100]});
+/// let stored = compressed_size_estimate(&val, Codec::Entropy, 0);
+/// assert!(stored < serialized_size(&val));
+/// ```
+pub fn compressed_size_estimate(
+    v: &Value,
+    codec: Codec,
+    // Only read by the real codecs.
+    #[cfg_attr(not(any(feature = "gzip", feature = "zstd")), allow(unused_variables))] level: u32,
+) -> usize {
+    match codec {
+        Codec::Entropy => entropy_size(v),
+        #[cfg(feature = "gzip")]
+        Codec::Gzip => {
+            let level = flate2::Compression::new(level.min(9));
+            let mut encoder = flate2::write::GzEncoder::new(CountingWriter::default(), level);
+            serialize(v, &mut encoder);
+            encoder.finish().expect(SINK_NEVER_FAILS).count
+        }
+        #[cfg(feature = "zstd")]
+        Codec::Zstd => {
+            let level = level.min(22) as i32;
+            let mut encoder = zstd::stream::write::Encoder::new(CountingWriter::default(), level)
+                .expect(SINK_NEVER_FAILS);
+            serialize(v, &mut encoder);
+            encoder.finish().expect(SINK_NEVER_FAILS).count
+        }
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+const SINK_NEVER_FAILS: &str = "compressing into a counting sink cannot fail";
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+fn serialize(v: &Value, w: &mut impl io::Write) {
+    serde_json::to_writer(w, v).expect(SINK_NEVER_FAILS);
+}
+
+/// Counts how often each byte value occurs in what is written to it.
+struct ByteHistogram([usize; 256]);
+
+impl io::Write for ByteHistogram {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &b in buf {
+            self.0[b as usize] += 1;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn entropy_size(v: &Value) -> usize {
+    let mut histogram = ByteHistogram([0; 256]);
+    serde_json::to_writer(&mut histogram, v).expect("serializing into a histogram cannot fail");
+    let total: usize = histogram.0.iter().sum();
+    let bits: f64 = histogram
+        .0
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / total as f64;
+            -(n as f64) * p.log2()
+        })
+        .sum();
+    (bits / 8.0).ceil() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialized_size;
+    use serde_json::json;
+
+    #[test]
+    fn test_entropy_of_uniform_bytes() {
+        // `"aaaa"` has one distinct byte besides the two quotes.
+        let val = json!("a".repeat(1000));
+        assert!(compressed_size_estimate(&val, Codec::Entropy, 0) < 20);
+    }
+
+    #[test]
+    fn test_entropy_never_exceeds_serialized_size() {
+        let val = json!({"k": (0..200).collect::<Vec<_>>(), "s": "mixed Content 123 !?"});
+        assert!(compressed_size_estimate(&val, Codec::Entropy, 0) <= serialized_size(&val));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_matches_real_compression() {
+        use std::io::Write;
+        let val = json!({"items": vec!["repeated"; 100]});
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(6));
+        encoder.write_all(val.to_string().as_bytes()).unwrap();
+        let expected = encoder.finish().unwrap().len();
+        assert_eq!(compressed_size_estimate(&val, Codec::Gzip, 6), expected);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_matches_real_compression() {
+        let val = json!({"items": vec!["repeated"; 100]});
+        let expected = zstd::encode_all(val.to_string().as_bytes(), 3)
+            .unwrap()
+            .len();
+        assert_eq!(compressed_size_estimate(&val, Codec::Zstd, 3), expected);
+    }
+}
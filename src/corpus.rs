@@ -0,0 +1,307 @@
+use crate::{entry_overhead, own_size, walk_sizes, PathSegment, ValueKind};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+const KINDS: [ValueKind; 6] = [
+    ValueKind::Null,
+    ValueKind::Bool,
+    ValueKind::Number,
+    ValueKind::String,
+    ValueKind::Array,
+    ValueKind::Object,
+];
+
+/// Aggregate size statistics over many documents.
+///
+/// ## Example
+/// ```
+/// use json_size::Corpus;
+/// use serde_json::json;
+///
+/// let mut corpus = Corpus::new();
+/// for i in 0..100 {
+///     corpus.add(&json!({"id": i, "name": format!("user-{i}")}));
+/// }
+/// let stats = corpus.stats(10);
+/// println!("10M documents: {:.0} bytes", stats.projected_total(10_000_000));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Corpus {
+    sizes: Vec<usize>,
+    type_bytes: [usize; KINDS.len()],
+    keys: HashMap<String, KeyStat>,
+}
+
+/// Total bytes and occurrences of one object key across a [`Corpus`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct KeyStat {
+    pub key: String,
+    /// Sum of the subtree sizes of all members with this key, including the key itself.
+    pub bytes: usize,
+    pub occurrences: usize,
+}
+
+/// Bytes held by nodes of one JSON type across a [`Corpus`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TypeShare {
+    #[serde(rename = "type")]
+    pub kind: ValueKind,
+    /// Own size of the nodes, see [`crate::SizeTree`].
+    pub bytes: usize,
+    /// Fraction of the corpus total, between 0 and 1.
+    pub share: f64,
+}
+
+/// Summary statistics of a [`Corpus`], created by [`Corpus::stats`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CorpusStats {
+    pub documents: usize,
+    pub total_size: usize,
+    pub mean: f64,
+    /// Population variance of the document sizes.
+    pub variance: f64,
+    pub std_dev: f64,
+    pub min: usize,
+    pub max: usize,
+    pub p50: usize,
+    pub p90: usize,
+    pub p99: usize,
+    /// Bytes per JSON type, largest first. Types absent from the corpus are omitted.
+    pub type_shares: Vec<TypeShare>,
+    /// The keys holding the most bytes, heaviest first.
+    pub heaviest_keys: Vec<KeyStat>,
+}
+
+impl CorpusStats {
+    /// Expected total size of `documents` documents drawn from the same distribution.
+    pub fn projected_total(&self, documents: u64) -> f64 {
+        self.mean * documents as f64
+    }
+}
+
+impl Corpus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds one document.
+    pub fn add(&mut self, v: &Value) {
+        let walked = walk_sizes(v, |path, node, size| {
+            let mut own = own_size(node);
+            if let Some(PathSegment::Key(k)) = path.segments().last() {
+                own += entry_overhead(k);
+                let stat = self.keys.entry(k.clone()).or_insert_with(|| KeyStat {
+                    key: k.clone(),
+                    ..KeyStat::default()
+                });
+                stat.bytes = stat.bytes.saturating_add(size);
+                stat.occurrences += 1;
+            }
+            let kind = ValueKind::of(node);
+            let slot = KINDS
+                .iter()
+                .position(|&k| k == kind)
+                .expect("all kinds listed");
+            self.type_bytes[slot] = self.type_bytes[slot].saturating_add(own);
+            ControlFlow::Continue(())
+        });
+        let ControlFlow::Continue(size) = walked else {
+            unreachable!("the callback never breaks")
+        };
+        self.sizes.push(size);
+    }
+
+    /// Adds every document read from `reader`. Documents may be separated by whitespace or
+    /// newlines, so this handles both single JSON files and JSON Lines. Returns the number of
+    /// documents added.
+    pub fn add_reader<R: Read>(&mut self, reader: R) -> io::Result<usize> {
+        let mut added = 0;
+        for v in serde_json::Deserializer::from_reader(BufReader::new(reader)).into_iter::<Value>()
+        {
+            self.add(&v?);
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    /// Adds every document in the file at `path`, see [`Corpus::add_reader`].
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> io::Result<usize> {
+        self.add_reader(File::open(path)?)
+    }
+
+    /// Adds every document of `other`, e.g. a corpus built on another thread.
+    pub fn merge(&mut self, other: Corpus) {
+        self.sizes.extend(other.sizes);
+        for (total, bytes) in self.type_bytes.iter_mut().zip(other.type_bytes) {
+            *total = total.saturating_add(bytes);
+        }
+        for (key, stat) in other.keys {
+            let total = self.keys.entry(key).or_insert_with_key(|key| KeyStat {
+                key: key.clone(),
+                ..KeyStat::default()
+            });
+            total.bytes = total.bytes.saturating_add(stat.bytes);
+            total.occurrences += stat.occurrences;
+        }
+    }
+
+    /// Number of documents added.
+    pub fn len(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sizes.is_empty()
+    }
+
+    /// Summarizes the corpus, listing the `top_keys` heaviest keys.
+    pub fn stats(&self, top_keys: usize) -> CorpusStats {
+        let mut sorted = self.sizes.clone();
+        sorted.sort_unstable();
+        let documents = sorted.len();
+        let total_size = sorted.iter().fold(0usize, |acc, &s| acc.saturating_add(s));
+        let mean = if documents == 0 {
+            0.0
+        } else {
+            sorted.iter().map(|&s| s as f64).sum::<f64>() / documents as f64
+        };
+        let variance = if documents == 0 {
+            0.0
+        } else {
+            sorted
+                .iter()
+                .map(|&s| (s as f64 - mean).powi(2))
+                .sum::<f64>()
+                / documents as f64
+        };
+
+        let all_bytes: usize = self.type_bytes.iter().sum();
+        let mut type_shares: Vec<TypeShare> = KINDS
+            .iter()
+            .zip(self.type_bytes)
+            .filter(|&(_, bytes)| bytes > 0)
+            .map(|(&kind, bytes)| TypeShare {
+                kind,
+                bytes,
+                share: bytes as f64 / all_bytes as f64,
+            })
+            .collect();
+        type_shares.sort_by_key(|t| Reverse(t.bytes));
+
+        let mut heaviest_keys: Vec<KeyStat> = self.keys.values().cloned().collect();
+        heaviest_keys.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.key.cmp(&b.key)));
+        heaviest_keys.truncate(top_keys);
+
+        CorpusStats {
+            documents,
+            total_size,
+            mean,
+            variance,
+            std_dev: variance.sqrt(),
+            min: sorted.first().copied().unwrap_or(0),
+            max: sorted.last().copied().unwrap_or(0),
+            p50: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+            type_shares,
+            heaviest_keys,
+        }
+    }
+
+    /// The document size below which `p` percent of the documents fall (nearest rank).
+    pub fn percentile(&self, p: f64) -> usize {
+        let mut sorted = self.sizes.clone();
+        sorted.sort_unstable();
+        percentile(&sorted, p)
+    }
+}
+
+fn percentile(sorted: &[usize], p: f64) -> usize {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sizeof_val;
+    use serde_json::json;
+
+    #[test]
+    fn test_moments_and_percentiles() {
+        let mut corpus = Corpus::new();
+        let docs: Vec<Value> = (1..=100).map(|i| json!("x".repeat(i))).collect();
+        docs.iter().for_each(|d| corpus.add(d));
+        let stats = corpus.stats(0);
+        let sizes: Vec<usize> = docs.iter().map(sizeof_val).collect();
+        assert_eq!(stats.documents, 100);
+        assert_eq!(stats.total_size, sizes.iter().sum::<usize>());
+        assert_eq!(stats.min, sizes[0]);
+        assert_eq!(stats.p50, sizes[49]);
+        assert_eq!(stats.p99, sizes[98]);
+        // Sizes grow by one byte per document: the variance of 1..=100 is (100^2 - 1) / 12.
+        assert!((stats.variance - 833.25).abs() < 1e-9);
+        assert_eq!(stats.projected_total(10), stats.mean * 10.0);
+    }
+
+    #[test]
+    fn test_type_shares_add_up() {
+        let mut corpus = Corpus::new();
+        corpus.add(&json!({"a": [1, "two", null], "b": true}));
+        let stats = corpus.stats(0);
+        let bytes: usize = stats.type_shares.iter().map(|t| t.bytes).sum();
+        assert_eq!(bytes, stats.total_size);
+        assert!((stats.type_shares.iter().map(|t| t.share).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_heaviest_keys_across_documents() {
+        let mut corpus = Corpus::new();
+        corpus.add(&json!({"id": 1, "blob": "x".repeat(100)}));
+        corpus.add(&json!({"id": 2, "blob": "y".repeat(100)}));
+        let stats = corpus.stats(1);
+        assert_eq!(stats.heaviest_keys.len(), 1);
+        assert_eq!(stats.heaviest_keys[0].key, "blob");
+        assert_eq!(stats.heaviest_keys[0].occurrences, 2);
+    }
+
+    #[test]
+    fn test_add_reader_json_lines() {
+        let mut corpus = Corpus::new();
+        let added = corpus
+            .add_reader("{\"a\": 1}\n{\"a\": 2}\n[3]\n".as_bytes())
+            .unwrap();
+        assert_eq!(added, 3);
+        assert_eq!(corpus.len(), 3);
+    }
+
+    #[test]
+    fn test_merge_matches_single_corpus() {
+        let docs = [json!({"a": 1}), json!({"a": "two", "b": [3]}), json!(null)];
+        let mut whole = Corpus::new();
+        docs.iter().for_each(|d| whole.add(d));
+        let mut left = Corpus::new();
+        left.add(&docs[0]);
+        let mut right = Corpus::new();
+        docs[1..].iter().for_each(|d| right.add(d));
+        left.merge(right);
+        assert_eq!(left.stats(10), whole.stats(10));
+    }
+
+    #[test]
+    fn test_empty_corpus() {
+        let stats = Corpus::new().stats(5);
+        assert_eq!(stats.documents, 0);
+        assert_eq!(stats.mean, 0.0);
+        assert!(stats.type_shares.is_empty());
+    }
+}
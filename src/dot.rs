@@ -0,0 +1,186 @@
+use crate::{HumanSize, SizeTree};
+use std::fmt::Write;
+
+/// Options for [`SizeTree::to_dot`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DotOptions {
+    /// Nodes deeper than this are left out; the root has depth 0.
+    pub max_depth: Option<usize>,
+    /// Nodes holding less than this fraction of the root's size, between 0 and 1, are left out.
+    pub min_share: f64,
+    /// Whether sizes are labelled as [`HumanSize`]s rather than in bytes.
+    pub human_sizes: bool,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            min_share: 0.0,
+            human_sizes: true,
+        }
+    }
+}
+
+impl SizeTree {
+    /// Renders the tree as a GraphViz DOT digraph, for `dot -Tsvg` and friends.
+    ///
+    /// Each node is labelled with its path and `cumulative_size`. Its font grows and its color
+    /// shifts from green to red with its share of the root's size. Children left out by
+    /// `options` are folded into one dashed node per parent, counting them and their bytes.
+    ///
+    /// ## Example
+    /// ```
+    /// use json_size::{DotOptions, SizeTree};
+    /// use serde_json::json;
+    ///
+    /// let tree = SizeTree::new(&json!({"users": [{"avatar": "iVBORw0KGgo"}], "id": 7}));
+    /// let dot = tree.to_dot(&DotOptions::default());
+    /// assert!(dot.starts_with("digraph json_size {"));
+    /// assert!(dot.contains("/users/0/avatar"));
+    /// ```
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        let mut out = String::from("digraph json_size {\n");
+        out.push_str("  node [shape=box, style=\"filled,rounded\", fontname=\"Helvetica\"];\n");
+        let mut dot = Dot {
+            out,
+            options,
+            total: self.cumulative_size.max(1),
+            next_id: 0,
+        };
+        dot.node(self, 0);
+        let mut out = dot.out;
+        out.push_str("}\n");
+        out
+    }
+}
+
+struct Dot<'a> {
+    out: String,
+    options: &'a DotOptions,
+    total: usize,
+    next_id: usize,
+}
+
+impl Dot<'_> {
+    /// Writes `tree` and its kept descendants, returning the id of its node.
+    fn node(&mut self, tree: &SizeTree, depth: usize) -> usize {
+        let id = self.id();
+        let share = tree.cumulative_size as f64 / self.total as f64;
+        let path = if tree.path.is_root() {
+            "root".to_owned()
+        } else {
+            tree.path.to_string()
+        };
+        let label = format!("{path}\n{}", self.size(tree.cumulative_size));
+        let _ = writeln!(
+            self.out,
+            "  n{id} [label=\"{}\", fontsize={:.1}, fillcolor=\"{:.3} 0.6 1.0\"];",
+            escape(&label),
+            10.0 + 14.0 * share.sqrt(),
+            // From green at no share to red at the whole document.
+            (1.0 - share) / 3.0
+        );
+        let (mut omitted, mut omitted_size) = (0, 0usize);
+        for child in &tree.children {
+            if self.keeps(child, depth + 1) {
+                let child_id = self.node(child, depth + 1);
+                let _ = writeln!(self.out, "  n{id} -> n{child_id};");
+            } else {
+                omitted += child.iter().count();
+                omitted_size = omitted_size.saturating_add(child.cumulative_size);
+            }
+        }
+        if omitted > 0 {
+            let rest = self.id();
+            let nodes = if omitted == 1 { "node" } else { "nodes" };
+            let label = format!("{omitted} more {nodes}\n{}", self.size(omitted_size));
+            let _ = writeln!(
+                self.out,
+                "  n{rest} [label=\"{}\", style=dashed, fontsize=10];",
+                escape(&label)
+            );
+            let _ = writeln!(self.out, "  n{id} -> n{rest} [style=dashed];");
+        }
+        id
+    }
+
+    fn keeps(&self, tree: &SizeTree, depth: usize) -> bool {
+        let deep_enough = self.options.max_depth.is_none_or(|max| depth <= max);
+        let share = tree.cumulative_size as f64 / self.total as f64;
+        deep_enough && share >= self.options.min_share
+    }
+
+    fn id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id - 1
+    }
+
+    fn size(&self, bytes: usize) -> String {
+        if self.options.human_sizes {
+            HumanSize(bytes).to_string()
+        } else {
+            format!("{bytes} bytes")
+        }
+    }
+}
+
+/// Escapes `s` for a double-quoted DOT string, keeping line breaks as `\n` escapes.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_every_node_and_edge() {
+        let tree = SizeTree::new(&json!({"a": [1, 2], "b": "text"}));
+        let options = DotOptions {
+            human_sizes: false,
+            ..DotOptions::default()
+        };
+        let dot = tree.to_dot(&options);
+        assert_eq!(dot.matches("[label=").count(), tree.iter().count());
+        assert_eq!(dot.matches(" -> ").count(), tree.iter().count() - 1);
+        assert!(dot.contains(&format!("label=\"root\\n{} bytes\"", tree.cumulative_size)));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_pruned_children_are_folded() {
+        let tree = SizeTree::new(&json!({"big": "x".repeat(1000), "small": [1, 2, 3]}));
+        let dot = tree.to_dot(&DotOptions {
+            min_share: 0.5,
+            ..DotOptions::default()
+        });
+        assert!(dot.contains("/big\\n"));
+        assert!(!dot.contains("/small"));
+        // The array and its three elements.
+        assert!(dot.contains("label=\"4 more nodes\\n"), "{dot}");
+        let shallow = tree.to_dot(&DotOptions {
+            max_depth: Some(0),
+            ..DotOptions::default()
+        });
+        assert_eq!(shallow.matches("[label=").count(), 2);
+    }
+
+    #[test]
+    fn test_labels_are_escaped() {
+        let tree = SizeTree::new(&json!({"say \"hi\"\\": 1}));
+        let dot = tree.to_dot(&DotOptions::default());
+        assert!(dot.contains(r#"label="/say \"hi\"\\\n"#), "{dot}");
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{sizeof_val, JsonPath};
+use serde::Serialize;
+use serde_json::Value;
+
+/// A string value holding a JSON object or array of its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DoubleEncoded {
+    pub path: JsonPath,
+    /// Estimated size of the string value.
+    pub string_size: usize,
+    /// Estimated size of the value the string holds, once parsed.
+    pub parsed_size: usize,
+}
+
+impl DoubleEncoded {
+    /// Bytes parsing the string would add, negative if the parsed value is smaller.
+    pub fn delta(&self) -> isize {
+        self.parsed_size as isize - self.string_size as isize
+    }
+}
+
+/// The stringified JSON payloads in a document, created by [`double_encoded_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DoubleEncodedReport {
+    /// Estimated size of all the double-encoded strings.
+    pub string_size: usize,
+    /// Estimated size of all of them once parsed.
+    pub parsed_size: usize,
+    /// Every double-encoded string, the largest first.
+    pub strings: Vec<DoubleEncoded>,
+}
+
+impl DoubleEncodedReport {
+    /// Bytes parsing every double-encoded string would add to the document, negative if it would
+    /// shrink.
+    pub fn delta(&self) -> isize {
+        self.parsed_size as isize - self.string_size as isize
+    }
+}
+
+/// Finds the string values of `v` that are themselves JSON objects or arrays, such as payloads
+/// stringified before being embedded, and sizes them as strings and as parsed values.
+///
+/// Strings holding a bare scalar, such as `"42"` or `"true"`, are not reported. Keys are never
+/// reported either. Parsed arrays are sized with the spare capacity parsing leaves them.
+///
+/// ## Example
+/// ```
+/// use json_size::double_encoded_report;
+/// use serde_json::json;
+///
+/// let log = json!({
+///     "level": "info",
+///     "payload": r#"{"user": {"id": 7, "roles": ["admin", "dev"]}}"#,
+/// });
+/// let report = double_encoded_report(&log);
+/// assert_eq!(report.strings.len(), 1);
+/// assert_eq!(report.strings[0].path.to_string(), "/payload");
+/// // Parsed, the payload takes several times the memory of its text.
+/// assert!(report.delta() > 0);
+/// ```
+pub fn double_encoded_report(v: &Value) -> DoubleEncodedReport {
+    let mut report = DoubleEncodedReport::default();
+    collect(v, &mut JsonPath::root(), &mut report);
+    report.strings.sort_by(|a, b| {
+        b.string_size
+            .cmp(&a.string_size)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    report
+}
+
+fn collect(v: &Value, path: &mut JsonPath, report: &mut DoubleEncodedReport) {
+    match v {
+        Value::String(s) => {
+            let Some(parsed) = parse_container(s) else {
+                return;
+            };
+            let found = DoubleEncoded {
+                path: path.clone(),
+                string_size: sizeof_val(v),
+                parsed_size: sizeof_val(&parsed),
+            };
+            report.string_size += found.string_size;
+            report.parsed_size += found.parsed_size;
+            report.strings.push(found);
+        }
+        Value::Array(a) => {
+            for (i, item) in a.iter().enumerate() {
+                path.push_index(i);
+                collect(item, path, report);
+                path.pop();
+            }
+        }
+        Value::Object(o) => {
+            for (k, item) in o {
+                path.push_key(k.as_str());
+                collect(item, path, report);
+                path.pop();
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+}
+
+/// Parses `s` if it is a JSON object or array, surrounded by whitespace at most.
+fn parse_container(s: &str) -> Option<Value> {
+    let trimmed = s.trim_start();
+    if !trimmed.starts_with(['{', '[']) {
+        return None;
+    }
+    serde_json::from_str(trimmed).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sizes_strings_and_parsed_values() {
+        let inner = r#"[1, 2, {"a": "b"}]"#;
+        let val = json!({"blob": inner, "nested": [{"deep": " {} "}]});
+        let report = double_encoded_report(&val);
+        let paths: Vec<String> = report.strings.iter().map(|s| s.path.to_string()).collect();
+        assert_eq!(paths, ["/blob", "/nested/0/deep"]);
+        let blob = &report.strings[0];
+        assert_eq!(blob.string_size, sizeof_val(&json!(inner)));
+        assert_eq!(
+            blob.parsed_size,
+            sizeof_val(&serde_json::from_str(inner).unwrap())
+        );
+        assert_eq!(
+            report.string_size,
+            blob.string_size + sizeof_val(&json!(" {} "))
+        );
+        assert_eq!(
+            report.parsed_size,
+            blob.parsed_size + sizeof_val(&json!({}))
+        );
+    }
+
+    #[test]
+    fn test_ignores_scalars_and_invalid_json() {
+        let val = json!([
+            "42",
+            "true",
+            "\"quoted\"",
+            "{not json",
+            "[1,",
+            "plain text",
+            "[] trailing"
+        ]);
+        assert_eq!(double_encoded_report(&val), DoubleEncodedReport::default());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::budget::{elements_from, serialized_fitting};
+use crate::serialized_size;
+use serde_json::Value;
+
+/// Protocol framing around a JSON payload, for checking it against a message size limit.
+///
+/// Broker and webhook limits apply to the whole message, not only the JSON in it: a message costs
+/// `fixed` bytes of framing, the bytes of its headers or attributes, and `per_record` bytes for
+/// each record it carries, on top of the serialized JSON.
+///
+/// ## Example
+/// ```
+/// use json_size::EnvelopeModel;
+/// use serde_json::json;
+///
+/// // A Kafka record batch has a 61-byte header; allow 32 bytes of framing per record.
+/// let kafka = EnvelopeModel::new()
+///     .fixed(61)
+///     .per_record(32)
+///     .header("trace-id", "4bf92f3577b34da6");
+/// let event = json!({"kind": "click", "x": 1});
+/// assert_eq!(kafka.serialized_size(&event), 61 + 32 + 24 + event.to_string().len());
+/// assert!(kafka.fits(&event, 1_000_000));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct EnvelopeModel {
+    fixed: usize,
+    per_record: usize,
+    headers: usize,
+}
+
+impl EnvelopeModel {
+    /// Creates a model without any overhead, in which a message is exactly its JSON.
+    pub const fn new() -> Self {
+        Self {
+            fixed: 0,
+            per_record: 0,
+            headers: 0,
+        }
+    }
+
+    /// Sets the bytes of framing added once per message.
+    pub const fn fixed(mut self, bytes: usize) -> Self {
+        self.fixed = bytes;
+        self
+    }
+
+    /// Sets the bytes of framing added for each record in a message.
+    pub const fn per_record(mut self, bytes: usize) -> Self {
+        self.per_record = bytes;
+        self
+    }
+
+    /// Adds a header or attribute sent with every message, counted as its name and value bytes.
+    pub const fn header(self, name: &str, value: &str) -> Self {
+        self.header_bytes(name.len() + value.len())
+    }
+
+    /// Adds `bytes` of headers sent with every message.
+    pub const fn header_bytes(mut self, bytes: usize) -> Self {
+        self.headers += bytes;
+        self
+    }
+
+    /// Size of a message carrying `records` records with `payload` bytes of JSON between them.
+    pub const fn message_size(&self, payload: usize, records: usize) -> usize {
+        self.fixed + self.headers + records * self.per_record + payload
+    }
+
+    /// Size of a message carrying the compact serialization of `v` as its only record.
+    pub fn serialized_size(&self, v: &Value) -> usize {
+        self.message_size(serialized_size(v), 1)
+    }
+
+    /// Whether a message carrying `v` as its only record is at most `limit` bytes.
+    pub fn fits(&self, v: &Value, limit: usize) -> bool {
+        self.serialized_size(v) <= limit
+    }
+
+    /// Returns how many elements of the array `v`, starting at index `start`, fit in a message
+    /// of at most `limit` bytes carrying them as one JSON array in a single record.
+    ///
+    /// Same as [`crate::elements_fitting_serialized`], with the envelope taken off the limit
+    /// first.
+    ///
+    /// ## Example
+    /// ```
+    /// use json_size::EnvelopeModel;
+    /// use serde_json::json;
+    ///
+    /// let sqs = EnvelopeModel::new().header("type", "order.created");
+    /// let orders = json!([{"id": 1}, {"id": 2}, {"id": 3}]);
+    /// // `[{"id":1},{"id":2}]` is 19 bytes and the attribute 17.
+    /// assert_eq!(sqs.elements_fitting(&orders, 36, 0), 2);
+    /// ```
+    pub fn elements_fitting(&self, v: &Value, limit: usize, start: usize) -> usize {
+        // The brackets, then each element and the comma before all but the first.
+        match limit.checked_sub(self.message_size(2, 1)) {
+            Some(remaining) => serialized_fitting(elements_from(v, start), remaining, 0, 1),
+            None => 0,
+        }
+    }
+
+    /// Returns how many elements of the array `v`, starting at index `start`, fit in a message
+    /// of at most `limit` bytes carrying each of them as a record of its own, as a batch of
+    /// broker records does.
+    ///
+    /// ## Example
+    /// ```
+    /// use json_size::EnvelopeModel;
+    /// use serde_json::json;
+    ///
+    /// let batch = EnvelopeModel::new().fixed(61).per_record(20);
+    /// let events = json!([{"n": 1}, {"n": 2}, {"n": 3}]);
+    /// // Each `{"n":1}` is 7 bytes, or 27 with its framing.
+    /// assert_eq!(batch.records_fitting(&events, 61 + 27 * 2, 0), 2);
+    /// ```
+    pub fn records_fitting(&self, v: &Value, limit: usize, start: usize) -> usize {
+        match limit.checked_sub(self.message_size(0, 0)) {
+            Some(remaining) => {
+                serialized_fitting(elements_from(v, start), remaining, self.per_record, 0)
+            }
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements_fitting_serialized;
+    use serde_json::json;
+
+    #[test]
+    fn test_empty_envelope_matches_serialized_size() {
+        let val = json!({"a": [1, 2, "three"]});
+        let none = EnvelopeModel::new();
+        assert_eq!(none.serialized_size(&val), serialized_size(&val));
+        assert!(none.fits(&val, serialized_size(&val)));
+        assert!(!none.fits(&val, serialized_size(&val) - 1));
+        let items = json!(["a", "bb", "ccc", "dddd"]);
+        for limit in 0..20 {
+            assert_eq!(
+                none.elements_fitting(&items, limit, 1),
+                elements_fitting_serialized(&items, limit, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_envelope_shrinks_chunks() {
+        let items = json!(["aaaa", "bbbb", "cccc", "dddd"]);
+        let envelope = EnvelopeModel::new().fixed(10).header_bytes(5);
+        // `["aaaa","bbbb"]` is 15 bytes.
+        assert_eq!(envelope.elements_fitting(&items, 30, 0), 2);
+        assert_eq!(envelope.elements_fitting(&items, 29, 0), 1);
+        assert_eq!(envelope.elements_fitting(&items, 14, 0), 0);
+        // As records, each element costs its 6 bytes plus the per-record framing.
+        let records = envelope.per_record(4);
+        assert_eq!(records.records_fitting(&items, 15 + 30, 1), 3);
+        assert_eq!(records.records_fitting(&items, 15 + 29, 1), 2);
+        assert_eq!(records.message_size(18, 3), 15 + 12 + 18);
+    }
+}
@@ -0,0 +1,526 @@
+use crate::{btree, STRING_OVERHEAD};
+use serde_json::Value;
+use std::mem::{align_of, size_of};
+
+/// Which length of a string buffer is counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CapacityMode {
+    /// Only the bytes in use (`len()`).
+    Len,
+    /// The whole allocated buffer (`capacity()`).
+    #[default]
+    Capacity,
+}
+
+/// How the size of each heap allocation is derived from the number of bytes requested.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AllocatorModel {
+    /// Allocations cost exactly the bytes requested.
+    #[default]
+    Exact,
+    /// Allocations carry a one-word header and are rounded up to the next power of two, with a
+    /// 16 byte minimum. This is at least what common allocators (glibc malloc, jemalloc, mimalloc)
+    /// hand out, so it makes a pessimistic model.
+    Bucketed,
+}
+
+impl AllocatorModel {
+    /// Size of an allocation of `requested` bytes, where [`AllocatorModel::Bucketed`] allocations
+    /// carry a `header` byte header, the size of a pointer.
+    pub(crate) const fn allocation_size(self, requested: usize, header: usize) -> usize {
+        match self {
+            Self::Exact => requested,
+            Self::Bucketed if requested == 0 => 0,
+            Self::Bucketed => match requested.saturating_add(header).checked_next_power_of_two() {
+                Some(bucket) if bucket < 16 => 16,
+                Some(bucket) => bucket,
+                None => usize::MAX,
+            },
+        }
+    }
+}
+
+/// The target whose type layouts are used: the sizes of pointers, `Value`s and `String`s.
+///
+/// Estimates are made for the target the crate is compiled for unless another one is chosen,
+/// e.g. to predict the footprint of a wasm32 build from a 64-bit host.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TargetModel {
+    /// The target this crate was compiled for.
+    #[default]
+    Host,
+    /// 64-bit targets such as x86_64 and aarch64: 8-byte pointers, 32-byte `Value`s and 24-byte
+    /// `String`s.
+    Bits64,
+    /// 32-bit targets that align 64-bit integers to 8 bytes, such as wasm32 and 32-bit ARM:
+    /// 4-byte pointers, 24-byte `Value`s and 12-byte `String`s.
+    Bits32,
+}
+
+impl TargetModel {
+    pub const fn pointer_size(self) -> usize {
+        match self {
+            Self::Host => size_of::<usize>(),
+            Self::Bits64 => 8,
+            Self::Bits32 => 4,
+        }
+    }
+
+    /// `size_of::<serde_json::Value>()` on the target.
+    pub const fn value_size(self) -> usize {
+        match self {
+            Self::Host => size_of::<Value>(),
+            Self::Bits64 => 32,
+            Self::Bits32 => 24,
+        }
+    }
+
+    /// `size_of::<String>()` on the target, also the size of a `Vec` or a `serde_json::Map`.
+    pub const fn string_size(self) -> usize {
+        3 * self.pointer_size()
+    }
+
+    /// `align_of::<serde_json::Value>()` on the target: 8 on both, for the 64-bit numbers.
+    pub(crate) const fn value_align(self) -> usize {
+        match self {
+            Self::Host => align_of::<Value>(),
+            Self::Bits64 | Self::Bits32 => 8,
+        }
+    }
+}
+
+/// How the bookkeeping of object maps is costed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MapModel {
+    /// The nodes of the `BTreeMap` behind `serde_json::Map`: leaves and internal nodes with room
+    /// for 11 keys and values each, assumed to be about 70% full once the map has more than 11
+    /// members. The key and value slots in the nodes are counted with the members, the empty
+    /// slots and node headers with the object.
+    ///
+    /// With `serde_json`'s `preserve_order` feature the map is an `IndexMap` instead, which this
+    /// model does not describe.
+    #[default]
+    BTree,
+    /// A flat number of bytes per object entry, on top of its key and value.
+    PerEntry(usize),
+}
+
+/// Where the nodes and buffers of the document live.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum StorageModel {
+    /// A `serde_json::Value` tree, where every string, array and map node is its own heap
+    /// allocation.
+    #[default]
+    Heap,
+    /// An arena-native tree held in a bump arena, bumpalo-style: nodes of a tag and two
+    /// pointer-width words, strings and arrays referenced by pointer and length, and objects as
+    /// slices of key reference and node pairs. Everything is stored contiguously at its exact
+    /// length, without allocation headers, spare capacity or map bookkeeping, so the capacity
+    /// mode, allocator model, string overhead and map model are ignored.
+    ///
+    /// The bytes an arena wastes at the end of its chunks, and those left behind by slices that
+    /// grew while being built, are not counted.
+    Arena,
+}
+
+/// A configurable version of the [`crate::sizeof_val`] cost model.
+///
+/// The default estimator reproduces [`crate::sizeof_val`] exactly.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::{sizeof_val, AllocatorModel, CapacityMode, SizeEstimator};
+///
+/// let val = json!({"name": "OpenAI"});
+/// assert_eq!(SizeEstimator::new().estimate(&val), sizeof_val(&val));
+///
+/// let pessimistic = SizeEstimator::new().allocator_model(AllocatorModel::Bucketed);
+/// assert!(pessimistic.estimate(&val) >= sizeof_val(&val));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SizeEstimator {
+    pub(crate) capacity_mode: CapacityMode,
+    pub(crate) allocator_model: AllocatorModel,
+    pub(crate) string_overhead: usize,
+    pub(crate) map_model: MapModel,
+    pub(crate) target_model: TargetModel,
+    pub(crate) storage_model: StorageModel,
+    #[cfg(feature = "tracing")]
+    pub(crate) trace_nodes: bool,
+    #[cfg(feature = "tracing")]
+    pub(crate) warn_over: Option<usize>,
+}
+
+impl Default for SizeEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SizeEstimator {
+    pub(crate) const DEFAULT: Self = Self::new();
+
+    /// Creates an estimator with the same constants as [`crate::sizeof_val`].
+    pub const fn new() -> Self {
+        Self {
+            capacity_mode: CapacityMode::Capacity,
+            allocator_model: AllocatorModel::Exact,
+            string_overhead: STRING_OVERHEAD,
+            map_model: MapModel::BTree,
+            target_model: TargetModel::Host,
+            storage_model: StorageModel::Heap,
+            #[cfg(feature = "tracing")]
+            trace_nodes: false,
+            #[cfg(feature = "tracing")]
+            warn_over: None,
+        }
+    }
+
+    /// Sets whether string buffers are counted by length or capacity.
+    pub fn capacity_mode(mut self, mode: CapacityMode) -> Self {
+        self.capacity_mode = mode;
+        self
+    }
+
+    /// Sets how heap allocations are rounded.
+    pub fn allocator_model(mut self, model: AllocatorModel) -> Self {
+        self.allocator_model = model;
+        self
+    }
+
+    /// Sets the bytes charged per string (value or key) on top of its buffer.
+    pub fn string_overhead(mut self, bytes: usize) -> Self {
+        self.string_overhead = bytes;
+        self
+    }
+
+    /// Sets how the bookkeeping of object maps is costed.
+    pub fn map_model(mut self, model: MapModel) -> Self {
+        self.map_model = model;
+        self
+    }
+
+    /// Sets the target whose type layouts are used, and the string overhead to the size of a
+    /// `String` on it. Call [`SizeEstimator::string_overhead`] afterwards to override the latter.
+    pub fn target_model(mut self, model: TargetModel) -> Self {
+        self.target_model = model;
+        self.string_overhead = model.string_size();
+        self
+    }
+
+    /// Sets where the document is stored. [`StorageModel::Arena`] predicts the footprint of an
+    /// arena-backed tree holding the same document:
+    ///
+    /// ```
+    /// use json_size::{sizeof_val, SizeEstimator, StorageModel};
+    /// use serde_json::json;
+    ///
+    /// let val = json!([{"id": 1, "tags": ["a", "b"]}, {"id": 2, "tags": []}]);
+    /// let arena = SizeEstimator::new().storage_model(StorageModel::Arena);
+    /// assert!(arena.estimate(&val) < sizeof_val(&val) / 2);
+    /// ```
+    pub fn storage_model(mut self, model: StorageModel) -> Self {
+        self.storage_model = model;
+        self
+    }
+
+    /// Charges a flat `bytes` per object entry for the map's own bookkeeping, instead of modeling
+    /// its B-tree nodes. Same as `map_model(MapModel::PerEntry(bytes))`.
+    pub fn map_entry_overhead(self, bytes: usize) -> Self {
+        self.map_model(MapModel::PerEntry(bytes))
+    }
+
+    /// Estimates the size of `v` in bytes, saturating at `usize::MAX`.
+    pub fn estimate(&self, v: &Value) -> usize {
+        self.estimate_checked(v)
+            .and_then(|size| usize::try_from(size).ok())
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Estimates the size of `v` in bytes, returning `None` if the sum overflows a `u64`.
+    pub fn estimate_checked(&self, v: &Value) -> Option<u64> {
+        let children = match v {
+            Value::Null => 0,
+            Value::Bool(_) => 0,
+            Value::Number(_) => 0, // incorrect if arbitrary_precision is enabled
+            Value::String(_) => 0,
+            Value::Array(a) => a
+                .iter()
+                .try_fold(0u64, |acc, v| acc.checked_add(self.estimate_checked(v)?))?,
+            Value::Object(o) => o.iter().try_fold(0u64, |acc, (k, v)| {
+                acc.checked_add(self.entry_overhead(k) as u64)?
+                    .checked_add(self.estimate_checked(v)?)
+            })?,
+        };
+        (self.own_size(v) as u64).checked_add(children)
+    }
+
+    /// Size attributed to a node itself, excluding its children.
+    pub(crate) fn own_size(&self, v: &Value) -> usize {
+        self.node_size()
+            + match v {
+                Value::String(s) => self.string_size(s),
+                Value::Array(a) => self.array_overhead(a.len(), a.capacity()),
+                Value::Object(o) => self.map_overhead(o.len()),
+                _ => 0,
+            }
+    }
+
+    /// Cost of an object entry on top of its value: the key buffer and the map bookkeeping.
+    pub(crate) fn entry_overhead(&self, k: &String) -> usize {
+        self.string_size(k) + self.per_entry_overhead()
+    }
+
+    /// Buffer of an array of `len` elements with room for `capacity`, beyond the element slots.
+    ///
+    /// The slots in use are charged to the elements, which each cost a `Value`, so this is the
+    /// spare capacity (in [`CapacityMode::Capacity`]) plus any allocator rounding.
+    pub(crate) const fn array_overhead(&self, len: usize, capacity: usize) -> usize {
+        if let StorageModel::Arena = self.storage_model {
+            return 0;
+        }
+        let slots = match self.capacity_mode {
+            CapacityMode::Len => len,
+            CapacityMode::Capacity => capacity,
+        };
+        let value = self.target_model.value_size();
+        self.allocate(slots.saturating_mul(value))
+            .saturating_sub(len * value)
+    }
+
+    /// Map bookkeeping charged to an object of `len` members as a whole, on top of what its
+    /// entries are charged.
+    pub(crate) const fn map_overhead(&self, len: usize) -> usize {
+        if let StorageModel::Arena = self.storage_model {
+            return 0;
+        }
+        match self.map_model {
+            MapModel::BTree => {
+                let (key, value) = (
+                    self.target_model.string_size(),
+                    self.target_model.value_size(),
+                );
+                let nodes =
+                    btree::nodes_size(len, key, value, self.allocator_model, self.target_model);
+                // The key and value slots are charged to the entries and their values.
+                nodes.saturating_sub(len * (key + value))
+            }
+            MapModel::PerEntry(_) => 0,
+        }
+    }
+
+    /// Map bookkeeping charged to each object entry; in an arena, the reference to its key.
+    pub(crate) const fn per_entry_overhead(&self) -> usize {
+        match (self.storage_model, self.map_model) {
+            (StorageModel::Arena, _) => 2 * self.target_model.pointer_size(),
+            (StorageModel::Heap, MapModel::BTree) => 0,
+            (StorageModel::Heap, MapModel::PerEntry(bytes)) => bytes,
+        }
+    }
+
+    /// Size of a value node: a `Value`, or in an arena, a tag and a string or slice reference.
+    pub(crate) const fn node_size(&self) -> usize {
+        match self.storage_model {
+            StorageModel::Heap => self.target_model.value_size(),
+            StorageModel::Arena => {
+                // The tag is padded to the 8-byte alignment of the numbers.
+                let payload = 2 * self.target_model.pointer_size();
+                (if payload > 8 { payload } else { 8 }) + 8
+            }
+        }
+    }
+
+    /// Bytes charged per string on top of its buffer, none in an arena.
+    pub(crate) const fn string_header(&self) -> usize {
+        match self.storage_model {
+            StorageModel::Heap => self.string_overhead,
+            StorageModel::Arena => 0,
+        }
+    }
+
+    fn string_size(&self, s: &String) -> usize {
+        self.string_header() + self.buffer_size(s)
+    }
+
+    /// The heap allocation behind `s`, without the `String` itself.
+    pub(crate) fn buffer_size(&self, s: &String) -> usize {
+        let bytes = match (self.storage_model, self.capacity_mode) {
+            (StorageModel::Arena, _) | (_, CapacityMode::Len) => s.len(),
+            (StorageModel::Heap, CapacityMode::Capacity) => s.capacity(),
+        };
+        self.allocate(bytes)
+    }
+
+    /// Cost of a string with a buffer of `bytes` bytes, on top of the `Value` holding it.
+    pub(crate) const fn string_buffer_size(&self, bytes: usize) -> usize {
+        self.string_header() + self.allocate(bytes)
+    }
+
+    /// An allocation of `bytes`, rounded by the allocator model with the target's pointer size.
+    const fn allocate(&self, bytes: usize) -> usize {
+        match self.storage_model {
+            StorageModel::Heap => self
+                .allocator_model
+                .allocation_size(bytes, self.target_model.pointer_size()),
+            StorageModel::Arena => bytes,
+        }
+    }
+
+    /// Same as [`SizeEstimator::entry_overhead`], for a key of `key_bytes` bytes.
+    pub(crate) const fn entry_overhead_for_len(&self, key_bytes: usize) -> usize {
+        self.string_buffer_size(key_bytes) + self.per_entry_overhead()
+    }
+}
+
+/// Returns a conservative lower bound and a pessimistic upper bound for the size of `v`.
+///
+/// The lower bound counts only the bytes strings actually use and assumes an allocator without
+/// any overhead. The upper bound counts full buffer capacities and rounds every allocation the
+/// way [`AllocatorModel::Bucketed`] does. [`crate::sizeof_val`] always lies between the two.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::{sizeof_val, sizeof_val_bounds};
+///
+/// let val = json!({"services": ["chatbot", "API"]});
+/// let (lower, upper) = sizeof_val_bounds(&val);
+/// assert!(lower <= sizeof_val(&val) && sizeof_val(&val) <= upper);
+/// ```
+pub fn sizeof_val_bounds(v: &Value) -> (usize, usize) {
+    let lower = SizeEstimator::new().capacity_mode(CapacityMode::Len);
+    let upper = SizeEstimator::new().allocator_model(AllocatorModel::Bucketed);
+    (lower.estimate(v), upper.estimate(v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sizeof_val;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_matches_sizeof_val() {
+        let val = json!({"a": [1, "two", {"three": null}], "b": "x".repeat(40)});
+        assert_eq!(SizeEstimator::new().estimate(&val), sizeof_val(&val));
+    }
+
+    #[test]
+    fn test_len_mode_ignores_spare_capacity() {
+        let mut s = String::with_capacity(100);
+        s.push_str("abc");
+        let val = Value::String(s);
+        let len = SizeEstimator::new().capacity_mode(CapacityMode::Len);
+        assert_eq!(sizeof_val(&val) - len.estimate(&val), 97);
+    }
+
+    #[test]
+    fn test_array_spare_capacity() {
+        let mut items = Vec::with_capacity(10);
+        items.push(Value::Null);
+        let val = Value::Array(items);
+        assert_eq!(
+            sizeof_val(&val),
+            2 * size_of::<Value>() + 9 * size_of::<Value>()
+        );
+        let len = SizeEstimator::new().capacity_mode(CapacityMode::Len);
+        assert_eq!(len.estimate(&val), 2 * size_of::<Value>());
+        let bucketed = SizeEstimator::new().allocator_model(AllocatorModel::Bucketed);
+        assert_eq!(bucketed.estimate(&val), size_of::<Value>() + 512);
+    }
+
+    #[test]
+    fn test_bucketed_allocation_sizes() {
+        assert_eq!(AllocatorModel::Bucketed.allocation_size(0, 8), 0);
+        assert_eq!(AllocatorModel::Bucketed.allocation_size(1, 8), 16);
+        assert_eq!(AllocatorModel::Bucketed.allocation_size(100, 8), 128);
+    }
+
+    #[test]
+    fn test_bounds_bracket_estimate() {
+        let mut s = String::with_capacity(64);
+        s.push_str("spare");
+        let mut val = json!({"list": ["a", "bb"]});
+        val["key"] = Value::String(s);
+        let (lower, upper) = sizeof_val_bounds(&val);
+        assert!(lower < sizeof_val(&val));
+        assert!(sizeof_val(&val) < upper);
+    }
+
+    #[test]
+    fn test_custom_overheads() {
+        let est = SizeEstimator::new()
+            .string_overhead(0)
+            .map_entry_overhead(0);
+        let val = json!({"ab": "cde"});
+        assert_eq!(est.estimate(&val), 2 * size_of::<Value>() + 2 + 3);
+    }
+
+    #[test]
+    fn test_btree_model_counts_nodes() {
+        let (leaf, internal) =
+            btree::node_sizes(size_of::<String>(), size_of::<Value>(), TargetModel::Host);
+        let est = SizeEstimator::new();
+        let val = json!({"k": null});
+        // One leaf holds the key and value slots; only the key buffer is extra.
+        assert_eq!(est.estimate(&val), size_of::<Value>() + leaf + 1);
+
+        let val = Value::Object((0..100).map(|i| (format!("{i:03}"), Value::Null)).collect());
+        let (leaves, internals) = btree::node_counts(100);
+        let nodes = leaves * leaf + internals * internal;
+        let est = est.capacity_mode(CapacityMode::Len);
+        assert_eq!(est.estimate(&val), size_of::<Value>() + nodes + 100 * 3);
+    }
+
+    #[test]
+    fn test_target_models() {
+        let val = json!({"name": "ada", "tags": ["a", "b"]});
+        let host = SizeEstimator::new();
+        let bits64 = SizeEstimator::new().target_model(TargetModel::Bits64);
+        assert_eq!(bits64.estimate(&val), host.estimate(&val));
+        let bits32 = SizeEstimator::new().target_model(TargetModel::Bits32);
+        // 5 nodes, 3 strings and 2 keys, and a 408-byte leaf beyond the key and value slots.
+        assert_eq!(
+            bits32.estimate(&val),
+            5 * 24 + (3 * 12 + 5) + (2 * 12 + 8) + (408 - 2 * (12 + 24))
+        );
+        assert_eq!(
+            bits32.string_overhead(0).estimate(&json!("abc")),
+            TargetModel::Bits32.value_size() + 3
+        );
+    }
+
+    #[test]
+    fn test_arena_model() {
+        let mut items = Vec::with_capacity(10);
+        items.extend([json!(1), json!(2)]);
+        let mut val = json!({"ab": String::with_capacity(64) + "cde"});
+        val["list"] = Value::Array(items);
+        let arena = SizeEstimator::new()
+            .target_model(TargetModel::Bits64)
+            .storage_model(StorageModel::Arena);
+        // Five 24-byte nodes, the string, and each key with its 16-byte reference.
+        let expected = 5 * 24 + 3 + (2 + 16) + (4 + 16);
+        assert_eq!(arena.estimate(&val), expected);
+        let ignored = arena
+            .clone()
+            .allocator_model(AllocatorModel::Bucketed)
+            .string_overhead(100)
+            .map_entry_overhead(100);
+        assert_eq!(ignored.estimate(&val), expected);
+        let bits32 = arena.target_model(TargetModel::Bits32);
+        assert_eq!(bits32.estimate(&val), 5 * 16 + 3 + (2 + 8) + (4 + 8));
+    }
+
+    #[test]
+    fn test_per_entry_model() {
+        let est = SizeEstimator::new().map_entry_overhead(24);
+        assert_eq!(est.map_model, MapModel::PerEntry(24));
+        let val = json!({"k": null});
+        assert_eq!(
+            est.estimate(&val),
+            2 * size_of::<Value>() + STRING_OVERHEAD + 1 + 24
+        );
+    }
+}
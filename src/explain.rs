@@ -0,0 +1,298 @@
+use crate::{AllocatorModel, CapacityMode, MapModel, SizeEstimator, StorageModel};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// One term of the cost model, see [`Explanation`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Component {
+    pub name: &'static str,
+    /// Number of items the term is charged for.
+    pub count: usize,
+    pub bytes: usize,
+    /// How `bytes` is derived, in terms of the estimator's constants.
+    pub formula: String,
+}
+
+/// The size of a document broken down into the terms of the cost model, created by
+/// [`explain`] or [`SizeEstimator::explain`].
+///
+/// The bytes of all components add up to `total`. Displaying an explanation prints one line per
+/// component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Explanation {
+    pub total: usize,
+    pub components: Vec<Component>,
+}
+
+impl Explanation {
+    /// The component called `name`.
+    pub fn component(&self, name: &str) -> Option<&Component> {
+        self.components.iter().find(|c| c.name == name)
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in &self.components {
+            writeln!(
+                f,
+                "{:<28} {:>8} {:>12}  {}",
+                c.name, c.count, c.bytes, c.formula
+            )?;
+        }
+        write!(f, "{:<28} {:>8} {:>12}", "total", "", self.total)
+    }
+}
+
+/// Attributes [`crate::sizeof_val`] of `v` to the terms of the cost model.
+///
+/// ## Example
+/// ```
+/// use json_size::{explain, sizeof_val};
+/// use serde_json::json;
+///
+/// let val = json!({"name": "OpenAI", "services": ["chatbot", "API"]});
+/// let explanation = explain(&val);
+/// assert_eq!(explanation.total, sizeof_val(&val));
+/// assert_eq!(explanation.component("value nodes").unwrap().count, 5);
+/// println!("{explanation}");
+/// ```
+pub fn explain(v: &Value) -> Explanation {
+    SizeEstimator::DEFAULT.explain(v)
+}
+
+#[derive(Default)]
+struct Tally {
+    nodes: usize,
+    strings: usize,
+    string_buffers: usize,
+    keys: usize,
+    key_buffers: usize,
+    objects: usize,
+    /// Map bookkeeping charged to objects as a whole.
+    map_overhead: usize,
+    arrays: usize,
+    /// Spare capacity and allocator rounding of array buffers.
+    array_slack: usize,
+    numbers: usize,
+}
+
+impl SizeEstimator {
+    /// Attributes [`SizeEstimator::estimate`] of `v` to the terms of this estimator's cost model.
+    pub fn explain(&self, v: &Value) -> Explanation {
+        let mut tally = Tally::default();
+        self.tally(v, &mut tally);
+
+        let arena = self.storage_model == StorageModel::Arena;
+        let node_size = self.node_size();
+        let string_header = self.string_header();
+        let measure = match self.capacity_mode {
+            _ if arena => "len()",
+            CapacityMode::Len => "len()",
+            CapacityMode::Capacity => "capacity()",
+        };
+        let buffers = |count: usize, what: &str| match self.allocator_model {
+            AllocatorModel::Bucketed if !arena => {
+                format!("sum of {measure} of {count} {what}, bucketed by the allocator model")
+            }
+            _ => format!("sum of {measure} of {count} {what}"),
+        };
+        let times =
+            |count: usize, unit: usize, constant: &str| format!("{count} x {unit} ({constant})");
+        let components = vec![
+            Component {
+                name: "value nodes",
+                count: tally.nodes,
+                bytes: tally.nodes.saturating_mul(node_size),
+                formula: times(
+                    tally.nodes,
+                    node_size,
+                    if arena {
+                        "arena node"
+                    } else {
+                        "size_of::<Value>()"
+                    },
+                ),
+            },
+            Component {
+                name: "string headers",
+                count: tally.strings,
+                bytes: tally.strings.saturating_mul(string_header),
+                formula: times(tally.strings, string_header, "string overhead"),
+            },
+            Component {
+                name: "string buffers",
+                count: tally.strings,
+                bytes: tally.string_buffers,
+                formula: buffers(tally.strings, "strings"),
+            },
+            Component {
+                name: "key headers",
+                count: tally.keys,
+                bytes: tally.keys.saturating_mul(string_header),
+                formula: times(tally.keys, string_header, "string overhead"),
+            },
+            Component {
+                name: "key buffers",
+                count: tally.keys,
+                bytes: tally.key_buffers,
+                formula: buffers(tally.keys, "keys"),
+            },
+            Component {
+                name: "map overhead",
+                count: tally.keys,
+                bytes: tally
+                    .keys
+                    .saturating_mul(self.per_entry_overhead())
+                    .saturating_add(tally.map_overhead),
+                formula: match self.map_model {
+                    _ if arena => times(tally.keys, self.per_entry_overhead(), "key reference"),
+                    MapModel::BTree => format!(
+                        "B-tree nodes of {} objects, less the key and value slots",
+                        tally.objects
+                    ),
+                    MapModel::PerEntry(bytes) => times(tally.keys, bytes, "map entry overhead"),
+                },
+            },
+            Component {
+                name: "array slack",
+                count: tally.arrays,
+                bytes: tally.array_slack,
+                formula: format!(
+                    "spare capacity of {} arrays{}",
+                    tally.arrays,
+                    match (self.capacity_mode, self.allocator_model) {
+                        _ if arena => ", none in an arena",
+                        (CapacityMode::Len, AllocatorModel::Exact) => ", not counted in len() mode",
+                        (_, AllocatorModel::Exact) => "",
+                        (_, AllocatorModel::Bucketed) => ", bucketed by the allocator model",
+                    }
+                ),
+            },
+            Component {
+                name: "arbitrary-precision buffers",
+                count: tally.numbers,
+                bytes: 0,
+                formula:
+                    "numbers are stored inline; serde_json's arbitrary_precision is not modeled"
+                        .to_owned(),
+            },
+        ];
+        Explanation {
+            total: components
+                .iter()
+                .fold(0usize, |acc, c| acc.saturating_add(c.bytes)),
+            components,
+        }
+    }
+
+    fn tally(&self, v: &Value, tally: &mut Tally) {
+        tally.nodes += 1;
+        match v {
+            Value::Number(_) => tally.numbers += 1,
+            Value::String(s) => {
+                tally.strings += 1;
+                tally.string_buffers = tally.string_buffers.saturating_add(self.buffer_size(s));
+            }
+            Value::Array(a) => {
+                tally.arrays += 1;
+                tally.array_slack = tally
+                    .array_slack
+                    .saturating_add(self.array_overhead(a.len(), a.capacity()));
+                a.iter().for_each(|item| self.tally(item, tally));
+            }
+            Value::Object(o) => {
+                tally.objects += 1;
+                tally.map_overhead = tally
+                    .map_overhead
+                    .saturating_add(self.map_overhead(o.len()));
+                for (k, item) in o {
+                    tally.keys += 1;
+                    tally.key_buffers = tally.key_buffers.saturating_add(self.buffer_size(k));
+                    self.tally(item, tally);
+                }
+            }
+            Value::Null | Value::Bool(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sizeof_val;
+    use serde_json::json;
+    use std::mem::size_of;
+
+    #[test]
+    fn test_components_add_up() {
+        let val = json!({"a": [1, "two", {"three": null}], "b": "x".repeat(40)});
+        let explanation = explain(&val);
+        assert_eq!(explanation.total, sizeof_val(&val));
+        assert_eq!(explanation.component("value nodes").unwrap().count, 7);
+        assert_eq!(
+            explanation.component("key buffers").unwrap().bytes,
+            1 + 1 + 5
+        );
+        assert_eq!(
+            explanation.component("string buffers").unwrap().bytes,
+            3 + 40
+        );
+        assert_eq!(
+            explanation
+                .component("arbitrary-precision buffers")
+                .unwrap()
+                .count,
+            1
+        );
+    }
+
+    #[test]
+    fn test_custom_estimator() {
+        let est = SizeEstimator::new()
+            .allocator_model(AllocatorModel::Bucketed)
+            .map_entry_overhead(100);
+        let val = json!({"k": "v"});
+        let explanation = est.explain(&val);
+        assert_eq!(explanation.total, est.estimate(&val));
+        assert_eq!(explanation.component("map overhead").unwrap().bytes, 100);
+        assert_eq!(explanation.component("key buffers").unwrap().bytes, 16);
+    }
+
+    #[test]
+    fn test_arena_estimator() {
+        let est = SizeEstimator::new().storage_model(StorageModel::Arena);
+        let val = json!({"k": ["v", 1.5], "list": [[], {}]});
+        let explanation = est.explain(&val);
+        assert_eq!(explanation.total, est.estimate(&val));
+        assert_eq!(explanation.component("string headers").unwrap().bytes, 0);
+        let map = explanation.component("map overhead").unwrap();
+        assert!(map.formula.ends_with("(key reference)"), "{}", map.formula);
+    }
+
+    #[test]
+    fn test_array_slack() {
+        let mut items = Vec::with_capacity(4);
+        items.push(json!(1));
+        let val = Value::Array(items);
+        let explanation = explain(&val);
+        assert_eq!(explanation.total, sizeof_val(&val));
+        assert_eq!(
+            explanation.component("array slack").unwrap().bytes,
+            3 * size_of::<Value>()
+        );
+    }
+
+    #[test]
+    fn test_display_lists_every_component() {
+        let explanation = explain(&json!([true]));
+        let text = explanation.to_string();
+        assert_eq!(text.lines().count(), explanation.components.len() + 1);
+        assert!(text
+            .lines()
+            .last()
+            .unwrap()
+            .ends_with(&sizeof_val(&json!([true])).to_string()));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{JsonPath, SizeEstimator};
+use serde::Serialize;
+use serde_json::Value;
+use std::ops::ControlFlow;
+
+/// A node found by [`extremes`], with the measurement it was picked for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Extreme {
+    pub path: JsonPath,
+    /// The depth, number of keys or number of elements, depending on the extreme.
+    pub measure: usize,
+    /// Estimated size of the subtree, including the key and map entry overhead of a member.
+    pub size: usize,
+}
+
+/// The structural extremes of a document, created by [`extremes`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Extremes {
+    /// The most deeply nested node, measured by its depth; the root has depth 0.
+    pub deepest: Extreme,
+    /// The object with the most keys, `None` if there is no object.
+    pub widest_object: Option<Extreme>,
+    /// The array with the most elements, `None` if there is no array.
+    pub longest_array: Option<Extreme>,
+}
+
+/// Locates the deepest node, the object with the most keys and the longest array in `v`.
+///
+/// Ties go to the node whose path orders first, so an ancestor wins over its descendants. Every
+/// node is visited once.
+///
+/// ## Example
+/// ```
+/// use json_size::extremes;
+/// use serde_json::json;
+///
+/// let val = json!({
+///     "config": {"a": 1, "b": 2, "c": 3},
+///     "events": [{"tags": [["x"]]}, {}, {}, {}],
+/// });
+/// let found = extremes(&val);
+/// assert_eq!(found.deepest.path.to_string(), "/events/0/tags/0/0");
+/// assert_eq!(found.deepest.measure, 5);
+/// assert_eq!(found.widest_object.unwrap().path.to_string(), "/config");
+/// assert_eq!(found.longest_array.unwrap().measure, 4);
+/// ```
+pub fn extremes(v: &Value) -> Extremes {
+    SizeEstimator::DEFAULT.extremes(v)
+}
+
+impl SizeEstimator {
+    /// Same as [`extremes`], with sizes computed by this estimator.
+    pub fn extremes(&self, v: &Value) -> Extremes {
+        let mut deepest: Option<Extreme> = None;
+        let mut widest_object = None;
+        let mut longest_array = None;
+        let _ = self.walk_sizes(v, |path, node, size| {
+            offer(&mut deepest, path, path.len(), size);
+            match node {
+                Value::Object(o) => offer(&mut widest_object, path, o.len(), size),
+                Value::Array(a) => offer(&mut longest_array, path, a.len(), size),
+                _ => {}
+            }
+            ControlFlow::Continue(())
+        });
+        Extremes {
+            deepest: deepest.expect("the walk visits the root"),
+            widest_object,
+            longest_array,
+        }
+    }
+}
+
+/// Keeps `path` in `best` if its measure is larger, or the same with a path that orders first.
+fn offer(best: &mut Option<Extreme>, path: &JsonPath, measure: usize, size: usize) {
+    let better = best
+        .as_ref()
+        .is_none_or(|b| measure > b.measure || (measure == b.measure && *path < b.path));
+    if better {
+        *best = Some(Extreme {
+            path: path.clone(),
+            measure,
+            size,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entry_overhead, sizeof_val};
+    use serde_json::json;
+
+    #[test]
+    fn test_sizes_include_member_overhead() {
+        let val = json!({"a": {"b": [1, 2, 3]}});
+        let found = extremes(&val);
+        assert_eq!(found.deepest.path.to_string(), "/a/b/0");
+        let longest = found.longest_array.unwrap();
+        assert_eq!(
+            (longest.path.to_string(), longest.measure),
+            ("/a/b".to_string(), 3)
+        );
+        assert_eq!(
+            longest.size,
+            entry_overhead(&"b".to_string()) + sizeof_val(&json!([1, 2, 3]))
+        );
+        let widest = found.widest_object.unwrap();
+        assert_eq!(
+            (widest.path, widest.size),
+            (JsonPath::root(), sizeof_val(&val))
+        );
+    }
+
+    #[test]
+    fn test_ties_go_to_the_first_path() {
+        let val = json!({"a": [{"x": [0, 0], "y": {"p": 1, "q": 2}}, [1, 1]]});
+        let found = extremes(&val);
+        assert_eq!(found.widest_object.unwrap().path.to_string(), "/a/0");
+        assert_eq!(found.longest_array.unwrap().path.to_string(), "/a");
+        assert_eq!(found.deepest.path.to_string(), "/a/0/x/0");
+    }
+
+    #[test]
+    fn test_scalar_document() {
+        let found = extremes(&json!(42));
+        assert_eq!(found.deepest.path, JsonPath::root());
+        assert_eq!(found.deepest.measure, 0);
+        assert_eq!((found.widest_object, found.longest_array), (None, None));
+    }
+}
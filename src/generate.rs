@@ -0,0 +1,447 @@
+use crate::schema::format_len;
+use crate::{serialized_size, sizeof_val};
+use serde_json::{json, Map, Number, Value};
+use std::cell::Cell;
+
+/// What [`generate_value`] generates: the shape of the documents, a seed, and which size the
+/// target applies to.
+///
+/// The default spec generates an array of user-like records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenSpec {
+    schema: Value,
+    seed: u64,
+    serialized: bool,
+}
+
+impl Default for GenSpec {
+    fn default() -> Self {
+        Self::new(json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "required": ["id", "name", "active", "tags"],
+                "properties": {
+                    "id": {"type": "integer", "minimum": 1, "maximum": 1_000_000},
+                    "name": {"type": "string"},
+                    "email": {"type": "string", "format": "email"},
+                    "active": {"type": "boolean"},
+                    "score": {"type": "number"},
+                    "tags": {"type": "array", "maxItems": 5, "items": {"type": "string", "maxLength": 12}}
+                }
+            }
+        }))
+    }
+}
+
+impl GenSpec {
+    /// Generates documents conforming to the JSON Schema `schema`, with the keywords
+    /// [`crate::estimate_from_schema`] supports plus `minimum` and `maximum`.
+    pub fn new(schema: Value) -> Self {
+        Self {
+            schema,
+            seed: 0,
+            serialized: false,
+        }
+    }
+
+    /// Sets the seed of the generator. The same spec and target always generate the same
+    /// document.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets whether the target applies to the compact serialization, as measured by
+    /// [`crate::serialized_size`], instead of the estimated in-memory size.
+    pub fn serialized(mut self, enabled: bool) -> Self {
+        self.serialized = enabled;
+        self
+    }
+}
+
+/// Generates a random document shaped by `spec` whose size lands just under `target_bytes`.
+///
+/// The size is the estimated in-memory size, as computed by [`crate::sizeof_val`], or the
+/// serialized size if [`GenSpec::serialized`] is set. Arrays are grown within their
+/// `minItems` and `maxItems` to get close to the target, then strings without a `format` within
+/// their `minLength` and `maxLength` to close the gap. The result is usually within a few bytes
+/// per string of the target; it only ends up over the target if the smallest conforming
+/// document already is, and further under it if the schema bounds every array and string or a
+/// single array item is larger than the whole target.
+///
+/// ## Example
+/// ```
+/// use json_size::{generate_value, sizeof_val, GenSpec};
+///
+/// let doc = generate_value(&GenSpec::default().seed(7), 64 * 1024);
+/// let size = sizeof_val(&doc);
+/// assert!(size <= 64 * 1024 && size > 63 * 1024);
+/// ```
+pub fn generate_value(spec: &GenSpec, target_bytes: usize) -> Value {
+    let generate = |items: usize, string_len: usize| {
+        let generator = Generator {
+            root: &spec.schema,
+            items,
+            string_len,
+            growable: Cell::new(Growable::default()),
+        };
+        let value = generator.value(&spec.schema, Rng(spec.seed), 0);
+        let size = if spec.serialized {
+            serialized_size(&value)
+        } else {
+            sizeof_val(&value)
+        };
+        (value, size, generator.growable.get())
+    };
+    let items = largest_within(target_bytes, |items| {
+        let (_, size, growable) = generate(items, TYPICAL_STRING_LEN);
+        (size, growable.items)
+    });
+    let string_len = largest_within(target_bytes, |len| {
+        let (_, size, growable) = generate(items, len);
+        (size, growable.strings)
+    });
+    generate(items, string_len).0
+}
+
+/// Length of strings while the array lengths are searched.
+const TYPICAL_STRING_LEN: usize = 16;
+
+/// Largest `x` for which `size(x)` is at most `target`, or 0 if there is none, for a `size` that
+/// never decreases. `size` also returns whether a larger `x` could change the size.
+fn largest_within(target: usize, size: impl Fn(usize) -> (usize, bool)) -> usize {
+    let (mut low, mut high) = (0, 1);
+    match size(0) {
+        (low_size, _) if low_size > target => return 0,
+        (_, false) => return 0,
+        _ => {}
+    }
+    loop {
+        match size(high) {
+            (high_size, _) if high_size > target => break,
+            (_, false) => return high,
+            _ => (low, high) = (high, high.saturating_mul(2)),
+        }
+    }
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if size(mid).0 <= target {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// Whether any array or string was cut short by the lengths a [`Generator`] was given.
+#[derive(Debug, Clone, Copy, Default)]
+struct Growable {
+    items: bool,
+    strings: bool,
+}
+
+/// SplitMix64, seeded per node so a node's content does not depend on the size of its siblings.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, or 0 if `n` is 0.
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next() % n
+        }
+    }
+
+    /// The generator of the child identified by `salt`.
+    fn child(self, salt: u64) -> Rng {
+        let mut rng = Rng(self.0 ^ salt.wrapping_mul(0xff51_afd7_ed55_8ccd));
+        rng.next();
+        rng
+    }
+
+    fn letters(&mut self, len: usize) -> String {
+        (0..len)
+            .map(|_| char::from(b'a' + self.below(26) as u8))
+            .collect()
+    }
+}
+
+/// FNV-1a, to salt the generators of object members.
+fn hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+struct Generator<'a> {
+    root: &'a Value,
+    /// Number of items of arrays, within their bounds.
+    items: usize,
+    /// Length of strings without a `format`, within their bounds.
+    string_len: usize,
+    growable: Cell<Growable>,
+}
+
+/// How many `$ref`s are followed along one path before generating `null`.
+const MAX_REF_DEPTH: usize = 8;
+
+impl Generator<'_> {
+    fn value(&self, schema: &Value, mut rng: Rng, refs: usize) -> Value {
+        let Value::Object(schema) = schema else {
+            return Value::Null;
+        };
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer));
+            return match target {
+                Some(target) if refs < MAX_REF_DEPTH => self.value(target, rng, refs + 1),
+                _ => Value::Null,
+            };
+        }
+        if let Some(c) = schema.get("const") {
+            return c.clone();
+        }
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            if !values.is_empty() {
+                return values[rng.below(values.len() as u64) as usize].clone();
+            }
+        }
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(Value::Array(options)) = schema.get(keyword) {
+                if !options.is_empty() {
+                    let option = &options[rng.below(options.len() as u64) as usize];
+                    return self.value(option, rng.child(1), refs);
+                }
+            }
+        }
+        if let Some(Value::Array(parts)) = schema.get("allOf") {
+            if let Some(first) = parts.first() {
+                return self.value(first, rng, refs);
+            }
+        }
+        let ty = match schema.get("type") {
+            Some(Value::String(ty)) => ty.as_str(),
+            // A nullable type is generated as its first non-null type.
+            Some(Value::Array(types)) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|&ty| ty != "null")
+                .unwrap_or("null"),
+            _ if schema.contains_key("properties") => "object",
+            _ if schema.contains_key("items") || schema.contains_key("prefixItems") => "array",
+            _ if schema.contains_key("maxLength") || schema.contains_key("minLength") => "string",
+            _ => "null",
+        };
+        match ty {
+            "boolean" => Value::Bool(rng.below(2) == 1),
+            "integer" => {
+                let min = schema.get("minimum").and_then(Value::as_i64).unwrap_or(0);
+                let max = schema
+                    .get("maximum")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(min.saturating_add(1_000_000))
+                    .max(min);
+                let span = max.abs_diff(min).saturating_add(1);
+                Value::from(min.wrapping_add_unsigned(rng.below(span)))
+            }
+            "number" => {
+                let min = schema.get("minimum").and_then(Value::as_f64).unwrap_or(0.0);
+                let max = schema
+                    .get("maximum")
+                    .and_then(Value::as_f64)
+                    .unwrap_or(min + 1000.0);
+                let x = min + (max - min) * (rng.below(1 << 20) as f64 / (1 << 20) as f64);
+                Number::from_f64((x * 100.0).round() / 100.0).map_or(Value::Null, Value::Number)
+            }
+            "string" => Value::String(self.string(schema, &mut rng)),
+            "array" => self.array(schema, rng, refs),
+            "object" => self.object(schema, rng, refs),
+            _ => Value::Null,
+        }
+    }
+
+    fn string(&self, schema: &Map<String, Value>, rng: &mut Rng) -> String {
+        let format = schema.get("format").and_then(Value::as_str);
+        let digits = |rng: &mut Rng, n: u64| rng.below(n);
+        match format {
+            Some("date-time") => format!(
+                "20{:02}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                digits(rng, 30),
+                digits(rng, 12) + 1,
+                digits(rng, 28) + 1,
+                digits(rng, 24),
+                digits(rng, 60),
+                digits(rng, 60)
+            ),
+            Some("date") => format!(
+                "20{:02}-{:02}-{:02}",
+                digits(rng, 30),
+                digits(rng, 12) + 1,
+                digits(rng, 28) + 1
+            ),
+            Some("uuid") => {
+                let hex = format!("{:016x}{:016x}", rng.next(), rng.next());
+                format!(
+                    "{}-{}-{}-{}-{}",
+                    &hex[..8],
+                    &hex[8..12],
+                    &hex[12..16],
+                    &hex[16..20],
+                    &hex[20..]
+                )
+            }
+            Some("ipv4") => format!(
+                "10.{}.{}.{}",
+                digits(rng, 256),
+                digits(rng, 256),
+                digits(rng, 256)
+            ),
+            Some("email") => format!("{}@example.com", rng.letters(8)),
+            Some("uri" | "url") => format!("https://example.com/{}", rng.letters(28)),
+            _ => {
+                if let Some(len) = format.and_then(format_len) {
+                    return rng.letters(len);
+                }
+                let min = uint(schema, "minLength").unwrap_or(0);
+                let max = uint(schema, "maxLength").unwrap_or(usize::MAX).max(min);
+                if self.string_len < max {
+                    self.growable.set(Growable {
+                        strings: true,
+                        ..self.growable.get()
+                    });
+                }
+                rng.letters(self.string_len.clamp(min, max))
+            }
+        }
+    }
+
+    fn array(&self, schema: &Map<String, Value>, rng: Rng, refs: usize) -> Value {
+        let prefix: &[Value] = match schema.get("prefixItems").or(schema.get("items")) {
+            Some(Value::Array(tuple)) => tuple,
+            _ => &[],
+        };
+        let items = match schema.get("items") {
+            Some(items @ Value::Object(_)) => Some(items),
+            Some(Value::Bool(false)) => None,
+            _ if prefix.is_empty() => Some(&Value::Bool(true)),
+            _ => None,
+        };
+        let min = uint(schema, "minItems").unwrap_or(0);
+        let max = uint(schema, "maxItems")
+            .unwrap_or(if items.is_some() {
+                usize::MAX
+            } else {
+                prefix.len()
+            })
+            .max(min);
+        if self.items < max {
+            self.growable.set(Growable {
+                items: true,
+                ..self.growable.get()
+            });
+        }
+        let count = self.items.clamp(min, max);
+        (0..count)
+            .map_while(|i| {
+                let item = prefix.get(i).or(items)?;
+                Some(self.value(item, rng.child(i as u64), refs))
+            })
+            .collect()
+    }
+
+    fn object(&self, schema: &Map<String, Value>, rng: Rng, refs: usize) -> Value {
+        let required: Vec<&str> = match schema.get("required") {
+            Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let Some(Value::Object(properties)) = schema.get("properties") else {
+            return Value::Object(Map::new());
+        };
+        properties
+            .iter()
+            .filter_map(|(name, property)| {
+                let mut rng = rng.child(hash(name));
+                // Optional properties are present half of the time.
+                let present = required.contains(&name.as_str()) || rng.below(2) == 1;
+                present.then(|| (name.clone(), self.value(property, rng, refs)))
+            })
+            .collect()
+    }
+}
+
+fn uint(schema: &Map<String, Value>, keyword: &str) -> Option<usize> {
+    schema
+        .get(keyword)
+        .and_then(Value::as_u64)
+        .map(|n| usize::try_from(n).unwrap_or(usize::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lands_just_under_target() {
+        // Each record has a name and up to five tags whose length is searched.
+        let slack = |doc: &Value| 6 * doc.as_array().unwrap().len();
+        for target in [10_000, 50_000, 200_000] {
+            let doc = generate_value(&GenSpec::default(), target);
+            let size = sizeof_val(&doc);
+            assert!(size <= target, "{size} > {target}");
+            assert!(size + slack(&doc) >= target, "{size} is far from {target}");
+        }
+        let serialized = GenSpec::default().serialized(true).seed(3);
+        let doc = generate_value(&serialized, 5000);
+        let size = serialized_size(&doc);
+        assert!(size <= 5000 && size + slack(&doc) >= 5000, "{size}");
+    }
+
+    #[test]
+    fn test_follows_schema() {
+        let spec = GenSpec::new(json!({
+            "type": "object",
+            "required": ["kind", "at", "points"],
+            "properties": {
+                "kind": {"enum": ["a", "b"]},
+                "at": {"type": "string", "format": "date-time"},
+                "points": {"type": "array", "items": {"type": "integer", "minimum": -5, "maximum": 5}},
+                "label": {"type": "string", "maxLength": 4}
+            }
+        }));
+        let doc = generate_value(&spec, 4096);
+        assert!(["a", "b"].contains(&doc["kind"].as_str().unwrap()));
+        assert_eq!(doc["at"].as_str().unwrap().len(), 20);
+        let points = doc["points"].as_array().unwrap();
+        assert!(points.len() > 10);
+        assert!(points
+            .iter()
+            .all(|p| (-5..=5).contains(&p.as_i64().unwrap())));
+        if let Some(label) = doc.get("label") {
+            assert!(label.as_str().unwrap().len() <= 4);
+        }
+        assert_eq!(generate_value(&spec, 4096), doc);
+    }
+
+    #[test]
+    fn test_bounded_schemas_stay_under_target() {
+        let spec =
+            GenSpec::new(json!({"type": "array", "maxItems": 3, "items": {"type": "boolean"}}));
+        assert_eq!(generate_value(&spec, 1 << 20).as_array().unwrap().len(), 3);
+        // The smallest conforming document is returned when even that is too large.
+        let spec = GenSpec::new(json!({"type": "string", "minLength": 100}));
+        assert_eq!(generate_value(&spec, 10).as_str().unwrap().len(), 100);
+    }
+}
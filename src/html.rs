@@ -0,0 +1,196 @@
+//! HTML treemap export of a [`SizeTree`], enabled with the `html` feature.
+
+use crate::{format_bytes, PathSegment, SizeTree, ValueKind};
+use serde::Serialize;
+
+impl SizeTree {
+    /// Renders the tree as a standalone HTML page with an interactive treemap of subtree sizes.
+    ///
+    /// The page needs no network access: the data, styles and script are all inlined. Each
+    /// rectangle is a node sized by its `cumulative_size`; hovering shows its path and size, and
+    /// clicking zooms into it. The breadcrumb at the top zooms back out.
+    ///
+    /// ## Example
+    /// ```
+    /// use serde_json::json;
+    /// use json_size::SizeTree;
+    ///
+    /// let tree = SizeTree::new(&json!({"users": [{"avatar": "iVBORw0KGgo"}]}));
+    /// let html = tree.to_html_treemap();
+    /// assert!(html.starts_with("<!DOCTYPE html>"));
+    /// ```
+    pub fn to_html_treemap(&self) -> String {
+        let data =
+            serde_json::to_string(&Node::from(self)).expect("treemap nodes always serialize");
+        // Markup characters only occur inside JSON strings, where these escapes keep their meaning,
+        // so the data can neither close the script element nor switch the parser out of it.
+        let data = data
+            .replace('<', "\\u003c")
+            .replace('>', "\\u003e")
+            .replace('&', "\\u0026");
+        TEMPLATE.replace("/*DATA*/null", &data)
+    }
+}
+
+/// A node as read by the treemap script.
+#[derive(Serialize)]
+struct Node {
+    /// Last path segment.
+    name: String,
+    path: String,
+    #[serde(rename = "type")]
+    kind: ValueKind,
+    size: usize,
+    /// `size` formatted for display.
+    label: String,
+    children: Vec<Node>,
+}
+
+impl From<&SizeTree> for Node {
+    fn from(tree: &SizeTree) -> Self {
+        Self {
+            name: match tree.path.segments().last() {
+                Some(PathSegment::Key(k)) => k.clone(),
+                Some(PathSegment::Index(i)) => i.to_string(),
+                None => "root".to_owned(),
+            },
+            path: tree.path.to_string(),
+            kind: tree.kind,
+            size: tree.cumulative_size,
+            label: format_bytes(tree.cumulative_size),
+            children: tree.children.iter().map(Node::from).collect(),
+        }
+    }
+}
+
+const TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>JSON size treemap</title>
+<style>
+  body { margin: 0; font: 13px system-ui, sans-serif; color: #222; }
+  #crumbs { padding: 8px 12px; background: #f4f4f4; border-bottom: 1px solid #ddd; }
+  #crumbs a { color: #0366d6; cursor: pointer; text-decoration: none; }
+  #map { position: absolute; top: 36px; left: 0; right: 0; bottom: 0; }
+  .node { position: absolute; box-sizing: border-box; border: 1px solid #fff; overflow: hidden;
+          cursor: pointer; padding: 2px 4px; color: #fff; white-space: nowrap; }
+  .node:hover { filter: brightness(1.15); }
+  .null, .bool, .number { background: #8a8a8a; }
+  .string { background: #d9534f; }
+  .array { background: #5b8def; }
+  .object { background: #3c9d5d; }
+</style>
+</head>
+<body>
+<div id="crumbs"></div>
+<div id="map"></div>
+<script>
+const root = /*DATA*/null;
+const map = document.getElementById("map");
+const crumbs = document.getElementById("crumbs");
+const MAX_DEPTH = 3;
+let trail = [root];
+
+// Lays `nodes` out in the rectangle with the squarified algorithm, returning [node, rect] pairs.
+function squarify(nodes, x, y, w, h) {
+  const total = nodes.reduce((sum, n) => sum + n.size, 0);
+  const out = [];
+  if (total === 0) return out;
+  const scale = (w * h) / total;
+  let items = nodes.filter(n => n.size > 0).sort((a, b) => b.size - a.size);
+  while (items.length > 0) {
+    const side = Math.min(w, h);
+    let row = [], rowArea = 0, worst = Infinity;
+    for (const n of items) {
+      const area = n.size * scale;
+      const next = rowArea + area;
+      const biggest = row.length ? row[0].size * scale : area;
+      const ratio = Math.max((side * side * biggest) / (next * next), (next * next) / (side * side * area));
+      if (row.length && ratio > worst) break;
+      row.push(n); rowArea = next; worst = ratio;
+    }
+    items = items.slice(row.length);
+    const thickness = rowArea / side;
+    let offset = 0;
+    for (const n of row) {
+      const length = (n.size * scale) / thickness;
+      out.push(w >= h
+        ? [n, { x: x, y: y + offset, w: thickness, h: length }]
+        : [n, { x: x + offset, y: y, w: length, h: thickness }]);
+      offset += length;
+    }
+    if (w >= h) { x += thickness; w -= thickness; } else { y += thickness; h -= thickness; }
+  }
+  return out;
+}
+
+function draw(node, rect, depth) {
+  if (rect.w < 2 || rect.h < 2) return;
+  const div = document.createElement("div");
+  div.className = "node " + node.type;
+  Object.assign(div.style, { left: rect.x + "px", top: rect.y + "px", width: rect.w + "px", height: rect.h + "px" });
+  div.title = (node.path || "/") + "\n" + node.label + " (" + node.size + " bytes)";
+  div.textContent = node.name + " " + node.label;
+  div.onclick = event => {
+    event.stopPropagation();
+    const chain = [];
+    for (let n = node; n; n = n.parent) chain.unshift(n);
+    if (chain.length > 1 && node.children.length) { trail = chain; render(); }
+  };
+  map.appendChild(div);
+  if (depth < MAX_DEPTH && node.children.length) {
+    const inset = 16;
+    for (const [child, r] of squarify(node.children, rect.x + 2, rect.y + inset, rect.w - 4, rect.h - inset - 2)) {
+      draw(child, r, depth + 1);
+    }
+  }
+}
+
+function render() {
+  map.replaceChildren();
+  crumbs.replaceChildren();
+  trail.forEach((n, i) => {
+    if (i > 0) crumbs.append(" / ");
+    const link = document.createElement("a");
+    link.textContent = n.name + " (" + n.label + ")";
+    link.onclick = () => { trail = trail.slice(0, i + 1); render(); };
+    crumbs.appendChild(link);
+  });
+  const current = trail[trail.length - 1];
+  draw(current, { x: 0, y: 0, w: map.clientWidth, h: map.clientHeight }, 0);
+}
+
+(function link(n) { for (const c of n.children) { c.parent = n; link(c); } })(root);
+window.onresize = render;
+render();
+</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_embeds_tree_data() {
+        let tree = SizeTree::new(&json!({"name": "ada", "tags": ["a"]}));
+        let html = tree.to_html_treemap();
+        assert!(!html.contains("/*DATA*/"));
+        assert!(html.contains(r#""path":"/tags/0""#));
+        assert!(html.contains(&format!(r#""size":{}"#, tree.cumulative_size)));
+    }
+
+    #[test]
+    fn test_data_cannot_close_the_script() {
+        let html = SizeTree::new(&json!({"</script><b>": 1})).to_html_treemap();
+        assert_eq!(html.matches("</script>").count(), 1);
+        // An escaped script start would keep the real end tag from closing the script.
+        let html = SizeTree::new(&json!({"<!--<script>": 1})).to_html_treemap();
+        assert!(!html.contains("<!--"));
+        assert_eq!(html.matches("<script").count(), 1);
+        assert!(html.contains(r"\u003c!--\u003cscript\u003e"));
+    }
+}
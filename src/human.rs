@@ -0,0 +1,71 @@
+use std::fmt;
+
+const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// A byte count that displays in binary units, e.g. `1.4 MiB`.
+///
+/// Counts below 1024 are printed as whole bytes. Larger counts use one decimal place by default;
+/// an explicit precision such as `{:.3}` overrides it.
+///
+/// ## Example
+/// ```
+/// use json_size::HumanSize;
+///
+/// assert_eq!(HumanSize(512).to_string(), "512 B");
+/// assert_eq!(HumanSize(1_468_006).to_string(), "1.4 MiB");
+/// assert_eq!(format!("{:.2}", HumanSize(1536)), "1.50 KiB");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HumanSize(pub usize);
+
+impl fmt::Display for HumanSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let precision = f.precision().unwrap_or(1);
+        let factor = 10f64.powi(precision as i32);
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        // Move to the next unit when rounding would print e.g. `1024.0 KiB`.
+        while unit + 1 < UNITS.len() && (value * factor).round() / factor >= 1024.0 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{value:.precision$} {}", UNITS[unit])
+    }
+}
+
+impl From<usize> for HumanSize {
+    fn from(bytes: usize) -> Self {
+        Self(bytes)
+    }
+}
+
+/// Formats a byte count in binary units, e.g. `1.4 MiB`. Shorthand for `HumanSize(bytes).to_string()`.
+pub fn format_bytes(bytes: usize) -> String {
+    HumanSize(bytes).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_rounding_promotes_unit() {
+        assert_eq!(format_bytes(1024 * 1024 - 1), "1.0 MiB");
+        assert_eq!(format!("{:.3}", HumanSize(1024 * 1024 - 1)), "1023.999 KiB");
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{SizeEstimator, SizeReport};
+use serde_json::Value;
+use std::borrow::Borrow;
+use std::collections::hash_map::{Entry, HashMap};
+use std::hash::Hash;
+
+/// What a [`SizeIndex`] stores for one document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedSize {
+    /// Estimated size of the document.
+    pub size: usize,
+    /// The document's report, if the index was built with [`SizeIndex::with_reports`].
+    pub report: Option<SizeReport>,
+}
+
+/// A cache of the estimated sizes of many documents, keyed by a document ID.
+///
+/// Documents are sized once, by [`SizeIndex::get_or_compute`] or [`SizeIndex::insert`], and the
+/// running total over every indexed document costs O(1). The index does not hold the documents,
+/// so it cannot notice them change: call [`SizeIndex::insert`] with the new version, or
+/// [`SizeIndex::invalidate`], when one does.
+///
+/// ## Example
+/// ```
+/// use json_size::{sizeof_val, SizeIndex};
+/// use serde_json::json;
+///
+/// let mut index = SizeIndex::new();
+/// let a = json!({"name": "ada", "tags": ["admin"]});
+/// let b = json!({"name": "grace"});
+/// assert_eq!(index.get_or_compute("a", &a).size, sizeof_val(&a));
+/// index.get_or_compute("b", &b);
+/// // Already indexed, so `a` is not sized again.
+/// index.get_or_compute("a", &a);
+/// assert_eq!(index.total_size(), sizeof_val(&a) + sizeof_val(&b));
+///
+/// index.invalidate("a");
+/// assert_eq!(index.total_size(), sizeof_val(&b));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SizeIndex<K> {
+    estimator: SizeEstimator,
+    report_top_n: Option<usize>,
+    entries: HashMap<K, IndexedSize>,
+    total: usize,
+}
+
+impl<K: Eq + Hash> Default for SizeIndex<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash> SizeIndex<K> {
+    pub fn new() -> Self {
+        Self {
+            estimator: SizeEstimator::DEFAULT,
+            report_top_n: None,
+            entries: HashMap::new(),
+            total: 0,
+        }
+    }
+
+    /// Sizes documents with `estimator` instead of [`SizeEstimator::DEFAULT`].
+    ///
+    /// Documents already indexed keep the size they were given.
+    pub fn with_estimator(mut self, estimator: SizeEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Also stores a [`SizeReport`] of each document, keeping its `top_n` heaviest paths.
+    pub fn with_reports(mut self, top_n: usize) -> Self {
+        self.report_top_n = Some(top_n);
+        self
+    }
+
+    /// Returns what is stored for `key`, sizing `v` and indexing it under `key` only if `key`
+    /// is not indexed yet.
+    pub fn get_or_compute(&mut self, key: K, v: &Value) -> &IndexedSize {
+        match self.entries.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let indexed = compute(&self.estimator, self.report_top_n, v);
+                self.total = self.total.saturating_add(indexed.size);
+                entry.insert(indexed)
+            }
+        }
+    }
+
+    /// Sizes `v` and indexes it under `key`, returning what was stored for the previous version.
+    pub fn insert(&mut self, key: K, v: &Value) -> Option<IndexedSize> {
+        let indexed = compute(&self.estimator, self.report_top_n, v);
+        self.total = self.total.saturating_add(indexed.size);
+        let old = self.entries.insert(key, indexed)?;
+        self.total -= old.size;
+        Some(old)
+    }
+
+    /// What is stored for `key`, without computing anything.
+    pub fn get<Q>(&self, key: &Q) -> Option<&IndexedSize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.get(key)
+    }
+
+    /// Removes `key` from the index, returning what was stored for it.
+    pub fn invalidate<Q>(&mut self, key: &Q) -> Option<IndexedSize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let old = self.entries.remove(key)?;
+        self.total -= old.size;
+        Some(old)
+    }
+
+    /// Removes every document for which `stale` returns true.
+    pub fn invalidate_where<F>(&mut self, mut stale: F)
+    where
+        F: FnMut(&K, &IndexedSize) -> bool,
+    {
+        let total = &mut self.total;
+        self.entries.retain(|key, indexed| {
+            let remove = stale(key, indexed);
+            if remove {
+                *total -= indexed.size;
+            }
+            !remove
+        });
+    }
+
+    /// Removes every document.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.total = 0;
+    }
+
+    /// Sum of the sizes of every indexed document.
+    pub fn total_size(&self) -> usize {
+        self.total
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The indexed documents, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &IndexedSize)> {
+        self.entries.iter()
+    }
+}
+
+fn compute(estimator: &SizeEstimator, report_top_n: Option<usize>, v: &Value) -> IndexedSize {
+    match report_top_n {
+        Some(top_n) => {
+            let report = SizeReport::with_estimator(v, top_n, estimator);
+            IndexedSize {
+                size: report.estimated_size,
+                report: Some(report),
+            }
+        }
+        None => IndexedSize {
+            size: estimator.estimate(v),
+            report: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sizeof_val;
+    use serde_json::json;
+
+    #[test]
+    fn test_total_follows_updates() {
+        let mut index = SizeIndex::new();
+        let v1 = json!({"a": [1, 2, 3]});
+        let v2 = json!("x".repeat(100));
+        assert!(index.insert(1, &v1).is_none());
+        index.insert(2, &v2);
+        assert_eq!(index.total_size(), sizeof_val(&v1) + sizeof_val(&v2));
+        // A stale entry is returned as is by get_or_compute, and replaced by insert.
+        assert_eq!(index.get_or_compute(1, &v2).size, sizeof_val(&v1));
+        assert_eq!(index.insert(1, &v2).unwrap().size, sizeof_val(&v1));
+        assert_eq!(index.total_size(), 2 * sizeof_val(&v2));
+        assert_eq!(index.invalidate(&2).unwrap().size, sizeof_val(&v2));
+        assert!(index.invalidate(&2).is_none());
+        assert_eq!(index.total_size(), sizeof_val(&v2));
+        assert_eq!(index.len(), 1);
+        index.clear();
+        assert_eq!(index.total_size(), 0);
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_invalidate_where() {
+        let mut index = SizeIndex::new();
+        for (tenant, doc) in [("a/1", json!(1)), ("a/2", json!("two")), ("b/1", json!([]))] {
+            index.insert(tenant.to_string(), &doc);
+        }
+        index.invalidate_where(|key, _| key.starts_with("a/"));
+        assert!(index.contains("b/1"));
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.total_size(), sizeof_val(&json!([])));
+    }
+
+    #[test]
+    fn test_reports_and_estimator() {
+        let v = json!({"big": "x".repeat(50), "small": 1});
+        let mut index = SizeIndex::new().with_reports(1);
+        let indexed = index.get_or_compute("doc", &v).clone();
+        let report = indexed.report.unwrap();
+        assert_eq!(report, SizeReport::new(&v, 1));
+        assert_eq!(indexed.size, sizeof_val(&v));
+        assert_eq!(index.get("doc").unwrap().size, indexed.size);
+        assert!(SizeIndex::new().insert("doc", &v).is_none());
+
+        let estimator = SizeEstimator::new().target_model(crate::TargetModel::Bits32);
+        let mut small = SizeIndex::new().with_estimator(estimator.clone());
+        assert_eq!(small.get_or_compute(0, &v).size, estimator.estimate(&v));
+    }
+}
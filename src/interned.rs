@@ -0,0 +1,120 @@
+use crate::{sizeof_val, SizeEstimator, STRING_OVERHEAD};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::mem::size_of;
+use std::sync::Arc;
+
+/// The size of a document with its string values interned, created by [`interned_estimate`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct InternedEstimate {
+    /// Size as stored by `serde_json`, as computed by [`crate::sizeof_val`].
+    pub estimated_size: usize,
+    /// Size if every string value were an `Arc<str>` pointing into a table of distinct strings.
+    pub interned_size: usize,
+    /// Number of string values, not counting keys.
+    pub strings: usize,
+    /// Number of distinct string values.
+    pub distinct_strings: usize,
+}
+
+impl InternedEstimate {
+    /// Bytes interning would save, negative if it would cost more than it saves.
+    pub fn savings(&self) -> isize {
+        self.estimated_size as isize - self.interned_size as isize
+    }
+}
+
+/// Reference counts heading the allocation behind an `Arc<str>`.
+const ARC_COUNTS: usize = 2 * size_of::<usize>();
+
+/// Estimates what `v` would take if every string value were stored as a deduplicated `Arc<str>`.
+///
+/// Each string value holds a pointer to a shared allocation instead of its own buffer; each
+/// distinct string is allocated once, with its reference counts. Keys and everything else are
+/// sized as [`crate::sizeof_val`] does, and the table doing the interning is not counted.
+///
+/// ## Example
+/// ```
+/// use json_size::interned_estimate;
+/// use serde_json::json;
+///
+/// let rows: Vec<_> = (0..100)
+///     .map(|i| json!({"id": i, "status": if i % 3 == 0 { "pending" } else { "delivered" }}))
+///     .collect();
+/// let estimate = interned_estimate(&json!(rows));
+/// assert_eq!((estimate.strings, estimate.distinct_strings), (100, 2));
+/// assert!(estimate.savings() > 0);
+/// ```
+pub fn interned_estimate(v: &Value) -> InternedEstimate {
+    let mut tally = Tally::default();
+    tally.add(v);
+    let estimated_size = sizeof_val(v);
+    InternedEstimate {
+        estimated_size,
+        interned_size: estimated_size - tally.buffers + tally.interned,
+        strings: tally.strings,
+        distinct_strings: tally.distinct.len(),
+    }
+}
+
+#[derive(Default)]
+struct Tally<'a> {
+    strings: usize,
+    /// What the string values cost on top of their `Value`s.
+    buffers: usize,
+    /// What the interned strings cost: a pointer per value and an allocation per distinct string.
+    interned: usize,
+    distinct: HashSet<&'a str>,
+}
+
+impl<'a> Tally<'a> {
+    fn add(&mut self, v: &'a Value) {
+        match v {
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+            Value::String(s) => {
+                self.strings += 1;
+                self.buffers += STRING_OVERHEAD + SizeEstimator::DEFAULT.buffer_size(s);
+                self.interned += size_of::<Arc<str>>();
+                if self.distinct.insert(s) {
+                    self.interned += ARC_COUNTS + s.len();
+                }
+            }
+            Value::Array(a) => a.iter().for_each(|item| self.add(item)),
+            Value::Object(o) => o.values().for_each(|item| self.add(item)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_counts_each_distinct_string_once() {
+        let val = json!(["red", "red", "red", "blue"]);
+        let estimate = interned_estimate(&val);
+        assert_eq!(estimate.estimated_size, sizeof_val(&val));
+        let strings = 4 * (size_of::<Value>() + size_of::<Arc<str>>());
+        let table = (ARC_COUNTS + 3) + (ARC_COUNTS + 4);
+        assert_eq!(
+            estimate.interned_size,
+            sizeof_val(&json!([])) + strings + table
+        );
+        assert_eq!(estimate.distinct_strings, 2);
+    }
+
+    #[test]
+    fn test_unique_strings_cost_more() {
+        // Keys are left alone, and a lone string pays for its reference counts.
+        let val = json!({"only": "a long and unique string"});
+        let estimate = interned_estimate(&val);
+        assert_eq!((estimate.strings, estimate.distinct_strings), (1, 1));
+        assert_eq!(
+            estimate.savings(),
+            STRING_OVERHEAD as isize - (size_of::<Arc<str>>() + ARC_COUNTS) as isize
+        );
+        assert_eq!(interned_estimate(&json!([1, null])).savings(), 0);
+    }
+}
@@ -1,11 +1,153 @@
+mod annotate;
+mod assert;
+mod blob;
+mod btree;
+mod budget;
+pub mod capped;
+mod collections;
+mod compressed;
+mod corpus;
+mod dot;
+mod double_encoded;
+mod envelope;
+mod estimator;
+mod explain;
+mod extremes;
+mod generate;
+mod human;
+mod index;
+mod interned;
+mod literal;
+mod minify;
+mod monitor;
+mod numbers;
+mod patch;
+mod path;
+mod pattern;
+mod queue;
+mod quota;
+mod redact;
+mod report;
+mod representation;
+mod safe_drop;
+mod sample;
+mod schema;
+mod serialized;
+mod shared;
+mod slack;
+mod stream;
+mod tracked;
+mod tree;
+mod typed;
+mod utf16;
+mod walk;
+mod wire;
+
+#[cfg(feature = "measure")]
+mod calibration;
+#[cfg(feature = "columnar")]
+mod columnar;
+#[cfg(feature = "html")]
+mod html;
+#[cfg(feature = "measure")]
+mod measure;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "tower")]
+mod middleware;
+#[cfg(feature = "rayon")]
+mod profile;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(feature = "transcode")]
+mod transcode;
+#[cfg(any(feature = "moka", feature = "lru"))]
+pub mod weigher;
+
+pub use annotate::{annotate, AnnotatedNode, AnnotatedValue};
+pub use blob::{blob_report, Blob, BlobEncoding, BlobReport};
+pub use budget::{elements_fitting, elements_fitting_serialized, BudgetedBuilder, Rejected};
+pub use capped::Capped;
+pub use collections::JsonSize;
+pub use compressed::{compressed_size_estimate, Codec};
+pub use corpus::{Corpus, CorpusStats, KeyStat, TypeShare};
+pub use dot::DotOptions;
+pub use double_encoded::{double_encoded_report, DoubleEncoded, DoubleEncodedReport};
+pub use envelope::EnvelopeModel;
+pub use estimator::{
+    sizeof_val_bounds, AllocatorModel, CapacityMode, MapModel, SizeEstimator, StorageModel,
+    TargetModel,
+};
+pub use explain::{explain, Component, Explanation};
+pub use extremes::{extremes, Extreme, Extremes};
+pub use generate::{generate_value, GenSpec};
+pub use human::{format_bytes, HumanSize};
+pub use index::{IndexedSize, SizeIndex};
+pub use interned::{interned_estimate, InternedEstimate};
+pub use minify::{minification_report, MinificationReport, PassSavings};
+pub use monitor::{KeyDelta, SizeDelta, SizeMonitor, Snapshot};
+pub use numbers::{number_report, NumberColumn, NumberReport, NumericType};
+pub use patch::merge_patch_size_delta;
+pub use path::{JsonPath, PathSegment};
+pub use pattern::{sizeof_matching, PatternError, SizePattern};
+pub use queue::SizeBoundedQueue;
+pub use quota::{to_writer_bounded, QuotaExceeded, QuotaWriter};
+pub use redact::{redaction_report, RedactionReport};
+pub use report::{AllocationCounts, EscapeStats, PathSize, SizeReport, StringEncodingStats};
+pub use representation::{representation_comparison, RepresentationComparison};
+pub use safe_drop::safe_drop;
+pub use sample::{sample_by_size, sample_by_size_seeded, SizeSample};
+pub use schema::{estimate_from_schema, SchemaAssumptions, SizeRange};
+pub use serialized::{bloat_factor, escape_inflation, serialized_size};
+pub use shared::{sizeof_shared, SharedSize, SharedSizer, SharedValue};
+pub use slack::{slack_report, SlackReport};
+pub use stream::SizedStream;
+pub use tracked::SizeTrackedMap;
+pub use tree::{SizeTree, SizeTreeIter, ValueKind};
+pub use typed::{compare_with_typed, TypedComparison};
+pub use utf16::{utf16_serialized_len, utf16_size, JsHeapModel};
+pub use walk::{keys_by_size, largest_leaf, paths_over, walk_sizes};
+pub use wire::{wire_size_comparison, FormatComparison, WireFormat};
+
+#[cfg(feature = "measure")]
+pub use calibration::Calibration;
+#[cfg(feature = "columnar")]
+pub use columnar::{estimate_columnar, ColumnEstimate, ColumnType, ColumnarEstimate};
+#[cfg(feature = "measure")]
+pub use measure::{measure_actual, CountingAllocator};
+#[cfg(feature = "metrics")]
+pub use metrics::{record_json_size, SizeMetrics};
+#[cfg(feature = "tower")]
+pub use middleware::{JsonSizeLimit, JsonSizeLimitLayer};
+#[cfg(feature = "rayon")]
+pub use profile::{profile_dir, DirProfile, FileError, ProfileOptions};
+#[cfg(feature = "transcode")]
+pub use transcode::{transcode_sized, CountingWriter, SizingSerializer};
+
+#[doc(hidden)]
+pub mod __private {
+    pub mod literal {
+        pub use crate::literal::{array, literal, object, scalar};
+    }
+    #[cfg(feature = "tracing")]
+    pub use tracing;
+}
+
+use serde_json::Value;
+use std::mem::size_of;
+
+const STRING_OVERHEAD: usize = size_of::<String>();
+
 /// Calculates the approximate size of a `serde_json::Value` in bytes.
 ///
 /// This function estimates the memory consumption of the given `serde_json::Value` object, including its nested structures. The estimation is based on the following assumptions:
 ///
 /// - Null, Boolean, and Number types have no additional size overhead.
 /// - String sizes are based on the capacity of the internal buffer.
-/// - Array sizes are calculated recursively based on the sum of each element's size.
-/// - Object sizes are calculated recursively, summing the size of each key-value pair. An additional crude approximation of map entry overhead is included.
+/// - Array sizes are calculated recursively based on the sum of each element's size, plus the spare capacity of the backing `Vec`.
+/// - Object sizes are calculated recursively, summing the size of each key-value pair, plus the nodes of the `BTreeMap` holding them (see [`MapModel::BTree`]).
 ///
 /// ## Parameters
 /// - `v`: A reference to a `serde_json::Value` whose size will be estimated.
@@ -31,31 +173,107 @@
 /// ## Caveats
 /// - This estimation might not be precise for objects using arbitrary precision numbers.
 /// - The estimation might vary depending on the specific architecture and implementation of the `serde_json` crate.
+/// - The sum saturates at `usize::MAX` instead of wrapping. On 32-bit and wasm32 targets, use
+///   [`sizeof_val_u64`] or [`sizeof_val_checked`] for documents that may exceed 4 GiB.
+pub fn sizeof_val(v: &Value) -> usize {
+    usize::try_from(sizeof_val_u64(v)).unwrap_or(usize::MAX)
+}
+
+/// Same as [`sizeof_val`], but accumulates in `u64` and saturates at `u64::MAX`.
+pub fn sizeof_val_u64(v: &Value) -> u64 {
+    // All terms are non-negative, so an overflowing sum saturates to the maximum.
+    sizeof_val_checked(v).unwrap_or(u64::MAX)
+}
+
+/// Same as [`sizeof_val`], but accumulates in `u64` and returns `None` if the sum overflows.
 ///
-/// ## Implementation
-use serde_json::Value;
-use std::mem::size_of;
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::{sizeof_val, sizeof_val_checked};
+///
+/// let val = json!(["chatbot", "API"]);
+/// assert_eq!(sizeof_val_checked(&val), Some(sizeof_val(&val) as u64));
+/// ```
+pub fn sizeof_val_checked(v: &Value) -> Option<u64> {
+    SizeEstimator::DEFAULT.estimate_checked(v)
+}
 
-const STRING_OVERHEAD: usize = size_of::<String>();
-const MAP_ENTRY_OVERHEAD: usize = size_of::<usize>() * 3;
+/// Sums [`sizeof_val`] over `values`, saturating at `usize::MAX`.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::{sizeof_iter, sizeof_val};
+///
+/// let queue = vec![json!("a"), json!({"b": 1})];
+/// assert_eq!(sizeof_iter(&queue), sizeof_val(&queue[0]) + sizeof_val(&queue[1]));
+/// ```
+pub fn sizeof_iter<'a>(values: impl IntoIterator<Item = &'a Value>) -> usize {
+    values
+        .into_iter()
+        .fold(0usize, |acc, v| acc.saturating_add(sizeof_val(v)))
+}
 
-pub fn sizeof_val(v: &Value) -> usize {
-    size_of::<Value>()
-        + match v {
-            Value::Null => 0,
-            Value::Bool(_) => 0,
-            Value::Number(_) => 0, // incorrect if arbitrary_precision is enabled
-            Value::String(s) => STRING_OVERHEAD + s.capacity(),
-            Value::Array(a) => a.iter().map(sizeof_val).sum(),
-            Value::Object(o) => o
-                .iter()
-                .map(|(k, v)| STRING_OVERHEAD + k.capacity() + sizeof_val(v) + MAP_ENTRY_OVERHEAD)
-                .sum(),
+/// Returns `true` if [`sizeof_iter`] of `values` is at most `budget`.
+///
+/// Iteration stops as soon as the budget is exceeded, even in the middle of a value, so the cost
+/// is proportional to the budget rather than to the size of the input.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::{fits_in_budget, sizeof_iter};
+///
+/// let queue: Vec<_> = (0..1_000_000).map(|i| json!(i)).collect();
+/// assert!(!fits_in_budget(&queue, 1024));
+/// assert!(fits_in_budget(&queue[..10], sizeof_iter(&queue[..10])));
+/// ```
+pub fn fits_in_budget<'a>(values: impl IntoIterator<Item = &'a Value>, budget: usize) -> bool {
+    let mut remaining = budget;
+    values.into_iter().all(|v| charge(v, &mut remaining))
+}
+
+/// Subtracts the size of `v` from `remaining`, returning `false` as soon as it runs out.
+pub(crate) fn charge(v: &Value, remaining: &mut usize) -> bool {
+    if !take(remaining, own_size(v)) {
+        return false;
+    }
+    match v {
+        Value::Array(a) => a.iter().all(|item| charge(item, remaining)),
+        Value::Object(o) => o
+            .iter()
+            .all(|(k, item)| take(remaining, entry_overhead(k)) && charge(item, remaining)),
+        _ => true,
+    }
+}
+
+fn take(remaining: &mut usize, bytes: usize) -> bool {
+    match remaining.checked_sub(bytes) {
+        Some(left) => {
+            *remaining = left;
+            true
         }
+        None => false,
+    }
 }
 
-#[cfg(test)]
+/// Size attributed to a node itself, excluding its children.
+pub(crate) fn own_size(v: &Value) -> usize {
+    SizeEstimator::DEFAULT.own_size(v)
+}
+
+/// Cost of an object entry on top of its value: the key buffer and the map bookkeeping.
+pub(crate) fn entry_overhead(k: &String) -> usize {
+    SizeEstimator::DEFAULT.entry_overhead(k)
+}
+
+/// Map bookkeeping charged to an object of `len` members as a whole, see [`own_size`].
+pub(crate) fn map_overhead(len: usize) -> usize {
+    SizeEstimator::DEFAULT.map_overhead(len)
+}
 
+#[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
@@ -100,27 +318,46 @@ mod tests {
     #[test]
     fn test_sizeof_val_object() {
         let val = json!({"key": "value"});
+        // A single B-tree leaf, with the key and value slots counted with the member.
+        let leaf = btree::node_sizes(size_of::<String>(), size_of::<Value>(), TargetModel::Host).0;
         let expected_size = std::mem::size_of::<serde_json::Value>()
+            + leaf
             + String::from("key").capacity()
             + sizeof_val(&json!("value"))
-            + std::mem::size_of::<String>()
-            + std::mem::size_of::<usize>() * 3;
+            - std::mem::size_of::<serde_json::Value>();
         assert_eq!(sizeof_val(&val), expected_size);
     }
 
+    #[test]
+    fn test_sizeof_val_u64_matches_sizeof_val() {
+        let val = json!({"a": [1, "two", {"three": null}]});
+        assert_eq!(sizeof_val_u64(&val), sizeof_val(&val) as u64);
+        assert_eq!(sizeof_val_checked(&val), Some(sizeof_val(&val) as u64));
+    }
+
+    #[test]
+    fn test_fits_in_budget_boundary() {
+        let values = [json!({"a": "bc"}), json!([1, 2])];
+        let total = sizeof_iter(&values);
+        assert!(fits_in_budget(&values, total));
+        assert!(!fits_in_budget(&values, total - 1));
+        assert!(fits_in_budget(std::iter::empty(), 0));
+    }
+
     #[test]
     fn test_sizeof_val_complex_object() {
         let val = json!({
             "name": "json_size",
             "details": {"year": 2022, "version": "v4"}
         });
+        let leaf = btree::node_sizes(size_of::<String>(), size_of::<Value>(), TargetModel::Host).0;
         let expected_size = std::mem::size_of::<serde_json::Value>()
+            + leaf
             + String::from("name").capacity()
             + sizeof_val(&json!("json_size"))
             + String::from("details").capacity()
             + sizeof_val(&json!({"year": 2022, "version": "v4"}))
-            + std::mem::size_of::<String>() * 2
-            + std::mem::size_of::<usize>() * 6; // Assuming each object entry overhead is 3 usize
+            - std::mem::size_of::<serde_json::Value>() * 2; // Both values live in the leaf
         assert_eq!(sizeof_val(&val), expected_size);
     }
 }
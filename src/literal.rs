@@ -0,0 +1,166 @@
+//! Compile-time sizing of JSON literals, see [`json_size!`](crate::json_size).
+
+use crate::SizeEstimator;
+use serde_json::Value;
+use std::mem::size_of;
+
+/// Computes [`crate::sizeof_val`] of a `json!` literal in a `const` context, without building it.
+///
+/// The input is written as for `serde_json::json!`, but may only contain literals: `null`,
+/// booleans, number and string literals, and arrays and objects of them. Object keys must be
+/// string literals and are assumed to be distinct. The result is the size of the value `json!`
+/// builds from the same tokens.
+///
+/// ## Example
+/// ```
+/// use json_size::{json_size, sizeof_val};
+/// use serde_json::json;
+///
+/// const DEFAULT_CONFIG: usize = json_size!({"retries": 3, "hosts": ["a.example", "b.example"]});
+/// const _: () = assert!(DEFAULT_CONFIG < 4096);
+///
+/// let config = json!({"retries": 3, "hosts": ["a.example", "b.example"]});
+/// assert_eq!(DEFAULT_CONFIG, sizeof_val(&config));
+/// ```
+#[macro_export]
+macro_rules! json_size {
+    // Collects the sizes of array elements.
+    (@elements [$($out:expr,)*]) => {
+        [$($out,)*]
+    };
+    (@elements [$($out:expr,)*] - $number:literal $(, $($rest:tt)*)?) => {
+        $crate::json_size!(@elements [$($out,)* $crate::json_size!(-$number),] $($($rest)*)?)
+    };
+    (@elements [$($out:expr,)*] $element:tt $(, $($rest:tt)*)?) => {
+        $crate::json_size!(@elements [$($out,)* $crate::json_size!($element),] $($($rest)*)?)
+    };
+
+    // Collects the key lengths and value sizes of object members.
+    (@members [$($out:expr,)*]) => {
+        [$($out,)*]
+    };
+    (@members [$($out:expr,)*] $key:literal : - $number:literal $(, $($rest:tt)*)?) => {
+        $crate::json_size!(
+            @members [$($out,)* ($key.len(), $crate::json_size!(-$number)),] $($($rest)*)?
+        )
+    };
+    (@members [$($out:expr,)*] $key:literal : $value:tt $(, $($rest:tt)*)?) => {
+        $crate::json_size!(
+            @members [$($out,)* ($key.len(), $crate::json_size!($value)),] $($($rest)*)?
+        )
+    };
+
+    (null) => {
+        $crate::__private::literal::scalar()
+    };
+    (true) => {
+        $crate::__private::literal::scalar()
+    };
+    (false) => {
+        $crate::__private::literal::scalar()
+    };
+    ([ $($elements:tt)* ]) => {
+        $crate::__private::literal::array(&$crate::json_size!(@elements [] $($elements)*))
+    };
+    ({ $($members:tt)* }) => {
+        $crate::__private::literal::object(&$crate::json_size!(@members [] $($members)*))
+    };
+    ($literal:literal) => {
+        $crate::__private::literal::literal(
+            ::core::stringify!($literal),
+            ::core::concat!($literal),
+        )
+    };
+    ($($other:tt)*) => {
+        ::core::compile_error!(
+            "json_size! only accepts null, booleans, number and string literals, and arrays and \
+             objects of them"
+        )
+    };
+}
+
+const ESTIMATOR: SizeEstimator = SizeEstimator::DEFAULT;
+
+/// Size of `null`, a boolean or a number.
+pub const fn scalar() -> usize {
+    size_of::<Value>()
+}
+
+/// Size of a number or string literal, given its source text and its value as `concat!` renders
+/// it.
+pub const fn literal(source: &str, value: &str) -> usize {
+    match source.as_bytes() {
+        // `json!` copies string literals into a `String` of the same capacity.
+        [b'"' | b'r', ..] => scalar() + ESTIMATOR.string_buffer_size(value.len()),
+        [b'\'' | b'b' | b'c', ..] => panic!("json_size! does not support char or byte literals"),
+        _ => scalar(),
+    }
+}
+
+/// Size of an array with elements of the given sizes.
+pub const fn array(elements: &[usize]) -> usize {
+    let mut size =
+        scalar().saturating_add(ESTIMATOR.array_overhead(elements.len(), elements.len()));
+    let mut i = 0;
+    while i < elements.len() {
+        size = size.saturating_add(elements[i]);
+        i += 1;
+    }
+    size
+}
+
+/// Size of an object with members of the given key lengths and value sizes.
+pub const fn object(members: &[(usize, usize)]) -> usize {
+    let mut size = scalar().saturating_add(ESTIMATOR.map_overhead(members.len()));
+    let mut i = 0;
+    while i < members.len() {
+        let (key, value) = members[i];
+        size = size
+            .saturating_add(ESTIMATOR.entry_overhead_for_len(key))
+            .saturating_add(value);
+        i += 1;
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sizeof_val;
+    use serde_json::json;
+
+    #[test]
+    fn test_matches_sizeof_val() {
+        assert_eq!(json_size!(null), sizeof_val(&json!(null)));
+        assert_eq!(json_size!(-1.5), sizeof_val(&json!(-1.5)));
+        assert_eq!(json_size!("tab\there"), sizeof_val(&json!("tab\there")));
+        assert_eq!(json_size!(r#"raw"#), sizeof_val(&json!(r#"raw"#)));
+        assert_eq!(json_size!([]), sizeof_val(&json!([])));
+        assert_eq!(
+            json_size!([1, -2, "three", [true, false], {},]),
+            sizeof_val(&json!([1, -2, "three", [true, false], {},]))
+        );
+        assert_eq!(
+            json_size!({"a": -1, "é": {"nested": [null, "x"]}, "b": "long string value"}),
+            sizeof_val(&json!({"a": -1, "é": {"nested": [null, "x"]}, "b": "long string value"}))
+        );
+    }
+
+    #[test]
+    fn test_large_object_uses_tree_model() {
+        let size = json_size!({
+            "k00": 0, "k01": 1, "k02": 2, "k03": 3, "k04": 4, "k05": 5, "k06": 6, "k07": 7,
+            "k08": 8, "k09": 9, "k10": 10, "k11": 11, "k12": 12, "k13": 13, "k14": 14
+        });
+        let val = json!({
+            "k00": 0, "k01": 1, "k02": 2, "k03": 3, "k04": 4, "k05": 5, "k06": 6, "k07": 7,
+            "k08": 8, "k09": 9, "k10": 10, "k11": 11, "k12": 12, "k13": 13, "k14": 14
+        });
+        assert_eq!(size, sizeof_val(&val));
+    }
+
+    #[test]
+    fn test_const_context() {
+        const SIZE: usize = json_size!({"enabled": true});
+        assert_eq!(SIZE, sizeof_val(&json!({"enabled": true})));
+    }
+}
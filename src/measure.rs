@@ -0,0 +1,141 @@
+//! Ground-truth heap measurement, enabled with the `measure` feature.
+
+use serde_json::Value;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+thread_local! {
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+fn track(delta: isize) {
+    // `try_with` because the allocator is still called while thread locals are torn down.
+    let _ = LIVE_BYTES.try_with(|live| live.set(live.get() + delta));
+}
+
+/// A `GlobalAlloc` wrapper that counts the bytes live on each thread.
+///
+/// It must be installed as the global allocator for [`measure_actual`] to work:
+///
+/// ```
+/// use json_size::CountingAllocator;
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static GLOBAL: CountingAllocator = CountingAllocator::new(System);
+/// # fn main() {}
+/// ```
+#[derive(Debug, Default)]
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+
+impl<A> CountingAllocator<A> {
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        INSTALLED.store(true, Ordering::Relaxed);
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        INSTALLED.store(true, Ordering::Relaxed);
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            track(layout.size() as isize);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        track(-(layout.size() as isize));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            track(new_size as isize - layout.size() as isize);
+        }
+        new_ptr
+    }
+}
+
+/// Runs `f` and returns the value it built together with the heap bytes that value retains.
+///
+/// The measurement is the net number of bytes allocated on the current thread while `f` runs, so
+/// temporaries freed before `f` returns are not counted. It only covers the heap: the root
+/// `Value` itself lives on the stack, so compare against
+/// `sizeof_val(&v) - size_of::<Value>()` when calibrating the estimator.
+///
+/// ## Panics
+/// Panics if [`CountingAllocator`] is not installed as the global allocator.
+///
+/// ## Example
+/// ```
+/// use json_size::{measure_actual, sizeof_val, CountingAllocator};
+/// use serde_json::json;
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static GLOBAL: CountingAllocator = CountingAllocator::new(System);
+///
+/// fn main() {
+///     let (val, heap) = measure_actual(|| json!({"name": "OpenAI", "services": ["chatbot", "API"]}));
+///     println!("measured {heap} heap bytes, estimated {}", sizeof_val(&val));
+/// }
+/// ```
+pub fn measure_actual<F: FnOnce() -> Value>(f: F) -> (Value, usize) {
+    drop(std::hint::black_box(Box::new(0u8)));
+    assert!(
+        INSTALLED.load(Ordering::Relaxed),
+        "measure_actual requires json_size::CountingAllocator as the global allocator"
+    );
+    let before = LIVE_BYTES.with(Cell::get);
+    let value = f();
+    let after = LIVE_BYTES.with(Cell::get);
+    (value, after.saturating_sub(before).max(0) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator::new(System);
+
+    #[test]
+    fn test_measures_string_buffer() {
+        let (val, heap) = measure_actual(|| Value::String(String::with_capacity(1000)));
+        assert_eq!(heap, 1000);
+        assert_eq!(val.as_str(), Some(""));
+    }
+
+    #[test]
+    fn test_temporaries_are_not_counted() {
+        let (_, heap) = measure_actual(|| {
+            let scratch = vec![0u8; 4096];
+            json!(scratch.len())
+        });
+        assert_eq!(heap, 0);
+    }
+
+    #[test]
+    fn test_clone_of_nested_value() {
+        let original = json!({"a": ["x", "yy"], "b": {"c": "zzz"}});
+        let (_, heap) = measure_actual(|| original.clone());
+        assert!(heap > 0);
+    }
+}
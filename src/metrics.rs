@@ -0,0 +1,155 @@
+//! `metrics` integration, enabled with the `metrics` feature.
+
+use crate::SizeEstimator;
+use metrics::Label;
+use serde_json::Value;
+
+/// Records the estimated size of `v` into the histogram `name` and returns the size.
+///
+/// ## Example
+/// ```
+/// use json_size::record_json_size;
+/// use serde_json::json;
+///
+/// record_json_size("ingest.payload", &json!({"id": 1}));
+/// ```
+pub fn record_json_size(name: &'static str, v: &Value) -> usize {
+    let size = crate::sizeof_val(v);
+    metrics::histogram!(name).record(size as f64);
+    size
+}
+
+/// Records estimated sizes under a fixed metric name and label set.
+///
+/// ## Example
+/// ```
+/// use json_size::SizeMetrics;
+/// use serde_json::json;
+///
+/// let metrics = SizeMetrics::new("ingest.payload")
+///     .label("service", "ingest")
+///     .label("tenant", "acme");
+/// metrics.record(&json!({"id": 1}));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SizeMetrics {
+    name: String,
+    labels: Vec<Label>,
+    estimator: SizeEstimator,
+}
+
+impl SizeMetrics {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            labels: Vec::new(),
+            estimator: SizeEstimator::new(),
+        }
+    }
+
+    /// Adds a label to every recorded metric.
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push(Label::new(key.into(), value.into()));
+        self
+    }
+
+    /// Sets the estimator used to compute sizes.
+    pub fn estimator(mut self, estimator: SizeEstimator) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Records the estimated size of `v` into a histogram and returns the size.
+    pub fn record(&self, v: &Value) -> usize {
+        let size = self.estimator.estimate(v);
+        metrics::histogram!(self.name.clone(), self.labels.clone()).record(size as f64);
+        size
+    }
+
+    /// Sets a gauge to the estimated size of `v` and returns the size.
+    ///
+    /// Use this for long-lived documents, where the current size matters more than the
+    /// distribution.
+    pub fn set_gauge(&self, v: &Value) -> usize {
+        let size = self.estimator.estimate(v);
+        metrics::gauge!(self.name.clone(), self.labels.clone()).set(size as f64);
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics::{
+        Counter, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+        SharedString, Unit,
+    };
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    /// Remembers the last key and value recorded into any histogram or gauge.
+    #[derive(Default)]
+    struct LastValue(Mutex<Option<(Key, f64)>>);
+
+    struct Handle(Arc<LastValue>, Key);
+
+    impl HistogramFn for Handle {
+        fn record(&self, value: f64) {
+            *self.0 .0.lock().unwrap() = Some((self.1.clone(), value));
+        }
+    }
+
+    impl GaugeFn for Handle {
+        fn increment(&self, _: f64) {}
+        fn decrement(&self, _: f64) {}
+        fn set(&self, value: f64) {
+            *self.0 .0.lock().unwrap() = Some((self.1.clone(), value));
+        }
+    }
+
+    struct TestRecorder(Arc<LastValue>);
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn register_counter(&self, _: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::noop()
+        }
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(Arc::new(Handle(self.0.clone(), key.clone())))
+        }
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::from_arc(Arc::new(Handle(self.0.clone(), key.clone())))
+        }
+    }
+
+    fn last(f: impl FnOnce()) -> (Key, f64) {
+        let last = Arc::new(LastValue::default());
+        metrics::with_local_recorder(&TestRecorder(last.clone()), f);
+        let recorded = last.0.lock().unwrap().take();
+        recorded.expect("nothing recorded")
+    }
+
+    #[test]
+    fn test_record_json_size() {
+        let val = json!({"id": 1});
+        let (key, value) = last(|| {
+            record_json_size("ingest.payload", &val);
+        });
+        assert_eq!(key.name(), "ingest.payload");
+        assert_eq!(value, crate::sizeof_val(&val) as f64);
+    }
+
+    #[test]
+    fn test_labels_and_gauge() {
+        let val = json!(["a"]);
+        let metrics = SizeMetrics::new("session.state").label("tenant", "acme");
+        let (key, value) = last(|| {
+            metrics.set_gauge(&val);
+        });
+        let labels: Vec<_> = key.labels().map(|l| (l.key(), l.value())).collect();
+        assert_eq!(labels, [("tenant", "acme")]);
+        assert_eq!(value, crate::sizeof_val(&val) as f64);
+    }
+}
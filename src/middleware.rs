@@ -0,0 +1,237 @@
+//! Tower middleware rejecting JSON requests by estimated in-memory size, enabled with the `tower`
+//! feature.
+//!
+//! The `Content-Length` of a request says little about the memory its body takes once parsed:
+//! `[0,0,0]` is 7 bytes on the wire and well over a hundred as a `Value`. [`JsonSizeLimitLayer`]
+//! buffers the body of every JSON request and runs it through [`CappedSeed`], answering
+//! `413 Payload Too Large` as soon as the estimate crosses the limit, before the inner service
+//! sees the request.
+//!
+//! ## Example
+//! ```
+//! use json_size::JsonSizeLimitLayer;
+//! use tower::Layer;
+//!
+//! # #[derive(Clone)] struct Handler;
+//! // Reject documents that would take more than 1 MiB as `serde_json::Value`s.
+//! let service = JsonSizeLimitLayer::new(1 << 20).layer(Handler);
+//! assert_eq!(service.limit(), 1 << 20);
+//! ```
+
+use crate::capped::CappedSeed;
+use bytes::Bytes;
+use http::header::CONTENT_TYPE;
+use http::{HeaderMap, Request, Response, StatusCode};
+use http_body::Body;
+use http_body_util::{BodyExt, Either, Full};
+use serde::de::DeserializeSeed;
+use serde_json::error::Category;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// A [`Layer`] wrapping services in a [`JsonSizeLimit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonSizeLimitLayer {
+    limit: usize,
+}
+
+impl JsonSizeLimitLayer {
+    /// Rejects JSON requests whose body would take more than `limit` bytes as a `Value`, as
+    /// estimated by [`crate::sizeof_val`].
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl<S> Layer<S> for JsonSizeLimitLayer {
+    type Service = JsonSizeLimit<S>;
+
+    fn layer(&self, inner: S) -> JsonSizeLimit<S> {
+        JsonSizeLimit {
+            inner,
+            limit: self.limit,
+        }
+    }
+}
+
+/// A service answering `413 Payload Too Large` to JSON requests whose body would exceed an
+/// estimated in-memory size, created by [`JsonSizeLimitLayer`].
+///
+/// Requests with a `Content-Type` of `application/json` or `application/*+json` are buffered and
+/// checked; the inner service receives the buffered body. Other requests are passed through with
+/// their body untouched. Bodies that are not valid JSON are passed through too, leaving it to the
+/// handler to reject them, and a body that fails to be read is answered with
+/// `400 Bad Request`.
+///
+/// The whole body is buffered before it is checked, so combine this with a limit on the raw body
+/// length, such as `tower_http::limit::RequestBodyLimitLayer`, to bound the buffer itself.
+#[derive(Debug, Clone)]
+pub struct JsonSizeLimit<S> {
+    inner: S,
+    limit: usize,
+}
+
+impl<S> JsonSizeLimit<S> {
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+impl<S, B, ResBody> Service<Request<B>> for JsonSizeLimit<S>
+where
+    S: Service<Request<Either<Full<Bytes>, B>>, Response = Response<ResBody>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    B: Body + Send + 'static,
+    B::Data: Send,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Response<ResBody>, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        // Call the clone that was not driven to readiness, keeping the ready one for the call.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        if !is_json(request.headers()) {
+            return Box::pin(inner.call(request.map(Either::Right)));
+        }
+        let limit = self.limit;
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let bytes = match body.collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(_) => return Ok(status(StatusCode::BAD_REQUEST)),
+            };
+            if exceeds(&bytes, limit) {
+                return Ok(status(StatusCode::PAYLOAD_TOO_LARGE));
+            }
+            inner
+                .call(Request::from_parts(parts, Either::Left(Full::new(bytes))))
+                .await
+        })
+    }
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    let Some((kind, subtype)) = mime.split_once('/') else {
+        return false;
+    };
+    let subtype = subtype.to_ascii_lowercase();
+    kind.eq_ignore_ascii_case("application") && (subtype == "json" || subtype.ends_with("+json"))
+}
+
+/// Whether `body` is a JSON document whose estimated size exceeds `limit`. The seed accepts any
+/// JSON, so a data error can only be the cap being crossed.
+fn exceeds(body: &[u8], limit: usize) -> bool {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    match CappedSeed::new(limit).deserialize(&mut deserializer) {
+        Ok(_) => false,
+        Err(e) => e.classify() == Category::Data,
+    }
+}
+
+fn status<B: Default>(status: StatusCode) -> Response<B> {
+    let mut response = Response::new(B::default());
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sizeof_val;
+    use serde_json::json;
+    use std::convert::Infallible;
+    use std::task::Waker;
+
+    /// Echoes the length of the body it receives.
+    #[derive(Clone)]
+    struct Echo;
+
+    impl<B: Body<Data = Bytes> + Send + 'static> Service<Request<B>> for Echo {
+        type Response = Response<String>;
+        type Error = Infallible;
+        type Future = BoxFuture<Result<Response<String>, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<B>) -> Self::Future {
+            Box::pin(async move {
+                let body = request.into_body().collect().await.ok().unwrap().to_bytes();
+                Ok(Response::new(body.len().to_string()))
+            })
+        }
+    }
+
+    /// Drives a future that never waits, as every body here is already in memory.
+    fn ready<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("the future was not ready"),
+        }
+    }
+
+    fn send(limit: usize, content_type: &str, body: &str) -> Response<String> {
+        let mut service = JsonSizeLimitLayer::new(limit).layer(Echo);
+        let request = Request::builder()
+            .header(CONTENT_TYPE, content_type)
+            .body(Full::new(Bytes::from(body.to_owned())))
+            .unwrap();
+        ready(service.call(request)).unwrap()
+    }
+
+    #[test]
+    fn test_rejects_oversized_json() {
+        let body = "[0,0,0,0,0,0,0,0,0,0]";
+        let size = sizeof_val(&json!([0, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+        // The body is far below the limit on the wire, but not in memory.
+        assert!(body.len() * 10 < size);
+        let ok = send(size, "application/json", body);
+        assert_eq!(ok.status(), StatusCode::OK);
+        assert_eq!(ok.body(), &body.len().to_string());
+        let rejected = send(size - 1, "application/json; charset=utf-8", body);
+        assert_eq!(rejected.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(rejected.body(), "");
+    }
+
+    #[test]
+    fn test_only_checks_json() {
+        let body = r#"{"blob": "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx"}"#;
+        assert_eq!(send(8, "text/plain", body).status(), StatusCode::OK);
+        assert_eq!(
+            send(8, "application/problem+JSON", body).status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        // Invalid JSON is left for the handler to reject.
+        assert_eq!(
+            send(1024, "application/json", "{oops").status(),
+            StatusCode::OK
+        );
+    }
+}
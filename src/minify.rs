@@ -0,0 +1,213 @@
+use crate::{
+    entry_overhead, escape_inflation, map_overhead, own_size, serialized_size, sizeof_val,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Bytes one normalization pass would save, see [`MinificationReport`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PassSavings {
+    /// Object members removed by the pass.
+    pub removed_members: usize,
+    /// Bytes saved in the compact serialization.
+    pub serialized: usize,
+    /// Bytes saved in the estimated in-memory size.
+    pub estimated: usize,
+}
+
+/// What normalizing a document before storage would save, created by [`minification_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MinificationReport {
+    /// Compact serialized size of the document as is.
+    pub serialized_size: usize,
+    /// Estimated in-memory size of the document as is.
+    pub estimated_size: usize,
+    /// Removing members whose value is `null`.
+    pub drop_nulls: PassSavings,
+    /// Removing members whose value is `{}` or `[]`.
+    pub drop_empty: PassSavings,
+    /// Removing members whose value is `false`, `0` or `""`.
+    pub drop_defaults: PassSavings,
+    /// Running all three passes together. This is usually more than the sum of the passes,
+    /// because members emptied by one pass are then removed as empty.
+    pub all: PassSavings,
+}
+
+#[derive(Clone, Copy)]
+struct Passes {
+    nulls: bool,
+    empty: bool,
+    defaults: bool,
+}
+
+/// Compares the compact serialized size and the estimated size of `v` before and after each
+/// normalization pass, without modifying `v`.
+///
+/// Passes only remove object members; array elements are kept so indices stay meaningful, and
+/// the root is never removed. Containers are normalized bottom-up, so with `drop_empty` an
+/// object whose members were all removed is removed as well.
+///
+/// ## Example
+/// ```
+/// use json_size::minification_report;
+/// use serde_json::json;
+///
+/// let val = json!({"id": 1, "middle_name": null, "tags": [], "flags": {"beta": false}});
+/// let report = minification_report(&val);
+/// assert_eq!(report.drop_nulls.serialized, r#","middle_name":null"#.len());
+/// assert_eq!(report.drop_empty.removed_members, 1);
+/// assert_eq!(report.all.removed_members, 4);
+/// ```
+pub fn minification_report(v: &Value) -> MinificationReport {
+    let full_serialized = serialized_size(v);
+    let full_estimated = sizeof_val(v);
+    let savings = |passes| {
+        let mut removed_members = 0;
+        let kept = normalized(v, passes, &mut removed_members);
+        PassSavings {
+            removed_members,
+            serialized: full_serialized - kept.serialized,
+            estimated: full_estimated - kept.estimated,
+        }
+    };
+    let only = |nulls, empty, defaults| {
+        savings(Passes {
+            nulls,
+            empty,
+            defaults,
+        })
+    };
+    MinificationReport {
+        serialized_size: full_serialized,
+        estimated_size: full_estimated,
+        drop_nulls: only(true, false, false),
+        drop_empty: only(false, true, false),
+        drop_defaults: only(false, false, true),
+        all: only(true, true, true),
+    }
+}
+
+/// The sizes of what is left of a node after the passes.
+struct Kept {
+    estimated: usize,
+    serialized: usize,
+    /// Whether the node is an array or object left without members or elements.
+    empty: bool,
+}
+
+/// Sizes `v` as the passes would leave it, without building the result, so that only the
+/// members removed count as savings and the buffers of `v` keep their capacity.
+fn normalized(v: &Value, passes: Passes, removed: &mut usize) -> Kept {
+    match v {
+        Value::Array(a) => {
+            let mut kept = Kept {
+                estimated: own_size(v),
+                // Brackets and commas.
+                serialized: 2 + a.len().saturating_sub(1),
+                empty: a.is_empty(),
+            };
+            for item in a {
+                let item = normalized(item, passes, removed);
+                kept.estimated = kept.estimated.saturating_add(item.estimated);
+                kept.serialized = kept.serialized.saturating_add(item.serialized);
+            }
+            kept
+        }
+        Value::Object(o) => {
+            let (mut len, mut estimated, mut serialized) = (0, 0usize, 0usize);
+            for (k, item) in o {
+                let item_kept = normalized(item, passes, removed);
+                if drops(item, item_kept.empty, passes) {
+                    *removed += 1;
+                    continue;
+                }
+                len += 1;
+                estimated = estimated.saturating_add(entry_overhead(k) + item_kept.estimated);
+                // The quoted key and the colon.
+                let key = 2 + k.len() + escape_inflation(k) + 1;
+                serialized = serialized.saturating_add(key + item_kept.serialized);
+            }
+            Kept {
+                // The bookkeeping of the map shrinks with the members removed.
+                estimated: (own_size(v) - map_overhead(o.len()) + map_overhead(len))
+                    .saturating_add(estimated),
+                serialized: (2 + len.saturating_sub(1)).saturating_add(serialized),
+                empty: len == 0,
+            }
+        }
+        _ => Kept {
+            estimated: own_size(v),
+            serialized: serialized_size(v),
+            empty: false,
+        },
+    }
+}
+
+/// Whether a member holding `v` is removed, where `empty` tells whether the passes emptied it.
+fn drops(v: &Value, empty: bool, passes: Passes) -> bool {
+    match v {
+        Value::Null => passes.nulls,
+        Value::Array(_) | Value::Object(_) => passes.empty && empty,
+        Value::Bool(b) => passes.defaults && !b,
+        Value::Number(n) => passes.defaults && n.as_f64() == Some(0.0),
+        Value::String(s) => passes.defaults && s.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_each_pass_alone() {
+        let val = json!({"a": null, "b": {}, "c": 0, "d": "", "e": [null], "f": "x"});
+        let report = minification_report(&val);
+        // The null inside the array is kept.
+        assert_eq!(report.drop_nulls.removed_members, 1);
+        assert_eq!(report.drop_empty.removed_members, 1);
+        assert_eq!(report.drop_defaults.removed_members, 2);
+        let stripped = json!({"b": {}, "c": 0, "d": "", "e": [null], "f": "x"});
+        assert_eq!(
+            report.drop_nulls.serialized,
+            serialized_size(&val) - serialized_size(&stripped)
+        );
+        assert_eq!(
+            report.drop_nulls.estimated,
+            sizeof_val(&val) - sizeof_val(&stripped)
+        );
+    }
+
+    #[test]
+    fn test_passes_cascade_bottom_up() {
+        let val = json!({"outer": {"inner": {"gone": null}}, "keep": 1});
+        let report = minification_report(&val);
+        assert_eq!(report.drop_nulls.removed_members, 1);
+        assert_eq!(report.all.removed_members, 3);
+        assert_eq!(
+            report.all.serialized,
+            serialized_size(&val) - serialized_size(&json!({"keep": 1}))
+        );
+    }
+
+    #[test]
+    fn test_parsed_capacity_is_not_savings() {
+        // Parsed arrays have spare capacity, which the passes keep.
+        let val: Value = serde_json::from_str(r#"{"a":[1,2,3],"b":"x"}"#).unwrap();
+        assert_eq!(minification_report(&val).drop_nulls, PassSavings::default());
+        let val: Value = serde_json::from_str(r#"{"a":[1,2,3],"c":null}"#).unwrap();
+        let nulls = minification_report(&val).drop_nulls;
+        assert_eq!(nulls.serialized, r#","c":null"#.len());
+        let key = "c".to_owned();
+        assert_eq!(
+            nulls.estimated,
+            entry_overhead(&key) + own_size(&Value::Null) + map_overhead(2) - map_overhead(1)
+        );
+    }
+
+    #[test]
+    fn test_root_is_kept() {
+        let report = minification_report(&json!(null));
+        assert_eq!(report.all, PassSavings::default());
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{keys_by_size, sizeof_val};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The size of a document at one point in time, taken by [`SizeMonitor::snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub taken_at: Instant,
+    pub size: usize,
+    /// Size of each top-level member, including its key, if the monitor tracks them and the
+    /// document is an object.
+    pub keys: BTreeMap<String, usize>,
+}
+
+/// How one top-level member changed between two snapshots. A member that was added or removed
+/// has a size of 0 on the other side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyDelta {
+    pub key: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+impl KeyDelta {
+    /// Bytes the member grew by, negative if it shrank.
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+/// The change between two snapshots of a [`SizeMonitor`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SizeDelta {
+    pub before: usize,
+    pub after: usize,
+    pub elapsed: Duration,
+    /// Top-level members that changed size, appeared or disappeared, the most grown first.
+    pub keys: Vec<KeyDelta>,
+}
+
+impl SizeDelta {
+    /// Bytes the document grew by, negative if it shrank.
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+
+    /// Growth in bytes per second, negative if the document shrank, and 0 if no time elapsed.
+    pub fn bytes_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.delta() as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+type GrowthCallback = Box<dyn FnMut(&SizeDelta) + Send>;
+
+/// Tracks the estimated size of a long-lived document across snapshots.
+///
+/// Each [`SizeMonitor::snapshot`] sizes the document and reports the change since the previous
+/// one. With [`SizeMonitor::per_key`] the top-level members are sized too, which shows the keys a
+/// slowly growing document keeps adding. [`SizeMonitor::on_growth`] registers a callback for
+/// snapshots growing faster than a given rate.
+///
+/// ## Example
+/// ```
+/// use json_size::SizeMonitor;
+/// use serde_json::json;
+/// use std::time::{Duration, Instant};
+///
+/// let start = Instant::now();
+/// let mut monitor = SizeMonitor::new().per_key(true);
+/// let mut session = json!({"user": "ada"});
+/// assert!(monitor.snapshot_at(&session, start).is_none());
+///
+/// session["cache-1"] = json!("x".repeat(1000));
+/// let delta = monitor.snapshot_at(&session, start + Duration::from_secs(10)).unwrap();
+/// assert!(delta.bytes_per_second() > 100.0);
+/// assert_eq!(delta.keys[0].key, "cache-1");
+/// ```
+#[derive(Default)]
+pub struct SizeMonitor {
+    per_key: bool,
+    on_growth: Option<(f64, GrowthCallback)>,
+    last: Option<Snapshot>,
+}
+
+impl fmt::Debug for SizeMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SizeMonitor")
+            .field("per_key", &self.per_key)
+            .field(
+                "growth_limit",
+                &self.on_growth.as_ref().map(|(rate, _)| rate),
+            )
+            .field("last", &self.last)
+            .finish()
+    }
+}
+
+impl SizeMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the top-level members of objects are sized in each snapshot.
+    pub fn per_key(mut self, enabled: bool) -> Self {
+        self.per_key = enabled;
+        self
+    }
+
+    /// Calls `callback` with the delta of every snapshot that grew by more than
+    /// `bytes_per_second` since the previous one.
+    pub fn on_growth<F>(mut self, bytes_per_second: f64, callback: F) -> Self
+    where
+        F: FnMut(&SizeDelta) + Send + 'static,
+    {
+        self.on_growth = Some((bytes_per_second, Box::new(callback)));
+        self
+    }
+
+    /// The most recent snapshot.
+    pub fn last(&self) -> Option<&Snapshot> {
+        self.last.as_ref()
+    }
+
+    /// Takes a snapshot of `v` now, returning the change since the previous snapshot.
+    pub fn snapshot(&mut self, v: &Value) -> Option<SizeDelta> {
+        self.snapshot_at(v, Instant::now())
+    }
+
+    /// Same as [`SizeMonitor::snapshot`], with the time of the snapshot given.
+    pub fn snapshot_at(&mut self, v: &Value, taken_at: Instant) -> Option<SizeDelta> {
+        let keys = match v {
+            Value::Object(map) if self.per_key => keys_by_size(map)
+                .into_iter()
+                .map(|(key, size)| (key.to_owned(), size))
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+        let snapshot = Snapshot {
+            taken_at,
+            size: sizeof_val(v),
+            keys,
+        };
+        let delta = self.last.as_ref().map(|last| diff(last, &snapshot));
+        self.last = Some(snapshot);
+        if let (Some(delta), Some((limit, callback))) = (&delta, &mut self.on_growth) {
+            if delta.bytes_per_second() > *limit {
+                callback(delta);
+            }
+        }
+        delta
+    }
+}
+
+fn diff(before: &Snapshot, after: &Snapshot) -> SizeDelta {
+    let mut keys: Vec<KeyDelta> = after
+        .keys
+        .iter()
+        .map(|(key, &size)| (key, before.keys.get(key).copied().unwrap_or(0), size))
+        .chain(
+            before
+                .keys
+                .iter()
+                .filter(|(key, _)| !after.keys.contains_key(*key))
+                .map(|(key, &size)| (key, size, 0)),
+        )
+        .filter(|&(_, before, after)| before != after)
+        .map(|(key, before, after)| KeyDelta {
+            key: key.clone(),
+            before,
+            after,
+        })
+        .collect();
+    keys.sort_by(|a, b| b.delta().cmp(&a.delta()).then_with(|| a.key.cmp(&b.key)));
+    SizeDelta {
+        before: before.size,
+        after: after.size,
+        elapsed: after.taken_at.saturating_duration_since(before.taken_at),
+        keys,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry_overhead;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_deltas_between_snapshots() {
+        let start = Instant::now();
+        let mut monitor = SizeMonitor::new();
+        let first = json!({"a": 1});
+        assert_eq!(monitor.snapshot_at(&first, start), None);
+        let second = json!({"a": 1, "b": "two"});
+        let delta = monitor
+            .snapshot_at(&second, start + Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(delta.before, sizeof_val(&first));
+        assert_eq!(delta.after, sizeof_val(&second));
+        assert_eq!(delta.bytes_per_second(), delta.delta() as f64 / 2.0);
+        // Keys are only tracked when asked for.
+        assert!(delta.keys.is_empty());
+        assert_eq!(monitor.last().unwrap().size, sizeof_val(&second));
+    }
+
+    #[test]
+    fn test_key_deltas() {
+        let start = Instant::now();
+        let mut monitor = SizeMonitor::new().per_key(true);
+        monitor.snapshot_at(&json!({"keep": 1, "gone": 2, "grows": "a"}), start);
+        let delta = monitor
+            .snapshot_at(&json!({"keep": 1, "grows": "abcdef", "new": null}), start)
+            .unwrap();
+        let keys: Vec<(&str, i64)> = delta
+            .keys
+            .iter()
+            .map(|k| (k.key.as_str(), k.delta()))
+            .collect();
+        let member = |key: &str, value: Value| entry_overhead(&key.into()) + sizeof_val(&value);
+        assert_eq!(
+            keys,
+            [
+                ("new", member("new", json!(null)) as i64),
+                ("grows", 5),
+                ("gone", -(member("gone", json!(2)) as i64)),
+            ]
+        );
+        assert_eq!(delta.bytes_per_second(), 0.0);
+    }
+
+    #[test]
+    fn test_growth_callback() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let mut monitor = SizeMonitor::new().on_growth(10.0, move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        let start = Instant::now();
+        monitor.snapshot_at(&json!([]), start);
+        monitor.snapshot_at(&json!(["x"]), start + Duration::from_secs(100));
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        monitor.snapshot_at(
+            &json!(["x", "y".repeat(100)]),
+            start + Duration::from_secs(101),
+        );
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,368 @@
+use serde::Serialize;
+use serde_json::{Number, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A fixed-width numeric type a column of numbers can be stored as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumericType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl NumericType {
+    /// Bytes one value takes.
+    pub fn width(self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::U64 | Self::I64 | Self::F64 => 8,
+        }
+    }
+
+    /// The narrowest integer type holding every integer in `lo..=hi`.
+    fn integer(lo: i128, hi: i128) -> Option<Self> {
+        let candidates: [(Self, i128, i128); 4] = if lo >= 0 {
+            [
+                (Self::U8, 0, u8::MAX.into()),
+                (Self::U16, 0, u16::MAX.into()),
+                (Self::U32, 0, u32::MAX.into()),
+                (Self::U64, 0, u64::MAX.into()),
+            ]
+        } else {
+            [
+                (Self::I8, i8::MIN.into(), i8::MAX.into()),
+                (Self::I16, i16::MIN.into(), i16::MAX.into()),
+                (Self::I32, i32::MIN.into(), i32::MAX.into()),
+                (Self::I64, i64::MIN.into(), i64::MAX.into()),
+            ]
+        };
+        candidates
+            .into_iter()
+            .find(|&(_, min, max)| lo >= min && hi <= max)
+            .map(|(ty, ..)| ty)
+    }
+}
+
+/// The numbers found at one column of a document, see [`number_report`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NumberColumn {
+    /// JSON Pointer of the column, with `*` standing for every array index.
+    pub column: String,
+    /// Non-negative integers, stored by `serde_json` as `u64`.
+    pub u64s: usize,
+    /// Negative integers, stored as `i64`.
+    pub i64s: usize,
+    /// Numbers with a fraction or an exponent, stored as `f64`.
+    pub f64s: usize,
+    pub min: Number,
+    pub max: Number,
+    /// The narrowest type storing every number of the column exactly, `None` if no type does,
+    /// as for integers from both sides of the `i64` range that are not exact as `f64`.
+    pub narrowest: Option<NumericType>,
+}
+
+impl NumberColumn {
+    pub fn count(&self) -> usize {
+        self.u64s + self.i64s + self.f64s
+    }
+
+    /// Bytes the numbers take as 64-bit values.
+    pub fn stored_bytes(&self) -> usize {
+        self.count() * 8
+    }
+
+    /// Bytes the numbers take as [`NumberColumn::narrowest`], or as 64-bit values if there is no
+    /// such type.
+    pub fn narrowed_bytes(&self) -> usize {
+        self.count() * self.narrowest.map_or(8, NumericType::width)
+    }
+
+    pub fn savings(&self) -> usize {
+        self.stored_bytes() - self.narrowed_bytes()
+    }
+}
+
+/// How the numbers of a document are represented, created by [`number_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NumberReport {
+    pub u64s: usize,
+    pub i64s: usize,
+    pub f64s: usize,
+    /// One entry per column holding numbers, the most to save by narrowing first.
+    pub columns: Vec<NumberColumn>,
+}
+
+impl NumberReport {
+    pub fn count(&self) -> usize {
+        self.u64s + self.i64s + self.f64s
+    }
+
+    /// Bytes narrowing every column to its narrowest type would save over 64-bit values.
+    pub fn savings(&self) -> usize {
+        self.columns.iter().map(NumberColumn::savings).sum()
+    }
+}
+
+/// Counts the numbers of `v` by how `serde_json` stores them, and finds the narrowest type each
+/// column of numbers could be stored as in a typed or columnar representation.
+///
+/// A column gathers the numbers at the same path once array indices are ignored, so the `price`
+/// of every element of an array of rows is one column. Integers narrow to the smallest integer
+/// type holding their range; a column with any float narrows to `f32` if every number in it
+/// survives the round trip through `f32`, and stays `f64` otherwise. A column of integers no
+/// integer type holds narrows like one with a float, and has no exact type, and so nothing to
+/// save, if some of them are not exact as `f64` either. Savings are counted against
+/// 8 bytes per number.
+///
+/// ## Example
+/// ```
+/// use json_size::{number_report, NumericType};
+/// use serde_json::json;
+///
+/// let rows = json!([
+///     {"id": 1, "qty": 3, "price": 9.5, "delta": -2},
+///     {"id": 70000, "qty": 12, "price": 0.25, "delta": 40},
+/// ]);
+/// let report = number_report(&rows);
+/// assert_eq!((report.u64s, report.i64s, report.f64s), (5, 1, 2));
+/// let narrowest = |name: &str| {
+///     let column = report.columns.iter().find(|c| c.column == name).unwrap();
+///     column.narrowest
+/// };
+/// assert_eq!(narrowest("/*/id"), Some(NumericType::U32));
+/// assert_eq!(narrowest("/*/qty"), Some(NumericType::U8));
+/// assert_eq!(narrowest("/*/price"), Some(NumericType::F32));
+/// assert_eq!(narrowest("/*/delta"), Some(NumericType::I8));
+/// assert_eq!(report.savings(), 2 * (4 + 7 + 4 + 7));
+/// ```
+pub fn number_report(v: &Value) -> NumberReport {
+    let mut columns = HashMap::new();
+    collect(v, &mut String::new(), &mut columns);
+    let mut report = NumberReport::default();
+    for (column, tally) in columns {
+        report.u64s += tally.u64s;
+        report.i64s += tally.i64s;
+        report.f64s += tally.f64s;
+        report.columns.push(tally.finish(column));
+    }
+    report.columns.sort_by(|a, b| {
+        b.savings()
+            .cmp(&a.savings())
+            .then_with(|| a.column.cmp(&b.column))
+    });
+    report
+}
+
+fn collect(v: &Value, column: &mut String, columns: &mut HashMap<String, Tally>) {
+    let len = column.len();
+    match v {
+        Value::Number(n) => match columns.get_mut(column.as_str()) {
+            Some(tally) => tally.add(n),
+            None => {
+                let mut tally = Tally::new(n);
+                tally.add(n);
+                columns.insert(column.clone(), tally);
+            }
+        },
+        Value::Array(a) => {
+            column.push_str("/*");
+            for item in a {
+                collect(item, column, columns);
+            }
+        }
+        Value::Object(o) => {
+            for (k, item) in o {
+                column.push('/');
+                column.push_str(&k.replace('~', "~0").replace('/', "~1"));
+                collect(item, column, columns);
+                column.truncate(len);
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::String(_) => {}
+    }
+    column.truncate(len);
+}
+
+/// The integer value of `n`, if it is one.
+fn integer(n: &Number) -> Option<i128> {
+    n.as_u64()
+        .map(i128::from)
+        .or_else(|| n.as_i64().map(i128::from))
+}
+
+fn cmp_numbers(a: &Number, b: &Number) -> Ordering {
+    match (integer(a), integer(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.total_cmp(&b)
+        }
+    }
+}
+
+/// Whether `n` survives the round trip through `f32`.
+fn fits_f32(n: &Number) -> bool {
+    match integer(n) {
+        Some(i) => (i as f32) as i128 == i,
+        None => n.as_f64().is_some_and(|f| f64::from(f as f32) == f),
+    }
+}
+
+/// Whether `n` survives the round trip through `f64`, as every float does.
+fn fits_f64(n: &Number) -> bool {
+    integer(n).is_none_or(|i| (i as f64) as i128 == i)
+}
+
+struct Tally {
+    u64s: usize,
+    i64s: usize,
+    f64s: usize,
+    min: Number,
+    max: Number,
+    fits_f32: bool,
+    fits_f64: bool,
+}
+
+impl Tally {
+    fn new(first: &Number) -> Self {
+        Self {
+            u64s: 0,
+            i64s: 0,
+            f64s: 0,
+            min: first.clone(),
+            max: first.clone(),
+            fits_f32: true,
+            fits_f64: true,
+        }
+    }
+
+    fn add(&mut self, n: &Number) {
+        if n.is_u64() {
+            self.u64s += 1;
+        } else if n.is_i64() {
+            self.i64s += 1;
+        } else {
+            self.f64s += 1;
+        }
+        if cmp_numbers(n, &self.min).is_lt() {
+            self.min = n.clone();
+        }
+        if cmp_numbers(n, &self.max).is_gt() {
+            self.max = n.clone();
+        }
+        self.fits_f32 &= fits_f32(n);
+        self.fits_f64 &= fits_f64(n);
+    }
+
+    fn finish(self, column: String) -> NumberColumn {
+        let range = integer(&self.min).zip(integer(&self.max));
+        let narrowest = match range {
+            Some((lo, hi)) if self.f64s == 0 => NumericType::integer(lo, hi),
+            _ => None,
+        };
+        let narrowest = narrowest.or(if self.fits_f32 {
+            Some(NumericType::F32)
+        } else if self.fits_f64 {
+            Some(NumericType::F64)
+        } else {
+            None
+        });
+        NumberColumn {
+            column,
+            u64s: self.u64s,
+            i64s: self.i64s,
+            f64s: self.f64s,
+            min: self.min,
+            max: self.max,
+            narrowest,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column<'a>(report: &'a NumberReport, name: &str) -> &'a NumberColumn {
+        report.columns.iter().find(|c| c.column == name).unwrap()
+    }
+
+    #[test]
+    fn test_integer_ranges() {
+        let val = json!({
+            "small": [0, 255],
+            "wide": [0, 65536],
+            "neg": [-129, 5],
+            "big": [u64::MAX],
+            "mixed": [-1, u64::MAX],
+            "exact": [-1, 1u64 << 63],
+        });
+        let report = number_report(&val);
+        let narrowest: Vec<_> = [
+            "/small/*", "/wide/*", "/neg/*", "/big/*", "/mixed/*", "/exact/*",
+        ]
+        .iter()
+        .map(|name| column(&report, name).narrowest)
+        .collect();
+        assert_eq!(
+            narrowest,
+            [
+                Some(NumericType::U8),
+                Some(NumericType::U32),
+                Some(NumericType::I16),
+                Some(NumericType::U64),
+                None,
+                // A power of two is exact even as an `f32`.
+                Some(NumericType::F32),
+            ]
+        );
+        let neg = column(&report, "/neg/*");
+        assert_eq!(
+            (neg.min.clone(), neg.max.clone()),
+            (Number::from(-129), Number::from(5))
+        );
+        assert_eq!((neg.u64s, neg.i64s, neg.f64s), (1, 1, 0));
+        assert_eq!(column(&report, "/mixed/*").savings(), 0);
+        assert_eq!(report.count(), 11);
+    }
+
+    #[test]
+    fn test_floats_narrow_to_f32_only_when_exact() {
+        let val = json!({"halves": [0.5, 1.5, 2], "tenths": [0.1], "huge_int": [1.0, 16777217]});
+        let report = number_report(&val);
+        assert_eq!(
+            column(&report, "/halves/*").narrowest,
+            Some(NumericType::F32)
+        );
+        assert_eq!(
+            column(&report, "/tenths/*").narrowest,
+            Some(NumericType::F64)
+        );
+        assert_eq!(
+            column(&report, "/huge_int/*").narrowest,
+            Some(NumericType::F64)
+        );
+        assert_eq!(report.savings(), 3 * 4);
+    }
+
+    #[test]
+    fn test_sorted_by_savings() {
+        let val = json!({"a": [1.5], "b": [1, 2, 3], "c": "text"});
+        let report = number_report(&val);
+        let names: Vec<_> = report.columns.iter().map(|c| c.column.as_str()).collect();
+        assert_eq!(names, ["/b/*", "/a/*"]);
+        assert_eq!(number_report(&json!(["x", null])), NumberReport::default());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{entry_overhead, map_overhead, sizeof_val};
+use serde_json::{Map, Value};
+use std::mem::size_of;
+
+/// Returns the change in the estimated size of `doc`, as computed by [`crate::sizeof_val`], if
+/// the JSON Merge Patch `patch` (RFC 7386) were applied to it. Negative if the document would
+/// shrink.
+///
+/// `doc` is not modified and the patched document is never built. Members set to `null` in the
+/// patch are removed, objects are merged recursively, and any other patch value replaces what
+/// is at its place. Patch values are sized as they are, so applying the patch must not change
+/// their string or array capacities for the result to match exactly.
+///
+/// ## Example
+/// ```
+/// use json_size::{merge_patch_size_delta, sizeof_val};
+/// use serde_json::json;
+///
+/// let doc = json!({"title": "Goodbye!", "author": {"name": "ada", "email": "ada@example.com"}});
+/// let patch = json!({"title": "Hello!", "author": {"email": null}});
+/// let patched = json!({"title": "Hello!", "author": {"name": "ada"}});
+/// assert_eq!(
+///     merge_patch_size_delta(&doc, &patch),
+///     sizeof_val(&patched) as isize - sizeof_val(&doc) as isize
+/// );
+/// ```
+pub fn merge_patch_size_delta(doc: &Value, patch: &Value) -> isize {
+    delta(Some(doc), patch)
+}
+
+/// Change in size of the value at one place in the document when `patch` is merged into it, or
+/// its whole size if the place is empty (`None`).
+fn delta(doc: Option<&Value>, patch: &Value) -> isize {
+    let Value::Object(members) = patch else {
+        return signed(sizeof_val(patch)) - doc.map_or(0, |v| signed(sizeof_val(v)));
+    };
+    let empty = Map::new();
+    // Anything but an object is replaced by an empty one before merging.
+    let (target, mut change) = match doc {
+        Some(Value::Object(target)) => (target, 0),
+        other => (
+            &empty,
+            signed(size_of::<Value>()) - other.map_or(0, |v| signed(sizeof_val(v))),
+        ),
+    };
+    let mut len = target.len();
+    for (k, value) in members {
+        match (target.get(k), value) {
+            (Some(old), Value::Null) => {
+                len -= 1;
+                change -= signed(entry_overhead(k) + sizeof_val(old));
+            }
+            (None, Value::Null) => {}
+            (Some(old), value) => change += delta(Some(old), value),
+            (None, value) => {
+                len += 1;
+                change += signed(entry_overhead(k)) + delta(None, value);
+            }
+        }
+    }
+    change + signed(map_overhead(len)) - signed(map_overhead(target.len()))
+}
+
+fn signed(size: usize) -> isize {
+    isize::try_from(size).unwrap_or(isize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Applies `patch` to `doc` as RFC 7386 describes.
+    fn apply(doc: &mut Value, patch: &Value) {
+        let Value::Object(members) = patch else {
+            *doc = patch.clone();
+            return;
+        };
+        if !doc.is_object() {
+            *doc = Value::Object(Map::new());
+        }
+        let target = doc.as_object_mut().unwrap();
+        for (k, value) in members {
+            if value.is_null() {
+                target.remove(k);
+            } else {
+                apply(target.entry(k).or_insert(Value::Null), value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_applied_patch() {
+        // Built from text, as `format!` leaves spare capacity in keys that cloning drops.
+        let members = |range: std::ops::Range<usize>| -> Value {
+            let text = range.map(|i| format!(r#""k{i}": {i}"#)).collect::<Vec<_>>();
+            serde_json::from_str(&format!("{{{}}}", text.join(", "))).unwrap()
+        };
+        let many = members(0..20);
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"})),
+            (json!({"a": "b"}), json!({"b": "c"})),
+            (json!({"a": "b"}), json!({"a": null})),
+            (json!({"a": "b", "b": "c"}), json!({"a": null})),
+            (json!({"a": ["b"]}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+            ),
+            (json!({"a": [{"b": "c"}]}), json!({"a": [1]})),
+            (json!(["a", "b"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"])),
+            (json!({"a": "foo"}), json!(null)),
+            (json!({"a": "foo"}), json!("bar")),
+            (json!({"e": null}), json!({"a": 1})),
+            (json!([1, 2]), json!({"a": "b", "c": null})),
+            (json!({}), json!({"a": {"bb": {"ccc": null}}})),
+            (
+                many.clone(),
+                json!({"k3": null, "k4": null, "k5": null, "k6": null}),
+            ),
+            (many, members(20..40)),
+        ];
+        for (doc, patch) in cases {
+            let mut patched = doc.clone();
+            apply(&mut patched, &patch);
+            let expected = sizeof_val(&patched) as isize - sizeof_val(&doc) as isize;
+            assert_eq!(
+                merge_patch_size_delta(&doc, &patch),
+                expected,
+                "{doc} + {patch}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_patch_changes_nothing() {
+        let doc = json!({"a": [1, 2, {"b": "c"}]});
+        assert_eq!(merge_patch_size_delta(&doc, &json!({})), 0);
+        assert_eq!(merge_patch_size_delta(&doc, &json!({"z": null})), 0);
+    }
+}
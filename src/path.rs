@@ -0,0 +1,129 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// A single step from a JSON node to one of its children.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PathSegment {
+    /// An object member, by key.
+    Key(String),
+    /// An array element, by index.
+    Index(usize),
+}
+
+/// The location of a node inside a `serde_json::Value`.
+///
+/// Paths are displayed as JSON Pointers (RFC 6901), so they can be passed straight to
+/// [`serde_json::Value::pointer`]. The root of a document is the empty path, displayed as `""`.
+///
+/// ## Example
+/// ```
+/// use json_size::JsonPath;
+///
+/// let mut path = JsonPath::root();
+/// path.push_key("users");
+/// path.push_index(0);
+/// path.push_key("a/b");
+/// assert_eq!(path.to_string(), "/users/0/a~1b");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JsonPath {
+    segments: Vec<PathSegment>,
+}
+
+impl JsonPath {
+    /// The path of the document root.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if this is the path of the document root.
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The segments of this path, outermost first.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Number of segments, i.e. the nesting depth of the node this path points at.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Same as [`JsonPath::is_root`].
+    pub fn is_empty(&self) -> bool {
+        self.is_root()
+    }
+
+    /// Appends an object key.
+    pub fn push_key(&mut self, key: impl Into<String>) {
+        self.segments.push(PathSegment::Key(key.into()));
+    }
+
+    /// Appends an array index.
+    pub fn push_index(&mut self, index: usize) {
+        self.segments.push(PathSegment::Index(index));
+    }
+
+    /// Removes and returns the last segment.
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+}
+
+impl From<Vec<PathSegment>> for JsonPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self { segments }
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(k) => write!(f, "/{}", k.replace('~', "~0").replace('/', "~1"))?,
+                PathSegment::Index(i) => write!(f, "/{i}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for JsonPath {
+    /// Serializes as the JSON Pointer string.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_root_is_empty_pointer() {
+        assert_eq!(JsonPath::root().to_string(), "");
+        assert!(JsonPath::root().is_root());
+    }
+
+    #[test]
+    fn test_pointer_escaping() {
+        let mut path = JsonPath::root();
+        path.push_key("a~b/c");
+        path.push_index(3);
+        assert_eq!(path.to_string(), "/a~0b~1c/3");
+    }
+
+    #[test]
+    fn test_display_matches_value_pointer() {
+        let val = json!({"x/y": [{"z": 1}]});
+        let path = JsonPath::from(vec![
+            PathSegment::Key("x/y".into()),
+            PathSegment::Index(0),
+            PathSegment::Key("z".into()),
+        ]);
+        assert_eq!(val.pointer(&path.to_string()), Some(&json!(1)));
+    }
+}
@@ -0,0 +1,332 @@
+use crate::{JsonPath, PathSegment, SizeEstimator};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+
+/// A pattern selecting nodes of a document by path, see [`sizeof_matching`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizePattern {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    /// A key, or an array index written in decimal.
+    Name(String),
+    /// Any one segment.
+    Any,
+    /// Any number of segments, none included.
+    Descendants,
+}
+
+/// Why a pattern could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    /// Byte offset in the pattern where parsing failed.
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid pattern at byte {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl Error for PatternError {}
+
+impl SizePattern {
+    /// Parses a pointer pattern or, if `pattern` starts with `$`, a JSONPath.
+    ///
+    /// A pointer pattern is a JSON Pointer whose segments may also be `*`, matching any one key or
+    /// index, or `**`, matching any number of segments. The JSONPath subset covers `.key`,
+    /// `['key']`, `[0]`, `.*`, `[*]` and the `..` descendant operator.
+    pub fn parse(pattern: &str) -> Result<Self, PatternError> {
+        let mut steps = match pattern.strip_prefix('$') {
+            Some(rest) => parse_json_path(rest)?,
+            None => parse_pointer(pattern)?,
+        };
+        // `/**/**` matches what `/**` does.
+        steps.dedup_by(|a, b| *a == Step::Descendants && *b == Step::Descendants);
+        Ok(Self { steps })
+    }
+
+    /// Whether the node at `path` is selected.
+    pub fn matches(&self, path: &JsonPath) -> bool {
+        matches(&self.steps, path.segments())
+    }
+
+    /// Same as [`sizeof_matching`], with a parsed pattern.
+    pub fn sizes(&self, v: &Value) -> Vec<(JsonPath, usize)> {
+        SizeEstimator::DEFAULT.sizeof_matching(v, self)
+    }
+}
+
+impl FromStr for SizePattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Returns the path and estimated size of every node of `v` selected by `pattern`, ordered by
+/// path, in a single traversal.
+///
+/// `pattern` is a JSON Pointer where `*` stands for any one key or index and `**` for any number
+/// of segments, or a JSONPath such as `$.records[*].payload` or `$..payload`; see
+/// [`SizePattern::parse`]. As everywhere in this crate, the size of an object member includes its
+/// key and map entry overhead.
+///
+/// # Panics
+///
+/// Panics if `pattern` is invalid. Use [`SizePattern::parse`] and [`SizePattern::sizes`] to
+/// handle patterns from user input.
+///
+/// ## Example
+/// ```
+/// use json_size::sizeof_matching;
+/// use serde_json::json;
+///
+/// let val = json!({"records": [
+///     {"id": 1, "payload": "x".repeat(100)},
+///     {"id": 2, "payload": "y".repeat(10)},
+/// ]});
+/// let sizes = sizeof_matching(&val, "/records/*/payload");
+/// assert_eq!(sizes.len(), 2);
+/// assert_eq!(sizes[0].0.to_string(), "/records/0/payload");
+/// assert!(sizes[0].1 > sizes[1].1);
+/// assert_eq!(sizeof_matching(&val, "$..payload"), sizes);
+/// ```
+pub fn sizeof_matching(v: &Value, pattern: &str) -> Vec<(JsonPath, usize)> {
+    match SizePattern::parse(pattern) {
+        Ok(pattern) => pattern.sizes(v),
+        Err(err) => panic!("{err}"),
+    }
+}
+
+impl SizeEstimator {
+    /// Same as [`sizeof_matching`], with sizes computed by this estimator.
+    pub fn sizeof_matching(&self, v: &Value, pattern: &SizePattern) -> Vec<(JsonPath, usize)> {
+        let mut found = Vec::new();
+        let _ = self.walk_sizes(v, |path, _, size| {
+            if pattern.matches(path) {
+                found.push((path.clone(), size));
+            }
+            ControlFlow::Continue(())
+        });
+        found.sort();
+        found
+    }
+}
+
+/// Runs the steps as an automaton over `segments`, tracking every step a prefix of the path can
+/// have reached, so that matching takes O(steps × depth) however many `**` the pattern has.
+fn matches(steps: &[Step], segments: &[PathSegment]) -> bool {
+    let mut reached = vec![false; steps.len() + 1];
+    reached[0] = true;
+    skip_descendants(steps, &mut reached);
+    for segment in segments {
+        let mut next = vec![false; steps.len() + 1];
+        for (i, step) in steps.iter().enumerate().filter(|&(i, _)| reached[i]) {
+            match step {
+                Step::Descendants => next[i] = true,
+                Step::Any => next[i + 1] = true,
+                Step::Name(name) => next[i + 1] |= name_matches(name, segment),
+            }
+        }
+        skip_descendants(steps, &mut next);
+        if !next.contains(&true) {
+            return false;
+        }
+        reached = next;
+    }
+    reached[steps.len()]
+}
+
+/// Marks the step after each reached `**` as reached, as it may match no segment.
+fn skip_descendants(steps: &[Step], reached: &mut [bool]) {
+    for (i, step) in steps.iter().enumerate() {
+        if reached[i] && *step == Step::Descendants {
+            reached[i + 1] = true;
+        }
+    }
+}
+
+fn name_matches(name: &str, segment: &PathSegment) -> bool {
+    match segment {
+        PathSegment::Key(k) => k == name,
+        PathSegment::Index(i) => *name == i.to_string(),
+    }
+}
+
+fn parse_pointer(pattern: &str) -> Result<Vec<Step>, PatternError> {
+    if pattern.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pattern.strip_prefix('/') else {
+        return Err(PatternError {
+            position: 0,
+            message: "a pointer pattern must start with '/' or be empty",
+        });
+    };
+    let steps = rest
+        .split('/')
+        .map(|segment| match segment {
+            "*" => Step::Any,
+            "**" => Step::Descendants,
+            _ => Step::Name(segment.replace("~1", "/").replace("~0", "~")),
+        })
+        .collect();
+    Ok(steps)
+}
+
+fn parse_json_path(rest: &str) -> Result<Vec<Step>, PatternError> {
+    let error = |at: &str, message| PatternError {
+        // One byte for the leading `$`.
+        position: 1 + rest.len() - at.len(),
+        message,
+    };
+    let mut steps = Vec::new();
+    let mut s = rest;
+    while !s.is_empty() {
+        if let Some(after) = s.strip_prefix("..") {
+            steps.push(Step::Descendants);
+            s = after;
+            if s.starts_with('[') {
+                continue;
+            }
+            let (step, after) =
+                dotted_name(s).ok_or_else(|| error(s, "expected a name after '..'"))?;
+            steps.push(step);
+            s = after;
+        } else if let Some(after) = s.strip_prefix('.') {
+            let (step, after) =
+                dotted_name(after).ok_or_else(|| error(after, "expected a name after '.'"))?;
+            steps.push(step);
+            s = after;
+        } else if let Some(after) = s.strip_prefix('[') {
+            let (step, after) =
+                bracketed(after).ok_or_else(|| error(s, "expected [*], [index] or ['key']"))?;
+            steps.push(step);
+            s = after;
+        } else {
+            return Err(error(s, "expected '.', '..' or '['"));
+        }
+    }
+    Ok(steps)
+}
+
+/// A `*` or a name running up to the next `.` or `[`.
+fn dotted_name(s: &str) -> Option<(Step, &str)> {
+    let end = s.find(['.', '[']).unwrap_or(s.len());
+    let (name, rest) = s.split_at(end);
+    match name {
+        "" => None,
+        "*" => Some((Step::Any, rest)),
+        _ => Some((Step::Name(name.to_string()), rest)),
+    }
+}
+
+/// The inside of `[...]`, `s` starting right after the `[`.
+fn bracketed(s: &str) -> Option<(Step, &str)> {
+    if let Some(rest) = s.strip_prefix("*]") {
+        return Some((Step::Any, rest));
+    }
+    if let Some(quote) = s.chars().next().filter(|&c| c == '\'' || c == '"') {
+        let inner = &s[1..];
+        let end = inner.find(quote)?;
+        let rest = inner[end + 1..].strip_prefix(']')?;
+        return Some((Step::Name(inner[..end].to_string()), rest));
+    }
+    let end = s.find(']')?;
+    let index: usize = s[..end].parse().ok()?;
+    Some((Step::Name(index.to_string()), &s[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entry_overhead, sizeof_val};
+    use serde_json::json;
+
+    fn paths(v: &Value, pattern: &str) -> Vec<String> {
+        sizeof_matching(v, pattern)
+            .iter()
+            .map(|(path, _)| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_pointer_patterns() {
+        let val = json!({"a": [{"b": 1}, {"b": 2, "c": {"b": 3}}], "a/b": 4});
+        assert_eq!(paths(&val, "/a/*/b"), ["/a/0/b", "/a/1/b"]);
+        assert_eq!(paths(&val, "/a/1"), ["/a/1"]);
+        assert_eq!(paths(&val, "/**/b"), ["/a/0/b", "/a/1/b", "/a/1/c/b"]);
+        assert_eq!(paths(&val, "/a~1b"), ["/a~1b"]);
+        assert_eq!(paths(&val, ""), [""]);
+        assert_eq!(paths(&val, "/**").len(), 9);
+        assert!(paths(&val, "/missing/*").is_empty());
+    }
+
+    #[test]
+    fn test_json_path_patterns() {
+        let val = json!({"a": [{"b": 1}, {"b": 2, "c": {"b": 3}}], "a.b": 4});
+        assert_eq!(paths(&val, "$.a[*].b"), ["/a/0/b", "/a/1/b"]);
+        assert_eq!(paths(&val, "$.a[1].c"), ["/a/1/c"]);
+        assert_eq!(paths(&val, "$..b"), ["/a/0/b", "/a/1/b", "/a/1/c/b"]);
+        assert_eq!(paths(&val, "$['a.b']"), ["/a.b"]);
+        assert_eq!(paths(&val, "$.*"), ["/a", "/a.b"]);
+        assert_eq!(paths(&val, "$..[0]"), ["/a/0"]);
+        assert_eq!(paths(&val, "$"), [""]);
+    }
+
+    #[test]
+    fn test_many_descendant_steps_on_deep_documents() {
+        let mut val = json!({"b": 1});
+        for _ in 0..40 {
+            val = json!({ "a": val });
+        }
+        let deepest = format!("{}/b", "/a".repeat(40));
+        assert_eq!(
+            paths(&val, &format!("{}/b", "/**".repeat(8))),
+            [deepest.as_str()]
+        );
+        assert_eq!(SizePattern::parse("/**/**/b"), SizePattern::parse("/**/b"));
+        // Nothing to collapse, and no way to match: every split of the path has to be ruled out.
+        assert!(paths(&val, &format!("{}/**/c", "/**/a".repeat(8))).is_empty());
+        assert_eq!(
+            paths(&val, &format!("{}/**/b", "/**/a".repeat(8))),
+            [deepest]
+        );
+    }
+
+    #[test]
+    fn test_sizes_include_member_overhead() {
+        let val = json!({"k": "v"});
+        assert_eq!(
+            sizeof_matching(&val, "/k")[0].1,
+            entry_overhead(&"k".to_string()) + sizeof_val(&json!("v"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        let err = SizePattern::parse("a/b").unwrap_err();
+        assert_eq!(err.position, 0);
+        assert_eq!(SizePattern::parse("$.a[").unwrap_err().position, 3);
+        assert_eq!(SizePattern::parse("$.a.").unwrap_err().position, 4);
+        assert_eq!(SizePattern::parse("$a").unwrap_err().position, 1);
+        assert!("$['x'".parse::<SizePattern>().is_err());
+        assert_eq!(
+            err.to_string(),
+            "invalid pattern at byte 0: a pointer pattern must start with '/' or be empty"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Parallel profiling of a directory of JSON files, enabled with the `rayon` feature.
+
+use crate::{Corpus, CorpusStats, HumanSize};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Options for [`profile_dir`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileOptions {
+    /// Whether files in subdirectories are profiled too.
+    pub recursive: bool,
+    /// Number of heaviest keys listed in the aggregate.
+    pub top_keys: usize,
+}
+
+impl Default for ProfileOptions {
+    fn default() -> Self {
+        Self {
+            recursive: true,
+            top_keys: 10,
+        }
+    }
+}
+
+/// A file [`profile_dir`] could not read or parse.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileError {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// The aggregate profile of a directory, created by [`profile_dir`].
+///
+/// The `Display` impl prints sizes in bytes; the alternate form (`{:#}`) prints them as
+/// [`HumanSize`]s instead.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DirProfile {
+    /// Number of files profiled, not counting the failed ones.
+    pub files: usize,
+    /// Statistics over every document of every profiled file.
+    pub stats: CorpusStats,
+    /// Files that could not be read or parsed, sorted by path. None of their documents are
+    /// included in `stats`.
+    pub failed: Vec<FileError>,
+}
+
+/// Profiles every `.json` and `.jsonl` file in the directory at `path` in parallel, merging them
+/// into one [`Corpus`].
+///
+/// Files may hold a single document or JSON Lines, see [`Corpus::add_reader`]. A file that
+/// cannot be read or parsed is listed in [`DirProfile::failed`] and does not stop the others.
+/// Only failing to list a directory is an error.
+///
+/// ## Example
+/// ```
+/// use json_size::{profile_dir, ProfileOptions};
+///
+/// let dir = std::env::temp_dir().join("json_size_profile_dir_doctest");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::write(dir.join("users.jsonl"), "{\"id\": 1}\n{\"id\": 2}\n").unwrap();
+/// std::fs::write(dir.join("config.json"), "{\"debug\": true}").unwrap();
+///
+/// let profile = profile_dir(&dir, &ProfileOptions::default()).unwrap();
+/// assert_eq!(profile.files, 2);
+/// assert_eq!(profile.stats.documents, 3);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn profile_dir(path: impl AsRef<Path>, options: &ProfileOptions) -> io::Result<DirProfile> {
+    let mut files = Vec::new();
+    collect_files(path.as_ref(), options.recursive, &mut files)?;
+    let (corpus, files, mut failed) = files
+        .into_par_iter()
+        .fold(
+            || (Corpus::new(), 0, Vec::new()),
+            |(mut corpus, mut files, mut failed), path| {
+                // Read each file on its own, so a failure drops none of the other documents.
+                let mut file_corpus = Corpus::new();
+                match file_corpus.add_file(&path) {
+                    Ok(_) => {
+                        corpus.merge(file_corpus);
+                        files += 1;
+                    }
+                    Err(e) => failed.push(FileError {
+                        path,
+                        error: e.to_string(),
+                    }),
+                }
+                (corpus, files, failed)
+            },
+        )
+        .reduce(
+            || (Corpus::new(), 0, Vec::new()),
+            |(mut corpus, files, mut failed), (other, other_files, other_failed)| {
+                corpus.merge(other);
+                failed.extend(other_failed);
+                (corpus, files + other_files, failed)
+            },
+        );
+    failed.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(DirProfile {
+        files,
+        stats: corpus.stats(options.top_keys),
+        failed,
+    })
+}
+
+fn collect_files(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            if recursive {
+                collect_files(&path, recursive, files)?;
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "json" || ext == "jsonl")
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for DirProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let human = f.alternate();
+        let size = |bytes: usize| {
+            if human {
+                HumanSize(bytes).to_string()
+            } else {
+                format!("{bytes} bytes")
+            }
+        };
+        let stats = &self.stats;
+        write!(f, "files:           {}", self.files)?;
+        if !self.failed.is_empty() {
+            write!(f, " ({} failed)", self.failed.len())?;
+        }
+        writeln!(f)?;
+        writeln!(f, "documents:       {}", stats.documents)?;
+        writeln!(f, "total size:      {}", size(stats.total_size))?;
+        writeln!(f, "mean size:       {}", size(stats.mean.round() as usize))?;
+        write!(
+            f,
+            "p50 / p90 / p99: {} / {} / {}",
+            size(stats.p50),
+            size(stats.p90),
+            size(stats.p99)
+        )?;
+        if !stats.heaviest_keys.is_empty() {
+            write!(f, "\nheaviest keys:")?;
+            for k in &stats.heaviest_keys {
+                let cell = if human {
+                    HumanSize(k.bytes).to_string()
+                } else {
+                    k.bytes.to_string()
+                };
+                write!(
+                    f,
+                    "\n  {cell:>10}  {} ({} occurrences)",
+                    k.key, k.occurrences
+                )?;
+            }
+        }
+        for e in &self.failed {
+            write!(f, "\nfailed: {}: {}", e.path.display(), e.error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sizeof_val;
+
+    /// A fresh directory under the system temp directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("json_size_{name}_{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(dir.join("nested")).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_merges_files() {
+        let dir = TempDir::new("merges_files");
+        fs::write(dir.0.join("a.json"), r#"{"blob": "xxxxxxxxxx"}"#).unwrap();
+        fs::write(dir.0.join("nested/b.jsonl"), "[1]\n[2, 3]\n").unwrap();
+        fs::write(dir.0.join("notes.txt"), "not json").unwrap();
+
+        let profile = profile_dir(&dir.0, &ProfileOptions::default()).unwrap();
+        assert_eq!(profile.files, 2);
+        assert!(profile.failed.is_empty());
+        // Parse the documents the same way, as parsed arrays carry spare capacity.
+        let expected = [r#"{"blob": "xxxxxxxxxx"}"#, "[1]", "[2, 3]"]
+            .iter()
+            .map(|doc| sizeof_val(&serde_json::from_str(doc).unwrap()))
+            .sum::<usize>();
+        assert_eq!(profile.stats.documents, 3);
+        assert_eq!(profile.stats.total_size, expected);
+        assert_eq!(profile.stats.heaviest_keys[0].key, "blob");
+
+        let flat = ProfileOptions {
+            recursive: false,
+            ..ProfileOptions::default()
+        };
+        assert_eq!(profile_dir(&dir.0, &flat).unwrap().stats.documents, 1);
+    }
+
+    #[test]
+    fn test_failed_files_are_skipped() {
+        let dir = TempDir::new("failed_files");
+        fs::write(dir.0.join("good.json"), "{}").unwrap();
+        fs::write(dir.0.join("bad.jsonl"), "{}\n{oops").unwrap();
+
+        let profile = profile_dir(&dir.0, &ProfileOptions::default()).unwrap();
+        assert_eq!(profile.files, 1);
+        assert_eq!(profile.stats.documents, 1);
+        assert_eq!(profile.failed.len(), 1);
+        assert!(profile.failed[0].path.ends_with("bad.jsonl"));
+        assert!(profile.to_string().contains("(1 failed)"));
+    }
+
+    #[test]
+    fn test_missing_dir_is_an_error() {
+        let missing = std::env::temp_dir().join("json_size_no_such_dir");
+        assert!(profile_dir(missing, &ProfileOptions::default()).is_err());
+    }
+}
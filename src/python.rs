@@ -0,0 +1,56 @@
+//! Python bindings, enabled with the `python` feature and built with `maturin`.
+//!
+//! Every function accepts either a `str` holding a JSON document or any object that
+//! `json.dumps` can serialize (dicts, lists, numbers, ...). The object is converted to a
+//! `serde_json::Value` first, so the sizes are exactly the ones the Rust API reports.
+
+use crate::SizeReport;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyString};
+use serde_json::Value;
+
+fn to_value(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let text: String = match obj.cast::<PyString>() {
+        Ok(s) => s.to_str()?.to_owned(),
+        Err(_) => obj
+            .py()
+            .import("json")?
+            .call_method1("dumps", (obj,))?
+            .extract()?,
+    };
+    serde_json::from_str(&text).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Estimated in-memory size in bytes of the document as a `serde_json::Value`.
+#[pyfunction]
+#[pyo3(name = "sizeof_val")]
+fn py_sizeof_val(obj: &Bound<'_, PyAny>) -> PyResult<usize> {
+    Ok(crate::sizeof_val(&to_value(obj)?))
+}
+
+/// Size profile of the document as a dict with `estimated_size`, `serialized_size`,
+/// `escape_inflation` and `heaviest`, a list of `(json_pointer, size)` tuples, heaviest first.
+#[pyfunction]
+#[pyo3(signature = (obj, top = 10))]
+fn report<'py>(obj: &Bound<'py, PyAny>, top: usize) -> PyResult<Bound<'py, PyDict>> {
+    let report = SizeReport::new(&to_value(obj)?, top);
+    let py = obj.py();
+    let heaviest = PyList::empty(py);
+    for p in &report.heaviest {
+        heaviest.append((p.path.to_string(), p.size))?;
+    }
+    let dict = PyDict::new(py);
+    dict.set_item("estimated_size", report.estimated_size)?;
+    dict.set_item("serialized_size", report.serialized_size)?;
+    dict.set_item("escape_inflation", report.escapes.inflation)?;
+    dict.set_item("heaviest", heaviest)?;
+    Ok(dict)
+}
+
+#[pymodule]
+fn json_size(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_sizeof_val, m)?)?;
+    m.add_function(wrap_pyfunction!(report, m)?)?;
+    Ok(())
+}
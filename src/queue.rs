@@ -0,0 +1,184 @@
+use crate::{sizeof_val, Rejected};
+use serde_json::Value;
+use std::collections::{vec_deque, VecDeque};
+use std::iter::Map;
+
+/// A FIFO buffer of `Value`s whose total estimated size never exceeds a byte cap.
+///
+/// Each value is sized once, by [`crate::sizeof_val`], when it is pushed. A push that would take
+/// the total over the cap first evicts the oldest values, handing them back, so a replay buffer
+/// can log or persist what it dropped. A value larger than the cap on its own is refused. The
+/// total covers the values only, not the queue's own storage.
+///
+/// ## Example
+/// ```
+/// use json_size::{sizeof_val, SizeBoundedQueue};
+/// use serde_json::json;
+///
+/// let event = |n: u32| json!({"seq": n, "kind": "click"});
+/// let mut replay = SizeBoundedQueue::new(3 * sizeof_val(&event(0)));
+/// for n in 0..3 {
+///     assert!(replay.push(event(n)).unwrap().is_empty());
+/// }
+/// let evicted = replay.push(event(3)).unwrap();
+/// assert_eq!(evicted, [event(0)]);
+/// assert_eq!(replay.front(), Some(&event(1)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeBoundedQueue {
+    items: VecDeque<(Value, usize)>,
+    total: usize,
+    max_bytes: usize,
+}
+
+impl SizeBoundedQueue {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            items: VecDeque::new(),
+            total: 0,
+            max_bytes,
+        }
+    }
+
+    /// Estimated size of the values in the queue.
+    pub fn total_size(&self) -> usize {
+        self.total
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Appends `value`, evicting the oldest values until it fits, and returns the evicted ones,
+    /// oldest first.
+    ///
+    /// If `value` alone is larger than the cap, it is handed back and the queue is left as is.
+    pub fn push(&mut self, value: Value) -> Result<Vec<Value>, Rejected<Value>> {
+        let size = sizeof_val(&value);
+        if size > self.max_bytes {
+            return Err(Rejected {
+                item: value,
+                size,
+                remaining: self.max_bytes - self.total,
+            });
+        }
+        let evicted = self.evict_until(self.max_bytes - size);
+        self.items.push_back((value, size));
+        self.total += size;
+        Ok(evicted)
+    }
+
+    /// Removes and returns the oldest value.
+    pub fn pop_front(&mut self) -> Option<Value> {
+        let (value, size) = self.items.pop_front()?;
+        self.total -= size;
+        Some(value)
+    }
+
+    pub fn front(&self) -> Option<&Value> {
+        self.items.front().map(|(value, _)| value)
+    }
+
+    pub fn back(&self) -> Option<&Value> {
+        self.items.back().map(|(value, _)| value)
+    }
+
+    /// Iterates over the values, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Value> + ExactSizeIterator {
+        self.items.iter().map(|(value, _)| value)
+    }
+
+    /// Changes the cap, evicting the oldest values until the total fits, and returns the evicted
+    /// ones, oldest first.
+    pub fn set_max_bytes(&mut self, max_bytes: usize) -> Vec<Value> {
+        self.max_bytes = max_bytes;
+        self.evict_until(max_bytes)
+    }
+
+    /// Removes every value.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.total = 0;
+    }
+
+    /// Removes every value, returning them oldest first.
+    pub fn drain(&mut self) -> impl DoubleEndedIterator<Item = Value> + ExactSizeIterator + '_ {
+        self.total = 0;
+        self.items.drain(..).map(|(value, _)| value)
+    }
+
+    fn evict_until(&mut self, total: usize) -> Vec<Value> {
+        let mut evicted = Vec::new();
+        while self.total > total {
+            evicted.extend(self.pop_front());
+        }
+        evicted
+    }
+}
+
+impl IntoIterator for SizeBoundedQueue {
+    type Item = Value;
+    type IntoIter = Map<vec_deque::IntoIter<(Value, usize)>, fn((Value, usize)) -> Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter().map(|(value, _)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_evicts_oldest_until_it_fits() {
+        let small = json!(1);
+        let big = json!("x".repeat(100));
+        let mut queue = SizeBoundedQueue::new(3 * sizeof_val(&small) + sizeof_val(&big));
+        for _ in 0..3 {
+            assert!(queue.push(small.clone()).unwrap().is_empty());
+        }
+        queue.push(json!("y")).unwrap();
+        // Making room for `big` takes the two oldest numbers and leaves the rest.
+        let evicted = queue.push(big.clone()).unwrap();
+        assert_eq!(evicted, [small.clone(), small.clone()]);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(
+            queue.total_size(),
+            queue.iter().map(sizeof_val).sum::<usize>()
+        );
+        assert!(queue.total_size() <= queue.max_bytes());
+        assert_eq!(queue.back(), Some(&big));
+    }
+
+    #[test]
+    fn test_refuses_oversized_values() {
+        let mut queue = SizeBoundedQueue::new(100);
+        queue.push(json!(null)).unwrap();
+        let rejected = queue.push(json!("x".repeat(100))).unwrap_err();
+        assert_eq!(rejected.remaining, 100 - sizeof_val(&json!(null)));
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn test_shrinking_the_cap_evicts() {
+        let mut queue = SizeBoundedQueue::new(1000);
+        for n in 0..10 {
+            queue.push(json!(n)).unwrap();
+        }
+        let evicted = queue.set_max_bytes(3 * sizeof_val(&json!(0)));
+        assert_eq!(evicted.len(), 7);
+        assert_eq!(
+            queue.into_iter().collect::<Vec<_>>(),
+            [json!(7), json!(8), json!(9)]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use serde_json::Value;
+use std::fmt;
+use std::io;
+
+/// The error a [`QuotaWriter`] fails with, carried inside an `io::Error` of kind
+/// `io::ErrorKind::Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    /// The quota, in bytes.
+    pub limit: usize,
+    /// Bytes the output would have reached with the refused write.
+    pub attempted: usize,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "writing {} bytes would exceed the quota of {} bytes",
+            self.attempted, self.limit
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+impl QuotaExceeded {
+    /// The `QuotaExceeded` inside `err`, if `err` was raised by a [`QuotaWriter`].
+    pub fn from_io_error(err: &io::Error) -> Option<&Self> {
+        err.get_ref()?.downcast_ref()
+    }
+}
+
+/// An `io::Write` wrapper that fails instead of writing more than `max_bytes` to `inner`.
+///
+/// A write that would take the output over the quota is refused as a whole, so `inner` never
+/// receives more than `max_bytes`, and every later write fails as well. This lets serializing an
+/// over-limit document stop as soon as the limit is crossed instead of after producing all of
+/// it. When buffering, put the `BufWriter` inside the `QuotaWriter`, so that bytes are counted
+/// as they are produced.
+///
+/// ## Example
+/// ```
+/// use json_size::{QuotaExceeded, QuotaWriter};
+/// use serde_json::json;
+/// use std::io::Write;
+///
+/// let mut w = QuotaWriter::new(Vec::new(), 8);
+/// w.write_all(b"12345").unwrap();
+/// let err = w.write_all(b"6789").unwrap_err();
+/// assert_eq!(QuotaExceeded::from_io_error(&err).unwrap().attempted, 9);
+/// assert_eq!(w.into_inner(), b"12345");
+/// ```
+#[derive(Debug)]
+pub struct QuotaWriter<W> {
+    inner: W,
+    limit: usize,
+    written: usize,
+    exceeded: bool,
+}
+
+impl<W: io::Write> QuotaWriter<W> {
+    pub fn new(inner: W, max_bytes: usize) -> Self {
+        Self {
+            inner,
+            limit: max_bytes,
+            written: 0,
+            exceeded: false,
+        }
+    }
+
+    /// Bytes passed on to the inner writer so far.
+    pub fn bytes_written(&self) -> usize {
+        self.written
+    }
+
+    /// Bytes that can still be written before the quota is exceeded.
+    pub fn remaining(&self) -> usize {
+        self.limit - self.written
+    }
+
+    /// Whether a write was refused.
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: io::Write> io::Write for QuotaWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.exceeded || buf.len() > self.remaining() {
+            self.exceeded = true;
+            return Err(io::Error::other(QuotaExceeded {
+                limit: self.limit,
+                attempted: self.written.saturating_add(buf.len()),
+            }));
+        }
+        let n = self.inner.write(buf)?;
+        self.written += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Serializes `v` as compact JSON into `w`, failing as soon as the output would exceed
+/// `max_bytes`, and returns the number of bytes written.
+///
+/// Built on [`QuotaWriter`]: on failure `w` holds at most `max_bytes` of truncated output, and
+/// [`QuotaExceeded::from_io_error`] tells the quota apart from errors of `w` itself. Use
+/// [`crate::serialized_size`] instead to learn the full size without writing anything.
+///
+/// ## Example
+/// ```
+/// use json_size::{to_writer_bounded, QuotaExceeded};
+/// use serde_json::json;
+///
+/// let val = json!({"items": vec!["x".repeat(100); 100]});
+/// let mut out = Vec::new();
+/// let err = to_writer_bounded(&val, &mut out, 1024).unwrap_err();
+/// assert!(QuotaExceeded::from_io_error(&err).is_some());
+/// assert!(out.len() <= 1024);
+///
+/// out.clear();
+/// assert_eq!(to_writer_bounded(&json!([1, 2]), &mut out, 1024).unwrap(), 5);
+/// ```
+pub fn to_writer_bounded<W: io::Write>(v: &Value, w: W, max_bytes: usize) -> io::Result<usize> {
+    let mut w = QuotaWriter::new(w, max_bytes);
+    serde_json::to_writer(&mut w, v)?;
+    Ok(w.bytes_written())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialized_size;
+    use serde_json::json;
+    use std::io::Write;
+
+    #[test]
+    fn test_limit_is_exact() {
+        let val = json!({"a": ["text", 1.5, null], "b": {"c": true}});
+        let size = serialized_size(&val);
+        let mut out = Vec::new();
+        assert_eq!(to_writer_bounded(&val, &mut out, size).unwrap(), size);
+        assert_eq!(out, serde_json::to_vec(&val).unwrap());
+        out.clear();
+        let err = to_writer_bounded(&val, &mut out, size - 1).unwrap_err();
+        let exceeded = QuotaExceeded::from_io_error(&err).unwrap();
+        assert_eq!(exceeded.limit, size - 1);
+        assert!(out.len() < size);
+    }
+
+    #[test]
+    fn test_refusals_are_sticky() {
+        let mut w = QuotaWriter::new(Vec::new(), 4);
+        w.write_all(b"abc").unwrap();
+        assert_eq!(w.remaining(), 1);
+        assert!(w.write_all(b"de").is_err());
+        assert!(w.is_exceeded());
+        // Fits in the quota, but the output is already truncated.
+        assert!(w.write_all(b"d").is_err());
+        assert_eq!(w.bytes_written(), 3);
+        assert_eq!(w.get_ref(), b"abc");
+    }
+
+    #[test]
+    fn test_inner_errors_pass_through() {
+        struct Broken;
+        impl Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = to_writer_bounded(&json!([1]), Broken, 100).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert!(QuotaExceeded::from_io_error(&err).is_none());
+    }
+}
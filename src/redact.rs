@@ -0,0 +1,100 @@
+use crate::{entry_overhead, map_overhead, own_size, sizeof_val};
+use serde::Serialize;
+use serde_json::Value;
+
+/// The size of a document with and without a set of redacted keys, created by
+/// [`redaction_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RedactionReport {
+    /// Estimated size of the document as is.
+    pub full_size: usize,
+    /// Estimated size once every member with a redacted key is removed.
+    pub redacted_size: usize,
+    /// Number of members removed. Members nested inside a removed member are not counted.
+    pub removed_members: usize,
+}
+
+impl RedactionReport {
+    /// Bytes saved by the redaction.
+    pub fn saved(&self) -> usize {
+        self.full_size - self.redacted_size
+    }
+}
+
+/// Estimates the size of `v` with every object member whose key is in `keys` removed, at any
+/// depth, without modifying or cloning `v`.
+///
+/// ## Example
+/// ```
+/// use json_size::{redaction_report, sizeof_val};
+/// use serde_json::json;
+///
+/// let val = json!({"user": {"name": "ada", "password": "hunter2"}, "raw_payload": "..."});
+/// let report = redaction_report(&val, &["password", "raw_payload"]);
+/// assert_eq!(report.full_size, sizeof_val(&val));
+/// assert_eq!(report.redacted_size, sizeof_val(&json!({"user": {"name": "ada"}})));
+/// assert_eq!(report.removed_members, 2);
+/// ```
+pub fn redaction_report(v: &Value, keys: &[&str]) -> RedactionReport {
+    let mut removed_members = 0;
+    let redacted_size = redacted(v, keys, &mut removed_members);
+    RedactionReport {
+        full_size: sizeof_val(v),
+        redacted_size,
+        removed_members,
+    }
+}
+
+fn redacted(v: &Value, keys: &[&str], removed: &mut usize) -> usize {
+    match v {
+        Value::Array(a) => a.iter().fold(own_size(v), |acc, item| {
+            acc.saturating_add(redacted(item, keys, removed))
+        }),
+        Value::Object(o) => {
+            let mut kept = 0;
+            let members = o.iter().fold(0usize, |acc, (k, item)| {
+                if keys.contains(&k.as_str()) {
+                    *removed += 1;
+                    acc
+                } else {
+                    kept += 1;
+                    acc.saturating_add(entry_overhead(k) + redacted(item, keys, removed))
+                }
+            });
+            // The bookkeeping of the map shrinks with the members removed.
+            (own_size(v) - map_overhead(o.len()) + map_overhead(kept)).saturating_add(members)
+        }
+        _ => own_size(v),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_removes_keys_at_every_depth() {
+        let val = json!([{"secret": 1, "keep": {"secret": [1, 2]}}, {"secret": "x"}]);
+        let report = redaction_report(&val, &["secret"]);
+        assert_eq!(report.redacted_size, sizeof_val(&json!([{"keep": {}}, {}])));
+        assert_eq!(report.removed_members, 3);
+        assert_eq!(report.saved(), report.full_size - report.redacted_size);
+    }
+
+    #[test]
+    fn test_nested_redacted_members_counted_once() {
+        let val = json!({"raw": {"raw": "inner"}});
+        let report = redaction_report(&val, &["raw"]);
+        assert_eq!(report.redacted_size, sizeof_val(&json!({})));
+        assert_eq!(report.removed_members, 1);
+    }
+
+    #[test]
+    fn test_no_keys() {
+        let val = json!({"a": "b"});
+        let report = redaction_report(&val, &[]);
+        assert_eq!(report.saved(), 0);
+        assert_eq!(report.removed_members, 0);
+    }
+}
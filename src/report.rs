@@ -0,0 +1,443 @@
+use crate::{
+    btree, escape_inflation, serialized, serialized_size, HumanSize, JsonPath, PathSegment,
+    SizeEstimator,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::ops::ControlFlow;
+
+/// The estimated size of the subtree found at `path`.
+///
+/// For object members the size includes the key and the map entry overhead, i.e. the number of
+/// bytes that would be freed by removing the member.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PathSize {
+    pub path: JsonPath,
+    pub size: usize,
+}
+
+impl Ord for PathSize {
+    // Heavier first, then by path so the order is deterministic.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .size
+            .cmp(&self.size)
+            .then_with(|| self.path.cmp(&other.path))
+    }
+}
+
+impl PartialOrd for PathSize {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// How much JSON string escaping inflates the serialized size.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EscapeStats {
+    /// Number of strings, values and keys, that need any escaping.
+    pub escaped_strings: usize,
+    /// Total bytes added by escaping, see [`crate::escape_inflation`].
+    pub inflation: usize,
+    /// The string inflated the most, with the bytes escaping adds to it. For keys, the path is
+    /// that of the member.
+    pub worst: Option<PathSize>,
+}
+
+impl EscapeStats {
+    fn add(&mut self, path: &JsonPath, s: &str) {
+        let inflation = escape_inflation(s);
+        if inflation == 0 {
+            return;
+        }
+        self.escaped_strings += 1;
+        self.inflation += inflation;
+        if self.worst.as_ref().is_none_or(|w| inflation > w.size) {
+            self.worst = Some(PathSize {
+                path: path.clone(),
+                size: inflation,
+            });
+        }
+    }
+}
+
+/// Estimated number of heap allocations behind a `serde_json::Value`, by what they hold.
+///
+/// Counts follow the layout of `serde_json::Value` rather than the cost model of the estimator:
+/// one per string or key with a non-empty buffer, one per array with a non-empty buffer, and
+/// one per B-tree node of each object. Numbers are stored inline, so they never allocate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AllocationCounts {
+    pub strings: usize,
+    pub keys: usize,
+    pub arrays: usize,
+    pub map_nodes: usize,
+}
+
+impl AllocationCounts {
+    pub fn total(&self) -> usize {
+        self.strings + self.keys + self.arrays + self.map_nodes
+    }
+
+    fn add(&mut self, path: &JsonPath, node: &Value) {
+        if let Some(PathSegment::Key(k)) = path.segments().last() {
+            self.keys += usize::from(!k.is_empty());
+        }
+        match node {
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+            Value::String(s) => self.strings += usize::from(s.capacity() > 0),
+            Value::Array(a) => self.arrays += usize::from(a.capacity() > 0),
+            Value::Object(o) => {
+                let (leaves, internals) = btree::node_counts(o.len());
+                self.map_nodes += leaves + internals;
+            }
+        }
+    }
+}
+
+/// What the text of the strings in a document, values and keys, is made of.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct StringEncodingStats {
+    /// Number of strings, values and keys.
+    pub strings: usize,
+    pub chars: usize,
+    /// UTF-8 bytes of ASCII characters.
+    pub ascii_bytes: usize,
+    /// UTF-8 bytes of characters taking two to four bytes.
+    pub multibyte_bytes: usize,
+    /// Characters above U+00FF, which latin-1 cannot store.
+    pub non_latin1_chars: usize,
+    /// Bytes the text would take in UTF-16.
+    pub utf16_size: usize,
+}
+
+impl StringEncodingStats {
+    /// UTF-8 bytes of all the text.
+    pub fn bytes(&self) -> usize {
+        self.ascii_bytes + self.multibyte_bytes
+    }
+
+    /// Average UTF-8 bytes per character, 0 if there is no text.
+    pub fn bytes_per_char(&self) -> f64 {
+        if self.chars == 0 {
+            0.0
+        } else {
+            self.bytes() as f64 / self.chars as f64
+        }
+    }
+
+    /// Bytes the text would take in latin-1, if every character is in U+0000..=U+00FF.
+    pub fn latin1_size(&self) -> Option<usize> {
+        (self.non_latin1_chars == 0).then_some(self.chars)
+    }
+
+    fn add(&mut self, s: &str) {
+        self.strings += 1;
+        for c in s.chars() {
+            self.chars += 1;
+            if c.is_ascii() {
+                self.ascii_bytes += 1;
+            } else {
+                self.multibyte_bytes += c.len_utf8();
+            }
+            self.non_latin1_chars += usize::from(u32::from(c) > 0xff);
+            self.utf16_size += 2 * c.len_utf16();
+        }
+    }
+}
+
+/// A size profile of a `serde_json::Value`.
+///
+/// The `Display` impl prints sizes in bytes; the alternate form (`{:#}`) prints them as
+/// [`HumanSize`]s instead.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::{sizeof_val, SizeReport};
+///
+/// let val = json!({"id": 7, "avatar": "iVBORw0KGgoAAAANSUhEUgAA"});
+/// let report = SizeReport::new(&val, 1);
+/// assert_eq!(report.estimated_size, sizeof_val(&val));
+/// assert_eq!(report.heaviest[0].path.to_string(), "/avatar");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeReport {
+    /// Estimated in-memory size, as computed by [`crate::sizeof_val`].
+    pub estimated_size: usize,
+    /// Length of the compact JSON serialization.
+    pub serialized_size: usize,
+    /// How much of `serialized_size` is due to string escaping.
+    pub escapes: EscapeStats,
+    /// Heap allocations making up `estimated_size`.
+    pub allocations: AllocationCounts,
+    /// How the text of the strings is encoded.
+    pub strings: StringEncodingStats,
+    /// The heaviest subtrees below the root, heaviest first.
+    pub heaviest: Vec<PathSize>,
+}
+
+impl SizeReport {
+    /// Profiles `v`, keeping the `top_n` heaviest paths.
+    pub fn new(v: &Value, top_n: usize) -> Self {
+        Self::with_estimator(v, top_n, &SizeEstimator::DEFAULT)
+    }
+
+    /// Same as [`SizeReport::new`], with sizes computed by `estimator`.
+    pub fn with_estimator(v: &Value, top_n: usize, estimator: &SizeEstimator) -> Self {
+        // Grown as paths are kept, as `top_n` may well exceed the number of nodes.
+        let mut heap = BinaryHeap::new();
+        let mut escapes = EscapeStats::default();
+        let mut allocations = AllocationCounts::default();
+        let mut strings = StringEncodingStats::default();
+        let walked = estimator.walk_sizes(v, |path, node, size| {
+            if !path.is_root() && top_n > 0 {
+                offer(&mut heap, top_n, path, size);
+            }
+            if let Some(PathSegment::Key(k)) = path.segments().last() {
+                escapes.add(path, k);
+                strings.add(k);
+            }
+            if let Value::String(s) = node {
+                escapes.add(path, s);
+                strings.add(s);
+            }
+            allocations.add(path, node);
+            ControlFlow::Continue(())
+        });
+        let ControlFlow::Continue(estimated_size) = walked else {
+            unreachable!("the callback never breaks")
+        };
+        debug_assert_eq!(estimated_size, estimator.estimate(v));
+        Self {
+            estimated_size,
+            serialized_size: serialized_size(v),
+            escapes,
+            allocations,
+            strings,
+            heaviest: heap.into_sorted_vec(),
+        }
+    }
+
+    /// Ratio of `estimated_size` to `serialized_size`, see [`crate::bloat_factor`].
+    pub fn bloat_factor(&self) -> f64 {
+        serialized::ratio(self.estimated_size, self.serialized_size)
+    }
+}
+
+/// Keeps `path` in `heap` if it is among the `top_n` heaviest paths seen so far.
+fn offer(heap: &mut BinaryHeap<PathSize>, top_n: usize, path: &JsonPath, size: usize) {
+    // Heavier entries order first, so the top of the heap is the lightest one kept so far.
+    let heavier = heap
+        .peek()
+        .is_none_or(|min| size > min.size || (size == min.size && *path < min.path));
+    if heap.len() < top_n || heavier {
+        heap.push(PathSize {
+            path: path.clone(),
+            size,
+        });
+        if heap.len() > top_n {
+            heap.pop();
+        }
+    }
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let human = f.alternate();
+        let size = |bytes: usize| {
+            if human {
+                HumanSize(bytes).to_string()
+            } else {
+                format!("{bytes} bytes")
+            }
+        };
+        writeln!(f, "estimated size:  {}", size(self.estimated_size))?;
+        write!(f, "serialized size: {}", size(self.serialized_size))?;
+        if self.escapes.inflation > 0 {
+            write!(
+                f,
+                " ({} from escaping {} strings)",
+                size(self.escapes.inflation),
+                self.escapes.escaped_strings
+            )?;
+        }
+        write!(f, "\nbloat factor:    {:.2}x", self.bloat_factor())?;
+        let a = &self.allocations;
+        write!(
+            f,
+            "\nallocations:     {} ({} strings, {} keys, {} arrays, {} map nodes)",
+            a.total(),
+            a.strings,
+            a.keys,
+            a.arrays,
+            a.map_nodes
+        )?;
+        let t = &self.strings;
+        if t.chars > 0 {
+            let latin1 = t.latin1_size().map_or_else(|| "n/a".to_string(), size);
+            write!(
+                f,
+                "\nstring text:     {}, {:.2} per char ({} multi-byte); latin-1 {latin1}, UTF-16 {}",
+                size(t.bytes()),
+                t.bytes_per_char(),
+                size(t.multibyte_bytes),
+                size(t.utf16_size)
+            )?;
+        }
+        if !self.heaviest.is_empty() {
+            write!(f, "\nheaviest paths:")?;
+            for p in &self.heaviest {
+                let cell = if human {
+                    HumanSize(p.size).to_string()
+                } else {
+                    p.size.to_string()
+                };
+                write!(f, "\n  {cell:>10}  {}", p.path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entry_overhead, sizeof_val};
+    use serde_json::json;
+
+    #[test]
+    fn test_report_sizes() {
+        let val = json!({"a": [1, 2], "b": "text"});
+        let report = SizeReport::new(&val, 10);
+        assert_eq!(report.estimated_size, sizeof_val(&val));
+        assert_eq!(
+            report.serialized_size,
+            serde_json::to_string(&val).unwrap().len()
+        );
+        assert_eq!(report.bloat_factor(), crate::bloat_factor(&val));
+        assert!(report.to_string().contains(&format!(
+            "\nbloat factor:    {:.2}x\n",
+            report.bloat_factor()
+        )));
+    }
+
+    #[test]
+    fn test_heaviest_includes_entry_overhead() {
+        let val = json!({"a": [1, 2], "b": "text"});
+        let report = SizeReport::new(&val, 10);
+        let a = report
+            .heaviest
+            .iter()
+            .find(|p| p.path.to_string() == "/a")
+            .unwrap();
+        assert_eq!(
+            a.size,
+            entry_overhead(&"a".to_string()) + sizeof_val(&json!([1, 2]))
+        );
+        // Every non-root node is listed: /a, /a/0, /a/1 and /b.
+        assert_eq!(report.heaviest.len(), 4);
+    }
+
+    #[test]
+    fn test_heaviest_is_sorted_and_truncated() {
+        let val = json!({"small": 1, "big": "x".repeat(100), "mid": "x".repeat(10)});
+        let report = SizeReport::new(&val, 2);
+        let paths: Vec<String> = report.heaviest.iter().map(|p| p.path.to_string()).collect();
+        assert_eq!(paths, ["/big", "/mid"]);
+    }
+
+    #[test]
+    fn test_unbounded_top_n() {
+        let report = SizeReport::new(&json!([1, "two", {"a": 3}]), usize::MAX);
+        // Every node but the root.
+        assert_eq!(report.heaviest.len(), 4);
+    }
+
+    #[test]
+    fn test_alternate_display_is_human_readable() {
+        let report = SizeReport::new(&json!("x".repeat(4096)), 1);
+        let text = format!("{report:#}");
+        assert!(text.starts_with("estimated size:  4.1 KiB\n"), "{text}");
+    }
+
+    #[test]
+    fn test_escape_stats() {
+        let val = json!({"plain": "text", "quo\"ted": ["line\nbreak", "\u{1}\u{2}"]});
+        let report = SizeReport::new(&val, 0);
+        let escapes = &report.escapes;
+        assert_eq!(escapes.escaped_strings, 3);
+        assert_eq!(escapes.inflation, 1 + 1 + 10);
+        let worst = escapes.worst.as_ref().unwrap();
+        assert_eq!(worst.path.to_string(), "/quo\"ted/1");
+        assert_eq!(worst.size, 10);
+        // Stripping the escaped characters removes the escapes and the characters themselves.
+        let stripped = json!({"plain": "text", "quoted": ["linebreak", ""]});
+        assert_eq!(
+            serialized_size(&val) - escapes.inflation,
+            serialized_size(&stripped) + 4
+        );
+    }
+
+    #[test]
+    fn test_allocation_counts() {
+        let val: Value = serde_json::from_str(r#"{"a": "x", "b": [1, 2.5, ""], "": []}"#).unwrap();
+        let a = SizeReport::new(&val, 0).allocations;
+        // Neither the empty string and key nor the empty array allocate.
+        assert_eq!(
+            a,
+            AllocationCounts {
+                strings: 1,
+                keys: 2,
+                arrays: 1,
+                map_nodes: 1,
+            }
+        );
+        let big: Value = (0..12).map(|i| (i.to_string(), json!({}))).collect();
+        // Two leaves and a root, and the empty maps have no nodes.
+        assert_eq!(SizeReport::new(&big, 0).allocations.map_nodes, 3);
+        assert!(SizeReport::new(&val, 0)
+            .to_string()
+            .contains("\nallocations:     5 (1 strings, 2 keys, 1 arrays, 1 map nodes)"));
+    }
+
+    #[test]
+    fn test_string_encoding_stats() {
+        let val = json!({"name": "Zoë", "city": "København", "emoji": "🙂"});
+        let report = SizeReport::new(&val, 0);
+        let t = report.strings;
+        assert_eq!((t.strings, t.chars), (6, 3 + 9 + 1 + 4 + 4 + 5));
+        assert_eq!(t.multibyte_bytes, 2 + 2 + 4);
+        assert_eq!(
+            t.bytes(),
+            val.as_object()
+                .unwrap()
+                .iter()
+                .map(|(k, v)| k.len() + v.as_str().unwrap().len())
+                .sum::<usize>()
+        );
+        // The emoji is outside latin-1, and a surrogate pair in UTF-16.
+        assert_eq!(t.non_latin1_chars, 1);
+        assert_eq!(t.latin1_size(), None);
+        assert_eq!(t.utf16_size, 2 * (t.chars + 1));
+        assert!(report
+            .to_string()
+            .contains("\nstring text:     31 bytes, 1.19 per char (8 bytes multi-byte); latin-1 n/a, UTF-16 54 bytes"));
+
+        let latin = SizeReport::new(&json!(["façade", "plain"]), 0).strings;
+        assert_eq!(latin.latin1_size(), Some(11));
+        assert_eq!(
+            SizeReport::new(&json!([1]), 0).strings.bytes_per_char(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_zero_top_n() {
+        let report = SizeReport::new(&json!([1, 2, 3]), 0);
+        assert!(report.heaviest.is_empty());
+    }
+}
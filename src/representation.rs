@@ -0,0 +1,209 @@
+use crate::collections::hash_table_size;
+use crate::sizeof_val;
+use serde::Serialize;
+use serde_json::{Number, Value};
+use std::collections::HashSet;
+use std::mem::size_of;
+
+/// Estimated footprints of one document in several JSON DOM libraries, created by
+/// [`representation_comparison`].
+///
+/// Each size includes the root value itself, like [`crate::sizeof_val`], and assumes the
+/// document was parsed from text. The simd-json and ijson sizes model the layouts of simd-json
+/// 0.18 and ijson 0.1 on a 64-bit target without depending on either crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RepresentationComparison {
+    /// `serde_json::Value`, as estimated by [`crate::sizeof_val`].
+    pub serde_json: usize,
+    /// `simd_json::OwnedValue`: 24-byte values, boxed arrays and objects, and objects stored as
+    /// a vector of entries up to 32 members and a SwissTable beyond.
+    pub simd_json: usize,
+    /// `ijson::IValue`: 8-byte values, with short strings and small numbers stored inline and
+    /// longer strings interned, so each distinct string is counted once. The global string cache
+    /// itself is not counted.
+    pub ijson: usize,
+}
+
+impl RepresentationComparison {
+    /// The library with the smallest footprint, and its size.
+    pub fn smallest(&self) -> (&'static str, usize) {
+        [
+            ("serde_json", self.serde_json),
+            ("simd-json", self.simd_json),
+            ("ijson", self.ijson),
+        ]
+        .into_iter()
+        .min_by_key(|&(_, size)| size)
+        .expect("the list is not empty")
+    }
+}
+
+/// Estimates the footprint of `v` under serde_json, simd-json and ijson at once.
+///
+/// ## Example
+/// ```
+/// use json_size::representation_comparison;
+/// use serde_json::json;
+///
+/// let val = json!([{"id": 1, "tags": ["a", "b"]}, {"id": 2, "tags": ["a", "c"]}]);
+/// let sizes = representation_comparison(&val);
+/// assert!(sizes.ijson < sizes.simd_json && sizes.simd_json < sizes.serde_json);
+/// assert_eq!(sizes.smallest().0, "ijson");
+/// ```
+pub fn representation_comparison(v: &Value) -> RepresentationComparison {
+    let mut interned = HashSet::new();
+    RepresentationComparison {
+        serde_json: sizeof_val(v),
+        simd_json: SIMD_VALUE + simd_json_heap(v),
+        ijson: IJSON_VALUE + ijson_heap(v, &mut interned),
+    }
+}
+
+/// `size_of::<simd_json::OwnedValue>()`.
+const SIMD_VALUE: usize = 24;
+/// `size_of::<halfbrown::HashMap<String, OwnedValue>>()`, boxed in every object.
+const SIMD_MAP: usize = 40;
+/// Members up to which halfbrown stores a map as a vector instead of a hash table.
+const SIMD_VEC_LIMIT: usize = 32;
+/// An object entry: a `String` key and a value.
+const SIMD_ENTRY: usize = 24 + SIMD_VALUE;
+
+fn simd_json_heap(v: &Value) -> usize {
+    match v {
+        Value::Null | Value::Bool(_) | Value::Number(_) => 0,
+        Value::String(s) => s.len(),
+        // A boxed `Vec` sized to fit.
+        Value::Array(a) => a.iter().fold(24 + a.len() * SIMD_VALUE, |acc, item| {
+            acc.saturating_add(simd_json_heap(item))
+        }),
+        Value::Object(o) => {
+            let table = if o.len() <= SIMD_VEC_LIMIT {
+                o.len() * SIMD_ENTRY
+            } else {
+                hash_table_size(o.len(), SIMD_ENTRY)
+            };
+            o.iter().fold(SIMD_MAP + table, |acc, (k, item)| {
+                acc.saturating_add(k.len() + simd_json_heap(item))
+            })
+        }
+    }
+}
+
+/// `size_of::<ijson::IValue>()`.
+const IJSON_VALUE: usize = 8;
+/// Length and capacity heading array and object allocations.
+const IJSON_HEADER: usize = 16;
+/// Strings up to this many bytes are stored inline.
+const IJSON_INLINE_STRING: usize = 7;
+/// Reference count, length and shard index heading an interned string.
+const IJSON_STRING_HEADER: usize = 16;
+/// Bytes of a number stored on the heap.
+const IJSON_HEAP_NUMBER: usize = 8;
+
+fn ijson_heap<'a>(v: &'a Value, interned: &mut HashSet<&'a str>) -> usize {
+    match v {
+        Value::Null | Value::Bool(_) => 0,
+        Value::Number(n) if ijson_inline_number(n) => 0,
+        Value::Number(_) => IJSON_HEAP_NUMBER,
+        Value::String(s) => ijson_string(s, interned),
+        Value::Array(a) if a.is_empty() => 0,
+        Value::Array(a) => a.iter().fold(
+            IJSON_HEADER + ijson_capacity(a.len()) * IJSON_VALUE,
+            |acc, item| acc.saturating_add(ijson_heap(item, interned)),
+        ),
+        Value::Object(o) if o.is_empty() => 0,
+        Value::Object(o) => {
+            // Key-value pairs followed by an index with a quarter more slots.
+            let cap = ijson_capacity(o.len());
+            let table = cap * 2 * IJSON_VALUE + (cap + cap / 4) * size_of::<usize>();
+            o.iter().fold(IJSON_HEADER + table, |acc, (k, item)| {
+                acc.saturating_add(ijson_string(k, interned))
+                    .saturating_add(ijson_heap(item, interned))
+            })
+        }
+    }
+}
+
+/// Capacity of an array or object grown one element at a time, as during parsing.
+fn ijson_capacity(len: usize) -> usize {
+    len.next_power_of_two().max(4)
+}
+
+fn ijson_string<'a>(s: &'a str, interned: &mut HashSet<&'a str>) -> usize {
+    if s.len() <= IJSON_INLINE_STRING || !interned.insert(s) {
+        return 0;
+    }
+    (IJSON_STRING_HEADER + s.len()).next_multiple_of(8)
+}
+
+/// Numbers are inline if they are a signed 56-bit mantissa times a power of two between `2^-7`
+/// and `2^7`. This checks integers below `2^55` and floats with at most seven binary fraction
+/// digits, such as `1.5`, and misses floats that only fit with a positive exponent.
+fn ijson_inline_number(n: &Number) -> bool {
+    const MANTISSA: f64 = (1u64 << 55) as f64;
+    if let Some(i) = n.as_i64() {
+        return i.unsigned_abs() < 1 << 55;
+    }
+    if n.as_u64().is_some() {
+        return false;
+    }
+    let scaled = n.as_f64().unwrap_or(f64::NAN) * 128.0;
+    scaled.fract() == 0.0 && scaled.abs() < MANTISSA
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_simd_json_layout() {
+        // Measured with a counting allocator on simd-json 0.18.
+        let heap = |v: Value| representation_comparison(&v).simd_json - SIMD_VALUE;
+        assert_eq!(heap(json!([])), 24);
+        assert_eq!(heap(json!([1, 2, 3, 4, 5, 6, 7, 8, 9, 10])), 264);
+        assert_eq!(heap(json!({})), 40);
+        assert_eq!(heap(json!({"a": 1, "b": 2})), 138);
+        assert_eq!(heap(json!(["abc", "abc"])), 78);
+        let object = |n: usize| -> Value {
+            (0..n)
+                .map(|i| (format!("k{i:03}"), Value::Null))
+                .collect::<serde_json::Map<_, _>>()
+                .into()
+        };
+        assert_eq!(heap(object(32)), 1704);
+        assert_eq!(heap(object(33)), 3324);
+        assert_eq!(heap(object(64)), 6584);
+    }
+
+    #[test]
+    fn test_ijson_layout() {
+        // Measured with a counting allocator on ijson 0.1.7, leaving out the string cache.
+        let heap = |v: Value| representation_comparison(&v).ijson - IJSON_VALUE;
+        assert_eq!(heap(json!([])), 0);
+        assert_eq!(heap(json!([1])), 48);
+        assert_eq!(heap(json!([1, 2, 3, 4, 5, 6, 7, 8, 9, 10])), 144);
+        assert_eq!(heap(json!([1000000, -5, 1e300, 300, 511, 512])), 88);
+        assert_eq!(heap(json!({"a": 1})), 120);
+        assert_eq!(
+            heap(json!({"k0": 0, "k1": 1, "k2": 2, "k3": 3, "k4": 4, "k5": 5, "k6": 6, "k7": 7})),
+            224
+        );
+        assert_eq!(heap(json!(1.5)), 0);
+        assert_eq!(heap(json!(0.1)), IJSON_HEAP_NUMBER);
+        assert_eq!(heap(json!("abc")), 0);
+        assert_eq!(heap(json!("abcdefghijklmnopqrstuvwxyz")), 48);
+        assert_eq!(heap(json!({"a long key!": 1})), 152);
+    }
+
+    #[test]
+    fn test_ijson_interns_strings() {
+        let long = "a string longer than seven bytes";
+        let once = representation_comparison(&json!([long]));
+        let twice = representation_comparison(&json!([long, long]));
+        // The second copy only costs its slot, which fits the same capacity.
+        assert_eq!(once.ijson, twice.ijson);
+        assert!(twice.simd_json > once.simd_json);
+        assert_eq!(twice.serde_json, sizeof_val(&json!([long, long])));
+    }
+}
@@ -0,0 +1,73 @@
+use serde_json::Value;
+
+/// Drops `v` without recursion, so that no nesting depth can overflow the stack.
+///
+/// The `Drop` of a `Value` recurses into its children, like [`crate::sizeof_val`] does, and a
+/// document nested a few hundred thousand levels deep, as built with serde_json's
+/// `unbounded_depth` feature or by hand, overflows the stack when it goes out of scope. This
+/// instead moves the children of every array and object onto a heap-allocated stack before
+/// dropping the emptied container, which costs one `Value` of memory per pending node.
+///
+/// ## Example
+/// ```
+/// use json_size::safe_drop;
+/// use serde_json::{json, Value};
+///
+/// let mut v = json!(null);
+/// for _ in 0..1_000_000 {
+///     v = Value::Array(vec![v]);
+/// }
+/// safe_drop(v);
+/// ```
+pub fn safe_drop(v: Value) {
+    let mut pending = vec![v];
+    while let Some(v) = pending.pop() {
+        match v {
+            Value::Array(items) => pending.extend(items.into_iter().filter(is_container)),
+            Value::Object(members) => pending.extend(
+                members
+                    .into_iter()
+                    .map(|(_, item)| item)
+                    .filter(is_container),
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// Only containers can recurse when dropped; anything else is dropped right away.
+fn is_container(v: &Value) -> bool {
+    matches!(v, Value::Array(_) | Value::Object(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Map};
+
+    /// Far deeper than the 2 MiB stack of a test thread allows to drop recursively.
+    const DEPTH: usize = 500_000;
+
+    #[test]
+    fn test_drops_deep_arrays_and_objects() {
+        let mut v = json!("leaf");
+        for i in 0..DEPTH {
+            v = if i % 2 == 0 {
+                Value::Array(vec![json!(i), v, json!([1, 2])])
+            } else {
+                let mut map = Map::new();
+                map.insert("next".into(), v);
+                map.insert("n".into(), json!(i));
+                Value::Object(map)
+            };
+        }
+        safe_drop(v);
+    }
+
+    #[test]
+    fn test_drops_wide_and_scalar_values() {
+        safe_drop(json!(1));
+        safe_drop(json!({"a": [[], {}], "b": "text"}));
+        safe_drop(Value::Array(vec![json!([null]); 100_000]));
+    }
+}
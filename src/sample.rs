@@ -0,0 +1,145 @@
+use crate::generate::Rng;
+use crate::sizeof_val;
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::collections::BinaryHeap;
+use std::hash::BuildHasher;
+
+/// An array element picked by [`sample_by_size`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SizeSample<'a> {
+    /// Position of the element in the array.
+    pub index: usize,
+    /// Estimated size of the element, as computed by [`crate::sizeof_val`].
+    pub size: usize,
+    pub value: &'a Value,
+}
+
+/// Picks `k` elements of the array `v` at random, each with a probability proportional to its
+/// estimated size, without replacement.
+///
+/// This is weighted reservoir sampling (Efraimidis and Spirakis' A-Res): a single pass that
+/// sizes every element once and keeps only `k` candidates, so it suits arrays far larger than
+/// `k`. The sample is ordered largest first, then by index. It holds every element if the array
+/// has at most `k`, and is empty if `v` is not an array.
+///
+/// ## Example
+/// ```
+/// use json_size::sample_by_size;
+/// use serde_json::json;
+///
+/// let mut rows = vec![json!(null); 1000];
+/// rows[512] = json!({"blob": "x".repeat(4_000_000)});
+/// let batch = json!(rows);
+/// let sample = sample_by_size(&batch, 3);
+/// assert_eq!(sample.len(), 3);
+/// // Picked all but surely, as it accounts for nearly all the bytes.
+/// assert_eq!(sample[0].index, 512);
+/// ```
+pub fn sample_by_size(v: &Value, k: usize) -> Vec<SizeSample<'_>> {
+    sample_by_size_seeded(v, k, RandomState::new().hash_one(k))
+}
+
+/// Same as [`sample_by_size`], with a seed, so the same document and seed always give the same
+/// sample.
+pub fn sample_by_size_seeded(v: &Value, k: usize, seed: u64) -> Vec<SizeSample<'_>> {
+    let Value::Array(items) = v else {
+        return Vec::new();
+    };
+    let mut rng = Rng(seed);
+    // Candidates are popped before others are pushed, so the heap never holds more than `k`.
+    let mut heap: BinaryHeap<Candidate> = BinaryHeap::with_capacity(k.min(items.len()));
+    for (index, item) in items.iter().enumerate() {
+        let size = sizeof_val(item);
+        // `u^(1/size)` for a uniform `u` in (0, 1], compared through its logarithm.
+        let unit = ((rng.next() >> 11) + 1) as f64 / (1u64 << 53) as f64;
+        let key = unit.ln() / size as f64;
+        if heap.len() < k {
+            heap.push(Candidate { key, index, size });
+        } else if heap.peek().is_some_and(|lowest| key > lowest.key) {
+            heap.pop();
+            heap.push(Candidate { key, index, size });
+        }
+    }
+    let mut sample: Vec<_> = heap
+        .into_iter()
+        .map(|c| SizeSample {
+            index: c.index,
+            size: c.size,
+            value: &items[c.index],
+        })
+        .collect();
+    sample.sort_by(|a, b| b.size.cmp(&a.size).then(a.index.cmp(&b.index)));
+    sample
+}
+
+/// An element kept in the reservoir, ordered so that the lowest key is on top of the heap.
+struct Candidate {
+    key: f64,
+    index: usize,
+    size: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.key.total_cmp(&self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_small_arrays_are_kept_whole() {
+        let val = json!(["a", "x".repeat(50), 3]);
+        let sample = sample_by_size_seeded(&val, 5, 1);
+        let indices: Vec<_> = sample.iter().map(|s| s.index).collect();
+        assert_eq!(indices, [1, 0, 2]);
+        assert_eq!(sample[0].size, sizeof_val(&val[1]));
+        assert_eq!(sample[0].value, &val[1]);
+        assert!(sample_by_size(&val, 0).is_empty());
+        assert_eq!(sample_by_size_seeded(&val, usize::MAX, 1), sample);
+        assert!(sample_by_size(&json!({"a": [1]}), 1).is_empty());
+    }
+
+    #[test]
+    fn test_seeded_samples_are_reproducible() {
+        let val = json!((0..100).map(|i| "x".repeat(i)).collect::<Vec<_>>());
+        assert_eq!(
+            sample_by_size_seeded(&val, 10, 42),
+            sample_by_size_seeded(&val, 10, 42)
+        );
+        assert_eq!(sample_by_size(&val, 10).len(), 10);
+    }
+
+    #[test]
+    fn test_picks_are_weighted_by_size() {
+        // One element holds half the bytes of the array.
+        let mut items = vec![json!(null); 100];
+        let rest: usize = items.iter().map(sizeof_val).sum();
+        items[0] = json!("x".repeat(rest - sizeof_val(&json!(""))));
+        let val = json!(items);
+        let picked = (0..1000)
+            .filter(|&seed| sample_by_size_seeded(&val, 1, seed)[0].index == 0)
+            .count();
+        assert!((400..600).contains(&picked), "{picked}");
+    }
+}
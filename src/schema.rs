@@ -0,0 +1,422 @@
+use crate::{sizeof_val, SizeEstimator};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::mem::size_of;
+
+/// Minimum, typical and maximum estimated sizes, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct SizeRange {
+    pub min: usize,
+    pub typical: usize,
+    pub max: usize,
+}
+
+/// Assumptions used by [`estimate_from_schema`] wherever the schema leaves a size open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaAssumptions {
+    /// Typical length in bytes of strings without a known `format`.
+    pub typical_string_len: usize,
+    /// Length of the longest string when the schema has no `maxLength`.
+    pub max_string_len: usize,
+    /// Typical number of array items.
+    pub typical_items: usize,
+    /// Number of array items when the schema has no `maxItems`.
+    pub max_items: usize,
+    /// Fraction of optional properties present in a typical document.
+    pub optional_property_rate: f64,
+    /// How many `$ref`s are followed along one path before a subschema is treated as `null`.
+    /// This bounds recursive schemas.
+    pub max_ref_depth: usize,
+}
+
+impl Default for SchemaAssumptions {
+    fn default() -> Self {
+        Self {
+            typical_string_len: 16,
+            max_string_len: 256,
+            typical_items: 4,
+            max_items: 64,
+            optional_property_rate: 0.5,
+            max_ref_depth: 8,
+        }
+    }
+}
+
+/// Predicts the estimated in-memory size of documents conforming to a JSON Schema.
+///
+/// Supports `type` (including lists of types), `properties` and `required`, `items` and
+/// `prefixItems`, `minLength`/`maxLength`, `minItems`/`maxItems`, `enum`, `const`, `oneOf`,
+/// `anyOf`, `allOf` and local `$ref`s (`#/...`). Other keywords are ignored, and so are
+/// `additionalProperties`, since they cannot be sized without an instance. Open-ended sizes come
+/// from `assumptions`.
+///
+/// `min` is the smallest conforming document, with only required properties; `max` has every
+/// property present and every bound at its limit; `typical` uses the typical sizes from
+/// `assumptions`.
+///
+/// ## Example
+/// ```
+/// use json_size::{estimate_from_schema, SchemaAssumptions};
+/// use serde_json::json;
+///
+/// let schema = json!({
+///     "type": "object",
+///     "required": ["id"],
+///     "properties": {
+///         "id": {"type": "integer"},
+///         "tags": {"type": "array", "maxItems": 10, "items": {"type": "string", "maxLength": 32}}
+///     }
+/// });
+/// let range = estimate_from_schema(&schema, &SchemaAssumptions::default());
+/// assert!(range.min <= range.typical && range.typical <= range.max);
+/// ```
+pub fn estimate_from_schema(schema: &Value, assumptions: &SchemaAssumptions) -> SizeRange {
+    let walker = SchemaWalker {
+        root: schema,
+        assumptions,
+        estimator: &SizeEstimator::DEFAULT,
+    };
+    walker.range(schema, 0).round()
+}
+
+/// A `SizeRange` with a fractional typical size, rounded once at the end.
+#[derive(Debug, Clone, Copy)]
+struct Range {
+    min: usize,
+    typical: f64,
+    max: usize,
+}
+
+impl Range {
+    fn exact(size: usize) -> Self {
+        Self {
+            min: size,
+            typical: size as f64,
+            max: size,
+        }
+    }
+
+    fn round(self) -> SizeRange {
+        SizeRange {
+            min: self.min,
+            typical: (self.typical.round() as usize).clamp(self.min, self.max),
+            max: self.max,
+        }
+    }
+
+    /// Either one of `ranges`, with the typical size averaged.
+    fn either(ranges: impl IntoIterator<Item = Range>) -> Option<Self> {
+        let mut count = 0;
+        let mut acc: Option<Range> = None;
+        for r in ranges {
+            count += 1;
+            acc = Some(match acc {
+                None => r,
+                Some(a) => Range {
+                    min: a.min.min(r.min),
+                    typical: a.typical + r.typical,
+                    max: a.max.max(r.max),
+                },
+            });
+        }
+        acc.map(|a| Range {
+            typical: a.typical / count as f64,
+            ..a
+        })
+    }
+}
+
+struct SchemaWalker<'a> {
+    root: &'a Value,
+    assumptions: &'a SchemaAssumptions,
+    estimator: &'a SizeEstimator,
+}
+
+impl SchemaWalker<'_> {
+    fn node() -> usize {
+        size_of::<Value>()
+    }
+
+    fn range(&self, schema: &Value, refs: usize) -> Range {
+        let Value::Object(schema) = schema else {
+            // `true` and `false` schemas say nothing about the instance.
+            return Range::exact(Self::node());
+        };
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer));
+            return match target {
+                Some(target) if refs < self.assumptions.max_ref_depth => {
+                    self.range(target, refs + 1)
+                }
+                _ => Range::exact(Self::node()),
+            };
+        }
+        if let Some(c) = schema.get("const") {
+            return Range::exact(sizeof_val(c));
+        }
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            if let Some(r) = Range::either(values.iter().map(|v| Range::exact(sizeof_val(v)))) {
+                return r;
+            }
+        }
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(Value::Array(options)) = schema.get(keyword) {
+                if let Some(r) = Range::either(options.iter().map(|s| self.range(s, refs))) {
+                    return r;
+                }
+            }
+        }
+        if let Some(Value::Array(parts)) = schema.get("allOf") {
+            // Every part describes the same instance, so the largest one bounds it best.
+            let parts = parts.iter().map(|s| self.range(s, refs));
+            if let Some(r) = parts.reduce(|a, b| Range {
+                min: a.min.max(b.min),
+                typical: a.typical.max(b.typical),
+                max: a.max.max(b.max),
+            }) {
+                return r;
+            }
+        }
+        match schema.get("type") {
+            Some(Value::String(ty)) => self.typed(ty, schema, refs),
+            Some(Value::Array(types)) => {
+                let types: Vec<&str> = types.iter().filter_map(Value::as_str).collect();
+                let ranges: Vec<Range> = types
+                    .iter()
+                    .map(|ty| self.typed(ty, schema, refs))
+                    .collect();
+                // A nullable type is typically present, so the first non-null type is typical.
+                let typical = types
+                    .iter()
+                    .position(|&ty| ty != "null")
+                    .map(|i| ranges[i].typical);
+                match Range::either(ranges) {
+                    Some(r) => Range {
+                        typical: typical.unwrap_or(r.typical),
+                        ..r
+                    },
+                    None => Range::exact(Self::node()),
+                }
+            }
+            _ if schema.contains_key("properties") => self.typed("object", schema, refs),
+            _ if schema.contains_key("items") || schema.contains_key("prefixItems") => {
+                self.typed("array", schema, refs)
+            }
+            _ if schema.contains_key("maxLength") || schema.contains_key("minLength") => {
+                self.typed("string", schema, refs)
+            }
+            _ => Range::exact(Self::node()),
+        }
+    }
+
+    fn typed(&self, ty: &str, schema: &Map<String, Value>, refs: usize) -> Range {
+        match ty {
+            "string" => self.string(schema),
+            "array" => self.array(schema, refs),
+            "object" => self.object(schema, refs),
+            _ => Range::exact(Self::node()),
+        }
+    }
+
+    fn string(&self, schema: &Map<String, Value>) -> Range {
+        let a = self.assumptions;
+        let min_len = uint(schema, "minLength").unwrap_or(0);
+        let max_len = uint(schema, "maxLength")
+            .unwrap_or(a.max_string_len)
+            .max(min_len);
+        let typical_len = schema
+            .get("format")
+            .and_then(Value::as_str)
+            .and_then(format_len)
+            .unwrap_or(a.typical_string_len)
+            .clamp(min_len, max_len);
+        let size = |len| Self::node() + self.estimator.string_buffer_size(len);
+        Range {
+            min: size(min_len),
+            typical: size(typical_len) as f64,
+            max: size(max_len),
+        }
+    }
+
+    fn array(&self, schema: &Map<String, Value>, refs: usize) -> Range {
+        let a = self.assumptions;
+        let prefix: Vec<Range> = match schema.get("prefixItems").or(schema.get("items")) {
+            Some(Value::Array(tuple)) => tuple.iter().map(|s| self.range(s, refs)).collect(),
+            _ => Vec::new(),
+        };
+        let items = match schema.get("items") {
+            Some(items @ Value::Object(_)) => Some(self.range(items, refs)),
+            Some(Value::Bool(false)) => None,
+            _ if prefix.is_empty() => Some(Range::exact(Self::node())),
+            _ => None,
+        };
+        let min_items = uint(schema, "minItems").unwrap_or(0);
+        let max_items = uint(schema, "maxItems")
+            .unwrap_or(if items.is_some() {
+                a.max_items
+            } else {
+                prefix.len()
+            })
+            .max(min_items);
+        let typical_items = a.typical_items.clamp(min_items, max_items);
+
+        // Item `i` uses the tuple schema if there is one, and `items` otherwise.
+        let item = |i: usize| prefix.get(i).copied().or(items);
+        let sum = |count: usize, pick: fn(Range) -> f64| -> f64 {
+            let tuple: f64 = (0..count.min(prefix.len()))
+                .filter_map(item)
+                .map(pick)
+                .sum();
+            let rest = count.saturating_sub(prefix.len()) as f64;
+            tuple + items.map_or(0.0, |r| pick(r) * rest)
+        };
+        // Parsed arrays are sized to fit, so only allocator rounding adds to the elements.
+        let node =
+            |count: usize| (Self::node() + self.estimator.array_overhead(count, count)) as f64;
+        Range {
+            min: saturate(node(min_items) + sum(min_items, |r| r.min as f64)),
+            typical: node(typical_items) + sum(typical_items, |r| r.typical),
+            max: saturate(node(max_items) + sum(max_items, |r| r.max as f64)),
+        }
+    }
+
+    fn object(&self, schema: &Map<String, Value>, refs: usize) -> Range {
+        let required: Vec<&str> = match schema.get("required") {
+            Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let mut range = Range::exact(Self::node());
+        let (mut min_members, mut typical_members, mut max_members) = (0, 0.0, 0);
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (name, property) in properties {
+                let entry = self.estimator.entry_overhead_for_len(name.len());
+                let child = self.range(property, refs);
+                let (min, typical) = if required.contains(&name.as_str()) {
+                    min_members += 1;
+                    typical_members += 1.0;
+                    (entry + child.min, entry as f64 + child.typical)
+                } else {
+                    let rate = self.assumptions.optional_property_rate;
+                    typical_members += rate;
+                    (0, rate * (entry as f64 + child.typical))
+                };
+                max_members += 1;
+                range.min = range.min.saturating_add(min);
+                range.typical += typical;
+                range.max = range.max.saturating_add(entry.saturating_add(child.max));
+            }
+        }
+        let map = |members| self.estimator.map_overhead(members);
+        range.min = range.min.saturating_add(map(min_members));
+        // Interpolate between the whole member counts around the expected one.
+        let (below, fraction) = (typical_members.floor(), typical_members.fract());
+        let below = saturate(below);
+        range.typical += map(below) as f64 * (1.0 - fraction) + map(below + 1) as f64 * fraction;
+        range.max = range.max.saturating_add(map(max_members));
+        range
+    }
+}
+
+fn uint(schema: &Map<String, Value>, keyword: &str) -> Option<usize> {
+    schema
+        .get(keyword)
+        .and_then(Value::as_u64)
+        .map(|n| usize::try_from(n).unwrap_or(usize::MAX))
+}
+
+fn saturate(size: f64) -> usize {
+    // Float to int casts saturate.
+    size as usize
+}
+
+/// Typical length of strings in well-known formats.
+pub(crate) fn format_len(format: &str) -> Option<usize> {
+    Some(match format {
+        "date-time" => 25,
+        "date" => 10,
+        "time" => 14,
+        "uuid" => 36,
+        "ipv4" => 13,
+        "ipv6" => 39,
+        "email" => 24,
+        "uri" | "url" => 48,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn estimate(schema: Value) -> SizeRange {
+        estimate_from_schema(&schema, &SchemaAssumptions::default())
+    }
+
+    #[test]
+    fn test_scalar() {
+        let r = estimate(json!({"type": "integer"}));
+        let node = size_of::<Value>();
+        assert_eq!(
+            r,
+            SizeRange {
+                min: node,
+                typical: node,
+                max: node
+            }
+        );
+    }
+
+    #[test]
+    fn test_string_bounds_match_instances() {
+        let r = estimate(json!({"type": "string", "minLength": 2, "maxLength": 10}));
+        assert_eq!(r.min, sizeof_val(&json!("ab")));
+        assert_eq!(r.max, sizeof_val(&json!("0123456789")));
+        assert_eq!(r.typical, r.max);
+    }
+
+    #[test]
+    fn test_object_required_and_optional() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {"id": {"type": "integer"}, "name": {"type": "string", "maxLength": 4}}
+        });
+        let r = estimate(schema);
+        assert_eq!(r.min, sizeof_val(&json!({"id": 1})));
+        assert_eq!(r.max, sizeof_val(&json!({"id": 1, "name": "abcd"})));
+        assert!(r.min < r.typical && r.typical < r.max);
+    }
+
+    #[test]
+    fn test_array_items() {
+        let r = estimate(
+            json!({"type": "array", "minItems": 1, "maxItems": 3, "items": {"type": "null"}}),
+        );
+        assert_eq!(r.min, sizeof_val(&json!([null])));
+        assert_eq!(r.max, sizeof_val(&json!([null, null, null])));
+    }
+
+    #[test]
+    fn test_enum_and_nullable() {
+        let r = estimate(json!({"enum": ["a", "bbbbbbbb"]}));
+        assert_eq!(r.min, sizeof_val(&json!("a")));
+        assert_eq!(r.max, sizeof_val(&json!("bbbbbbbb")));
+        let r = estimate(json!({"type": ["null", "string"], "maxLength": 8}));
+        assert_eq!(r.min, sizeof_val(&json!(null)));
+        assert_eq!(r.typical, sizeof_val(&json!("0123456789abcdef"[..8])));
+    }
+
+    #[test]
+    fn test_recursive_ref_terminates() {
+        let schema = json!({
+            "$defs": {"node": {"type": "object", "properties": {"next": {"$ref": "#/$defs/node"}}}},
+            "$ref": "#/$defs/node"
+        });
+        let r = estimate(schema);
+        assert_eq!(r.min, size_of::<Value>());
+        assert!(r.max > r.min);
+    }
+}
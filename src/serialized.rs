@@ -0,0 +1,128 @@
+use crate::sizeof_val;
+use serde_json::Value;
+use std::io;
+
+/// An `io::Write` sink that discards its input and only counts bytes.
+#[derive(Debug, Default)]
+pub(crate) struct CountingWriter {
+    pub(crate) count: usize,
+}
+
+impl io::Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.count += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Calculates the exact length in bytes of the compact JSON serialization of a `serde_json::Value`.
+///
+/// The value is serialized into a counting sink, so no output buffer is allocated.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::serialized_size;
+///
+/// let val = json!({"name": "bread", "amount": 2});
+/// assert_eq!(serialized_size(&val), r#"{"amount":2,"name":"bread"}"#.len());
+/// ```
+pub fn serialized_size(v: &Value) -> usize {
+    let mut w = CountingWriter::default();
+    serde_json::to_writer(&mut w, v).expect("serializing a Value into a counting sink cannot fail");
+    w.count
+}
+
+/// Ratio of the estimated in-memory size of `v`, as computed by [`crate::sizeof_val`], to the
+/// length of its compact serialization.
+///
+/// Documents made of many tiny strings, arrays and objects take many times their wire size in
+/// memory, where each node costs at least a `Value` and often a heap allocation, so a high
+/// factor flags documents that are cheap to receive but expensive to hold. Also available as
+/// [`crate::SizeReport::bloat_factor`].
+///
+/// ## Example
+/// ```
+/// use json_size::bloat_factor;
+/// use serde_json::json;
+///
+/// let text = json!("x".repeat(1000));
+/// let tiny = json!([[1], [2], [3], [4], [5], [6], [7], [8]]);
+/// assert!(bloat_factor(&text) < 1.1);
+/// assert!(bloat_factor(&tiny) > 10.0);
+/// ```
+pub fn bloat_factor(v: &Value) -> f64 {
+    ratio(sizeof_val(v), serialized_size(v))
+}
+
+/// `estimated` bytes in memory per serialized byte.
+pub(crate) fn ratio(estimated: usize, serialized: usize) -> f64 {
+    estimated as f64 / serialized.max(1) as f64
+}
+
+/// Number of bytes JSON escaping adds to `s` when serialized, not counting the quotes.
+///
+/// `"` and `\\` and the control characters `\b`, `\f`, `\n`, `\r` and `\t` take two bytes when
+/// escaped; other control characters become six-byte `\u00XX` sequences. Everything else,
+/// including non-ASCII text, is written as is.
+///
+/// ## Example
+/// ```
+/// use json_size::escape_inflation;
+///
+/// assert_eq!(escape_inflation("plain"), 0);
+/// assert_eq!(escape_inflation("say \"hi\"\n"), 3);
+/// assert_eq!(escape_inflation("\u{1}"), 5);
+/// ```
+pub fn escape_inflation(s: &str) -> usize {
+    s.bytes()
+        .map(|b| match b {
+            b'"' | b'\\' | b'\x08' | b'\x0c' | b'\n' | b'\r' | b'\t' => 1,
+            0x00..=0x1f => 5,
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_serialized_size_matches_to_string() {
+        let val = json!({"a": [1, 2.5, null, true], "b": "line\nbreak \"quoted\""});
+        assert_eq!(
+            serialized_size(&val),
+            serde_json::to_string(&val).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_bloat_factor() {
+        let val = json!({"a": [1, 2.5, null, true]});
+        assert_eq!(
+            bloat_factor(&val),
+            sizeof_val(&val) as f64 / serialized_size(&val) as f64
+        );
+        assert!(bloat_factor(&json!([{}, {}, {}])) > bloat_factor(&json!("[{},{},{}]")));
+    }
+
+    #[test]
+    fn test_escape_inflation_matches_serializer() {
+        let all_ascii: String = (0u8..128).map(char::from).collect();
+        for s in [
+            all_ascii.as_str(),
+            "quote\" back\\slash",
+            "ünïcode ✓",
+            "\u{7f}",
+        ] {
+            let serialized = serde_json::to_string(s).unwrap().len();
+            assert_eq!(serialized, s.len() + 2 + escape_inflation(s), "{s:?}");
+        }
+    }
+}
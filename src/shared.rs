@@ -0,0 +1,152 @@
+use crate::sizeof_val;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A reference-counted pointer to a `Value`, implemented for `Arc<Value>` and `Rc<Value>`.
+pub trait SharedValue {
+    /// Bytes the pointer's allocation holds on top of the `Value`: for `Arc` and `Rc`, the
+    /// strong and weak counts.
+    const ALLOCATION_OVERHEAD: usize;
+
+    fn value(&self) -> &Value;
+}
+
+impl SharedValue for Arc<Value> {
+    const ALLOCATION_OVERHEAD: usize = 2 * size_of::<usize>();
+
+    fn value(&self) -> &Value {
+        self
+    }
+}
+
+impl SharedValue for Rc<Value> {
+    const ALLOCATION_OVERHEAD: usize = 2 * size_of::<usize>();
+
+    fn value(&self) -> &Value {
+        self
+    }
+}
+
+/// Sizes of a set of shared values, created by [`sizeof_shared`] or [`SharedSizer`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SharedSize {
+    /// Number of pointers added.
+    pub handles: usize,
+    /// Number of distinct allocations they point to.
+    pub distinct: usize,
+    /// Bytes held by the distinct allocations, each counted once, including the reference
+    /// counts. This is what dropping all handles would free.
+    pub retained: usize,
+    /// Sum of [`crate::sizeof_val`] over all handles, counting shared values once per handle.
+    pub naive: usize,
+}
+
+impl SharedSize {
+    /// Bytes counted more than once by the naive sum.
+    pub fn shared(&self) -> usize {
+        self.naive.saturating_sub(self.retained)
+    }
+}
+
+/// Sizes the values behind `handles`, counting each distinct allocation once.
+///
+/// The pointers themselves are not counted, as they live in whatever container holds them.
+///
+/// ## Example
+/// ```
+/// use json_size::{sizeof_shared, sizeof_val};
+/// use serde_json::json;
+/// use std::sync::Arc;
+///
+/// let config = Arc::new(json!({"theme": "dark", "locale": "en-GB"}));
+/// let handles = vec![config.clone(), config.clone(), Arc::new(json!(null))];
+/// let size = sizeof_shared(&handles);
+/// assert_eq!(size.distinct, 2);
+/// assert_eq!(size.naive, 2 * sizeof_val(&config) + sizeof_val(&json!(null)));
+/// assert!(size.retained < size.naive);
+/// ```
+pub fn sizeof_shared<'a, P: SharedValue + 'a>(
+    handles: impl IntoIterator<Item = &'a P>,
+) -> SharedSize {
+    let mut sizer = SharedSizer::new();
+    handles.into_iter().for_each(|p| sizer.add(p));
+    sizer.size()
+}
+
+/// Accumulates [`SharedSize`]s across several containers, so values shared between them are
+/// counted once.
+///
+/// Allocations are told apart by address; the borrow of every added handle lasts as long as the
+/// sizer, so no allocation can be freed and its address reused in the meantime.
+#[derive(Debug, Default)]
+pub struct SharedSizer<'a> {
+    seen: HashSet<usize>,
+    size: SharedSize,
+    handles: PhantomData<&'a Value>,
+}
+
+impl<'a> SharedSizer<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<P: SharedValue>(&mut self, handle: &'a P) {
+        let value = handle.value();
+        let size = sizeof_val(value);
+        self.size.handles += 1;
+        self.size.naive = self.size.naive.saturating_add(size);
+        if self.seen.insert(value as *const Value as usize) {
+            self.size.distinct += 1;
+            self.size.retained = self
+                .size
+                .retained
+                .saturating_add(size + P::ALLOCATION_OVERHEAD);
+        }
+    }
+
+    pub fn size(&self) -> SharedSize {
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_counts_each_allocation_once() {
+        let shared = Arc::new(json!({"big": "x".repeat(1000)}));
+        let handles: Vec<Arc<Value>> = (0..10).map(|_| shared.clone()).collect();
+        let size = sizeof_shared(&handles);
+        assert_eq!(size.handles, 10);
+        assert_eq!(size.distinct, 1);
+        assert_eq!(size.retained, sizeof_val(&shared) + 16);
+        assert_eq!(size.naive, 10 * sizeof_val(&shared));
+        assert_eq!(size.shared(), size.naive - size.retained);
+    }
+
+    #[test]
+    fn test_equal_but_distinct_values() {
+        let handles = vec![Rc::new(json!("same")), Rc::new(json!("same"))];
+        let size = sizeof_shared(&handles);
+        assert_eq!(size.distinct, 2);
+        assert_eq!(size.retained, size.naive + 2 * 16);
+    }
+
+    #[test]
+    fn test_sizer_across_containers() {
+        let a = Arc::new(json!([1, 2, 3]));
+        let first = [a.clone()];
+        let second = [a.clone(), Arc::new(json!({}))];
+        let mut sizer = SharedSizer::new();
+        first.iter().chain(&second).for_each(|p| sizer.add(p));
+        assert_eq!(sizer.size().handles, 3);
+        assert_eq!(sizer.size().distinct, 2);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{sizeof_val, JsonPath, PathSize};
+use serde::Serialize;
+use serde_json::Value;
+use std::mem::size_of;
+
+/// Bytes allocated but unused in a document, created by [`slack_report`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlackReport {
+    /// Estimated size of the document as is.
+    pub estimated_size: usize,
+    /// Unused capacity of string buffers, values and keys.
+    pub string_slack: usize,
+    /// Unused capacity of array buffers.
+    pub array_slack: usize,
+    /// Every node with slack of its own, heaviest first. The slack of a key is reported at the
+    /// path of its member. The sizes add up to [`SlackReport::total`].
+    pub paths: Vec<PathSize>,
+}
+
+impl SlackReport {
+    /// Total bytes allocated but unused.
+    pub fn total(&self) -> usize {
+        self.string_slack + self.array_slack
+    }
+
+    /// Estimated size of the document once every buffer is shrunk to fit.
+    pub fn compacted_size(&self) -> usize {
+        self.estimated_size - self.total()
+    }
+}
+
+/// Sums the difference between capacity and length of every string and array buffer in `v`.
+///
+/// ## Example
+/// ```
+/// use json_size::slack_report;
+/// use serde_json::{json, Value};
+///
+/// let mut name = String::with_capacity(64);
+/// name.push_str("ada");
+/// let mut val = json!({"id": 7});
+/// val["name"] = Value::String(name);
+/// let report = slack_report(&val);
+/// assert_eq!(report.string_slack, 61);
+/// assert_eq!(report.paths[0].path.to_string(), "/name");
+/// ```
+pub fn slack_report(v: &Value) -> SlackReport {
+    let mut report = SlackReport {
+        estimated_size: sizeof_val(v),
+        string_slack: 0,
+        array_slack: 0,
+        paths: Vec::new(),
+    };
+    collect(v, &mut JsonPath::root(), 0, &mut report);
+    report.paths.sort();
+    report
+}
+
+/// Records the slack of `v` itself, plus `key_slack` for the key of its member.
+fn collect(v: &Value, path: &mut JsonPath, key_slack: usize, report: &mut SlackReport) {
+    let own = match v {
+        Value::String(s) => {
+            let slack = s.capacity() - s.len();
+            report.string_slack += slack;
+            slack
+        }
+        Value::Array(a) => {
+            let slack = (a.capacity() - a.len()) * size_of::<Value>();
+            report.array_slack += slack;
+            for (i, item) in a.iter().enumerate() {
+                path.push_index(i);
+                collect(item, path, 0, report);
+                path.pop();
+            }
+            slack
+        }
+        Value::Object(o) => {
+            for (k, item) in o {
+                let slack = k.capacity() - k.len();
+                report.string_slack += slack;
+                path.push_key(k.as_str());
+                collect(item, path, slack, report);
+                path.pop();
+            }
+            0
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => 0,
+    };
+    if own + key_slack > 0 {
+        report.paths.push(PathSize {
+            path: path.clone(),
+            size: own + key_slack,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Map};
+
+    fn with_capacity(s: &str, capacity: usize) -> String {
+        let mut buffer = String::with_capacity(capacity);
+        buffer.push_str(s);
+        buffer
+    }
+
+    #[test]
+    fn test_exact_document_has_no_slack() {
+        let val = json!({"a": [1, "two"], "b": {"c": null}});
+        let report = slack_report(&val);
+        assert_eq!(report.total(), 0);
+        assert!(report.paths.is_empty());
+        assert_eq!(report.compacted_size(), sizeof_val(&val));
+    }
+
+    #[test]
+    fn test_strings_arrays_and_keys() {
+        let mut items = Vec::with_capacity(4);
+        items.push(Value::String(with_capacity("x", 11)));
+        let mut map = Map::new();
+        map.insert(with_capacity("items", 8), Value::Array(items));
+        let val = Value::Object(map);
+
+        let report = slack_report(&val);
+        assert_eq!(report.string_slack, 10 + 3);
+        assert_eq!(report.array_slack, 3 * size_of::<Value>());
+        let paths: Vec<(String, usize)> = report
+            .paths
+            .iter()
+            .map(|p| (p.path.to_string(), p.size))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("/items".to_string(), 3 + 3 * size_of::<Value>()),
+                ("/items/0".to_string(), 10)
+            ]
+        );
+        assert_eq!(
+            report.compacted_size(),
+            sizeof_val(&json!({"items": ["x"]}))
+        );
+    }
+}
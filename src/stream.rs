@@ -0,0 +1,96 @@
+use crate::capped;
+use serde::{Deserialize, Deserializer};
+use serde_json::de::Read;
+use serde_json::{StreamDeserializer, Value};
+
+/// An iterator over a stream of concatenated or whitespace-separated JSON documents, yielding
+/// each one with its estimated size.
+///
+/// Sizes are tallied while each `Value` is built, so there is no second pass over the parsed
+/// document; they match what [`crate::sizeof_val`] returns for the yielded value. As with
+/// [`crate::capped`], arrays are shrunk to fit. Iteration ends after the first error.
+///
+/// ## Example
+/// ```
+/// use json_size::{sizeof_val, SizedStream};
+/// use serde_json::Deserializer;
+///
+/// let input = r#"{"id": 1, "tags": ["a"]} {"id": 2} [1, 2, 3]"#;
+/// let mut stream = SizedStream::new(Deserializer::from_str(input));
+/// let (first, size) = stream.next().unwrap().unwrap();
+/// assert_eq!(size, sizeof_val(&first));
+/// assert_eq!(stream.count(), 2);
+/// ```
+pub struct SizedStream<'de, R> {
+    inner: StreamDeserializer<'de, R, SizedDocument>,
+}
+
+impl<'de, R: Read<'de>> SizedStream<'de, R> {
+    pub fn new(deserializer: serde_json::Deserializer<R>) -> Self {
+        Self {
+            inner: deserializer.into_iter(),
+        }
+    }
+
+    /// Number of bytes of input consumed so far, see
+    /// [`serde_json::StreamDeserializer::byte_offset`].
+    pub fn byte_offset(&self) -> usize {
+        self.inner.byte_offset()
+    }
+}
+
+impl<'de, R: Read<'de>> Iterator for SizedStream<'de, R> {
+    type Item = serde_json::Result<(Value, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|doc| doc.map(|SizedDocument(v, size)| (v, size)))
+    }
+}
+
+/// The target type of the inner stream, which only accepts `Deserialize` types.
+struct SizedDocument(Value, usize);
+
+impl<'de> Deserialize<'de> for SizedDocument {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (v, size) = capped::deserialize_sized(deserializer, usize::MAX)?;
+        Ok(Self(v, size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sizeof_val;
+    use serde_json::json;
+
+    #[test]
+    fn test_sizes_match_estimate() {
+        let docs = [
+            json!({"a": [1, "two", null], "b": {"c": 1.5}}),
+            json!("text"),
+            json!([[], {}, [true]]),
+        ];
+        let input: String = docs.iter().map(|d| format!("{d}\n")).collect();
+        let sized: Vec<_> = SizedStream::new(serde_json::Deserializer::from_str(&input))
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(sized.len(), docs.len());
+        for ((v, size), doc) in sized.iter().zip(&docs) {
+            assert_eq!(v, doc);
+            assert_eq!(*size, sizeof_val(doc));
+        }
+    }
+
+    #[test]
+    fn test_reader_and_errors() {
+        let input = b"1 2 {bad} 3";
+        let mut stream = SizedStream::new(serde_json::Deserializer::from_reader(&input[..]));
+        assert_eq!(stream.next().unwrap().unwrap().0, json!(1));
+        assert_eq!(stream.next().unwrap().unwrap().0, json!(2));
+        assert_eq!(stream.byte_offset(), 3);
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+    }
+}
@@ -0,0 +1,188 @@
+//! `tracing` integration, enabled with the `tracing` feature.
+
+use crate::{JsonPath, SizeEstimator};
+
+/// Estimates the size of a value and emits a `tracing` event with it, returning the size.
+///
+/// Events use the `json_size` target and carry the `label` and `size` fields. They are emitted at
+/// `DEBUG` level, or at `WARN` level when a `warn_over` limit is given and exceeded.
+///
+/// ## Example
+/// ```
+/// use json_size::trace_size;
+/// use serde_json::json;
+///
+/// let body = json!({"items": [1, 2, 3]});
+/// let size = trace_size!(body, "request_body");
+/// trace_size!(body, "request_body", warn_over = 1024 * 1024);
+/// # assert!(size > 0);
+/// ```
+#[macro_export]
+macro_rules! trace_size {
+    ($value:expr, $label:expr) => {{
+        let size = $crate::sizeof_val(&$value);
+        $crate::__private::tracing::debug!(target: "json_size", label = $label, size, "json size");
+        size
+    }};
+    ($value:expr, $label:expr, warn_over = $limit:expr) => {{
+        let size = $crate::sizeof_val(&$value);
+        let limit: usize = $limit;
+        if size > limit {
+            $crate::__private::tracing::warn!(
+                target: "json_size",
+                label = $label,
+                size,
+                limit,
+                "json size over limit"
+            );
+        } else {
+            $crate::__private::tracing::debug!(target: "json_size", label = $label, size, "json size");
+        }
+        size
+    }};
+}
+
+/// Emits a `WARN` event if the estimated size of a value is over a number of bytes, without
+/// panicking. The non-panicking counterpart of [`crate::debug_assert_json_size!`].
+///
+/// Like `debug_assert!`, the check only runs when debug assertions are enabled. Events use the
+/// `json_size` target and carry the `label`, `size` and `limit` fields; the label defaults to
+/// the value expression.
+///
+/// ## Example
+/// ```
+/// use json_size::warn_json_size;
+/// use serde_json::json;
+///
+/// let body = json!({"items": [1, 2, 3]});
+/// warn_json_size!(body, 64 * 1024);
+/// warn_json_size!(body, 64 * 1024, "request_body");
+/// ```
+#[macro_export]
+macro_rules! warn_json_size {
+    ($value:expr, $max_bytes:expr $(,)?) => {
+        $crate::warn_json_size!($value, $max_bytes, ::core::stringify!($value))
+    };
+    ($value:expr, $max_bytes:expr, $label:expr $(,)?) => {
+        if ::core::cfg!(debug_assertions) {
+            let size = $crate::sizeof_val(&$value);
+            let limit: usize = $max_bytes;
+            if size > limit {
+                $crate::__private::tracing::warn!(
+                    target: "json_size",
+                    label = $label,
+                    size,
+                    limit,
+                    "json size over limit"
+                );
+            }
+        }
+    };
+}
+
+impl SizeEstimator {
+    /// Emits a `TRACE` event with the path and size of every node visited while profiling with
+    /// this estimator, e.g. by [`SizeEstimator::walk_sizes`] or [`crate::SizeReport::with_estimator`].
+    pub fn trace_nodes(mut self, enabled: bool) -> Self {
+        self.trace_nodes = enabled;
+        self
+    }
+
+    /// Emits a `WARN` event for every node visited while profiling whose subtree is larger than
+    /// `bytes`.
+    pub fn warn_over(mut self, bytes: usize) -> Self {
+        self.warn_over = Some(bytes);
+        self
+    }
+
+    pub(crate) fn trace_node(&self, path: &JsonPath, size: usize) {
+        if self.trace_nodes {
+            tracing::trace!(target: "json_size", path = %path, size, "json node size");
+        }
+        if let Some(limit) = self.warn_over.filter(|&limit| size > limit) {
+            tracing::warn!(target: "json_size", path = %path, size, limit, "json subtree over limit");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SizeReport;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    /// Counts events by level.
+    #[derive(Default)]
+    struct Counter {
+        warn: AtomicUsize,
+        other: AtomicUsize,
+    }
+
+    struct CountingSubscriber(Arc<Counter>);
+
+    impl Subscriber for CountingSubscriber {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            if *event.metadata().level() == Level::WARN {
+                self.0.warn.fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.0.other.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn count(f: impl FnOnce()) -> Arc<Counter> {
+        let counter = Arc::new(Counter::default());
+        tracing::subscriber::with_default(CountingSubscriber(counter.clone()), f);
+        counter
+    }
+
+    #[test]
+    fn test_trace_size_macro() {
+        let val = json!(["a", "b"]);
+        let counter = count(|| {
+            assert_eq!(trace_size!(val, "body"), crate::sizeof_val(&val));
+            trace_size!(&val, "body", warn_over = 1);
+        });
+        assert_eq!(counter.other.load(Ordering::Relaxed), 1);
+        assert_eq!(counter.warn.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_warn_json_size_macro() {
+        let val = json!(["a", "b"]);
+        let counter = count(|| {
+            warn_json_size!(val, crate::sizeof_val(&val));
+            warn_json_size!(val, 1);
+            warn_json_size!(&val, 1, "body");
+        });
+        assert_eq!(counter.other.load(Ordering::Relaxed), 0);
+        let expected = if cfg!(debug_assertions) { 2 } else { 0 };
+        assert_eq!(counter.warn.load(Ordering::Relaxed), expected);
+    }
+
+    #[test]
+    fn test_estimator_events() {
+        let val = json!({"big": "x".repeat(500), "small": 1});
+        let estimator = SizeEstimator::new().trace_nodes(true).warn_over(400);
+        let counter = count(|| {
+            SizeReport::with_estimator(&val, 1, &estimator);
+        });
+        // One trace event per node; the root and `/big` are over the limit.
+        assert_eq!(counter.other.load(Ordering::Relaxed), 3);
+        assert_eq!(counter.warn.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{entry_overhead, map_overhead, sizeof_val, SizeEstimator};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::mem::size_of;
+use std::ops::Deref;
+
+/// A `serde_json::Map` that keeps a running total of its estimated size.
+///
+/// [`SizeTrackedMap::total_size`] is what [`crate::sizeof_val`] returns for the map wrapped in a
+/// `Value::Object`, and costs O(1). Each mutation only sizes the entries it touches. The map is
+/// readable through `Deref`; to keep the total accurate it can only be modified through the
+/// methods below.
+///
+/// ## Example
+/// ```
+/// use json_size::{sizeof_val, SizeTrackedMap};
+/// use serde_json::json;
+///
+/// let mut response = SizeTrackedMap::new();
+/// for (field, value) in [("id", json!(7)), ("body", json!("x".repeat(5000)))] {
+///     if response.size_after_insert(field, &value) > 4096 {
+///         break;
+///     }
+///     response.insert(field.into(), value);
+/// }
+/// assert_eq!(response.len(), 1);
+/// assert_eq!(response.total_size(), sizeof_val(&response.into_value()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizeTrackedMap {
+    map: Map<String, Value>,
+    total: usize,
+}
+
+impl Default for SizeTrackedMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SizeTrackedMap {
+    pub fn new() -> Self {
+        Self {
+            map: Map::new(),
+            total: size_of::<Value>(),
+        }
+    }
+
+    /// Estimated size of the map as a `Value::Object`.
+    pub fn total_size(&self) -> usize {
+        self.total
+    }
+
+    /// What [`SizeTrackedMap::total_size`] would be after inserting `value` under `key`.
+    pub fn size_after_insert(&self, key: &str, value: &Value) -> usize {
+        let added = sizeof_val(value);
+        match self.map.get(key) {
+            Some(old) => self.total - sizeof_val(old) + added,
+            None => self.grown(
+                SizeEstimator::DEFAULT
+                    .entry_overhead_for_len(key.len())
+                    .saturating_add(added),
+            ),
+        }
+    }
+
+    /// The total after adding a member of `member` bytes, including the change in map
+    /// bookkeeping.
+    fn grown(&self, member: usize) -> usize {
+        let len = self.map.len();
+        self.total
+            .saturating_add(member)
+            .saturating_add(map_overhead(len + 1))
+            - map_overhead(len)
+    }
+
+    /// Inserts a member, returning the value it replaced. Replacing keeps the existing key, like
+    /// `Map::insert`.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        let added = sizeof_val(&value);
+        let grown = self.grown(entry_overhead(&key).saturating_add(added));
+        let old = self.map.insert(key, value);
+        match &old {
+            Some(old) => self.total = self.total - sizeof_val(old) + added,
+            None => self.total = grown,
+        }
+        old
+    }
+
+    /// Removes a member, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let (key, value) = self.map.remove_entry(key)?;
+        let len = self.map.len();
+        self.total = self.total + map_overhead(len)
+            - map_overhead(len + 1)
+            - entry_overhead(&key)
+            - sizeof_val(&value);
+        Some(value)
+    }
+
+    /// Moves every member of `other` into this map, replacing existing members with the same
+    /// key. `other` is left empty.
+    pub fn append(&mut self, other: &mut Map<String, Value>) {
+        for (key, value) in std::mem::take(other) {
+            self.insert(key, value);
+        }
+    }
+
+    /// Removes every member.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.total = size_of::<Value>();
+    }
+
+    pub fn into_inner(self) -> Map<String, Value> {
+        self.map
+    }
+
+    pub fn into_value(self) -> Value {
+        Value::Object(self.map)
+    }
+}
+
+impl Deref for SizeTrackedMap {
+    type Target = Map<String, Value>;
+
+    fn deref(&self) -> &Map<String, Value> {
+        &self.map
+    }
+}
+
+impl From<Map<String, Value>> for SizeTrackedMap {
+    fn from(map: Map<String, Value>) -> Self {
+        let value = Value::Object(map);
+        let total = sizeof_val(&value);
+        let Value::Object(map) = value else {
+            unreachable!()
+        };
+        Self { map, total }
+    }
+}
+
+impl From<SizeTrackedMap> for Value {
+    fn from(map: SizeTrackedMap) -> Self {
+        map.into_value()
+    }
+}
+
+impl Serialize for SizeTrackedMap {
+    /// Serializes exactly like the inner map.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.map.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(map: &mut SizeTrackedMap) {
+        // Cloning would shrink the key buffers, so size the map itself.
+        let value = Value::Object(std::mem::take(&mut map.map));
+        assert_eq!(map.total_size(), sizeof_val(&value));
+        let Value::Object(inner) = value else {
+            unreachable!()
+        };
+        map.map = inner;
+    }
+
+    #[test]
+    fn test_total_follows_mutations() {
+        let mut map = SizeTrackedMap::new();
+        check(&mut map);
+        assert_eq!(
+            map.size_after_insert("a", &json!("long value")),
+            sizeof_val(&json!({"a": "long value"}))
+        );
+        assert_eq!(map.insert("a".into(), json!("long value")), None);
+        map.insert("b".into(), json!([1, 2, 3]));
+        check(&mut map);
+        assert_eq!(map.insert("a".into(), json!(1)), Some(json!("long value")));
+        check(&mut map);
+        assert_eq!(map.remove("b"), Some(json!([1, 2, 3])));
+        assert_eq!(map.remove("missing"), None);
+        check(&mut map);
+        map.clear();
+        check(&mut map);
+    }
+
+    #[test]
+    fn test_total_across_node_splits() {
+        let mut map = SizeTrackedMap::new();
+        for i in 0..50 {
+            map.insert(format!("key-{i:02}"), json!(i));
+            check(&mut map);
+        }
+        for i in (0..50).step_by(3) {
+            map.remove(&format!("key-{i:02}"));
+            check(&mut map);
+        }
+    }
+
+    #[test]
+    fn test_append_and_from_map() {
+        let mut map = SizeTrackedMap::from(json!({"a": 1, "b": 2}).as_object().unwrap().clone());
+        check(&mut map);
+        let mut other = json!({"b": "two", "c": null}).as_object().unwrap().clone();
+        map.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(map.len(), 3);
+        check(&mut map);
+        assert_eq!(
+            serde_json::to_value(&map).unwrap(),
+            json!({"a": 1, "b": "two", "c": null})
+        );
+    }
+}
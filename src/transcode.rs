@@ -0,0 +1,726 @@
+//! Size accounting while transcoding between serde formats, enabled with the `transcode` feature.
+
+use crate::{own_size, SizeEstimator};
+use serde::ser::{self, Serialize, Serializer};
+use serde::Deserializer;
+use serde_json::Value;
+use std::cell::Cell;
+use std::io::{self, Write};
+
+const ESTIMATOR: SizeEstimator = SizeEstimator::DEFAULT;
+
+/// Transcodes `deserializer` into `serializer` with `serde_transcode`, returning the result of the
+/// serializer and the estimated size of the data as a `serde_json::Value`.
+///
+/// Nothing is materialized: the size is tallied as the data streams through, by a
+/// [`SizingSerializer`] wrapped around `serializer`. Wrap the output in a [`CountingWriter`] to
+/// tally the output size in the same pass.
+///
+/// ## Example
+/// ```
+/// use json_size::{sizeof_val, transcode_sized, CountingWriter};
+/// use serde_json::json;
+///
+/// let val = json!({"id": 7, "tags": ["a", "b"], "nested": {"ok": true}});
+/// let input = val.to_string();
+/// let mut out = CountingWriter::new(Vec::new());
+/// let mut deserializer = serde_json::Deserializer::from_str(&input);
+/// let mut serializer = serde_json::Serializer::pretty(&mut out);
+/// let ((), value_size) = transcode_sized(&mut deserializer, &mut serializer).unwrap();
+/// assert_eq!(value_size, sizeof_val(&val));
+/// assert!(out.bytes_written() > input.len());
+/// ```
+pub fn transcode_sized<'de, D, S>(
+    deserializer: D,
+    serializer: S,
+) -> Result<(S::Ok, usize), S::Error>
+where
+    D: Deserializer<'de>,
+    S: Serializer,
+{
+    let tally = Cell::new(0);
+    let ok = serde_transcode::transcode(deserializer, SizingSerializer::new(serializer, &tally))?;
+    Ok((ok, tally.get()))
+}
+
+/// A `Serializer` forwarding everything to `inner` while adding the estimated size of the data, as
+/// a `serde_json::Value` would hold it, to a tally.
+///
+/// Data maps to a `Value` the way `serde_json::to_value` maps it: enum variants other than unit
+/// variants become single-member objects, byte strings become arrays of numbers and map keys are
+/// sized as their JSON text. Arrays are sized without spare capacity, as the tally cannot know
+/// how a builder would grow them.
+pub struct SizingSerializer<'a, S> {
+    inner: S,
+    tally: &'a Cell<usize>,
+}
+
+impl<'a, S> SizingSerializer<'a, S> {
+    pub fn new(inner: S, tally: &'a Cell<usize>) -> Self {
+        Self { inner, tally }
+    }
+}
+
+fn add(tally: &Cell<usize>, bytes: usize) {
+    tally.set(tally.get().saturating_add(bytes));
+}
+
+/// The cost of a `Value` node, excluding whatever it owns.
+fn node() -> usize {
+    own_size(&Value::Null)
+}
+
+fn string(len: usize) -> usize {
+    node() + ESTIMATOR.string_buffer_size(len)
+}
+
+/// The object wrapping the content of a non-unit enum variant.
+fn variant(name: &str) -> usize {
+    node() + ESTIMATOR.map_overhead(1) + ESTIMATOR.entry_overhead_for_len(name.len())
+}
+
+/// A value serialized through a [`SizingSerializer`] sharing `tally`.
+struct Tallied<'a, T: ?Sized> {
+    value: &'a T,
+    tally: &'a Cell<usize>,
+}
+
+impl<T: Serialize + ?Sized> Serialize for Tallied<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value
+            .serialize(SizingSerializer::new(serializer, self.tally))
+    }
+}
+
+macro_rules! forward_scalar {
+    ($($method:ident: $ty:ty)*) => {$(
+        fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+            add(self.tally, node());
+            self.inner.$method(v)
+        }
+    )*};
+}
+
+impl<'a, S: Serializer> Serializer for SizingSerializer<'a, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<'a, S::SerializeSeq>;
+    type SerializeTuple = Compound<'a, S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<'a, S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<'a, S::SerializeTupleVariant>;
+    type SerializeMap = Compound<'a, S::SerializeMap>;
+    type SerializeStruct = Compound<'a, S::SerializeStruct>;
+    type SerializeStructVariant = Compound<'a, S::SerializeStructVariant>;
+
+    forward_scalar! {
+        serialize_bool: bool serialize_i8: i8 serialize_i16: i16 serialize_i32: i32
+        serialize_i64: i64 serialize_i128: i128 serialize_u8: u8 serialize_u16: u16
+        serialize_u32: u32 serialize_u64: u64 serialize_u128: u128 serialize_f32: f32
+        serialize_f64: f64
+    }
+
+    fn serialize_char(self, v: char) -> Result<S::Ok, S::Error> {
+        add(self.tally, string(v.len_utf8()));
+        self.inner.serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<S::Ok, S::Error> {
+        add(self.tally, string(v.len()));
+        self.inner.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<S::Ok, S::Error> {
+        let elements = v.len().saturating_mul(node());
+        add(
+            self.tally,
+            node() + elements + ESTIMATOR.array_overhead(v.len(), v.len()),
+        );
+        self.inner.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        add(self.tally, node());
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_some(&Tallied {
+            value,
+            tally: self.tally,
+        })
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        add(self.tally, node());
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        add(self.tally, node());
+        self.inner.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        add(self.tally, string(variant.len()));
+        self.inner
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_newtype_struct(
+            name,
+            &Tallied {
+                value,
+                tally: self.tally,
+            },
+        )
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        add(self.tally, self::variant(variant));
+        self.inner.serialize_newtype_variant(
+            name,
+            variant_index,
+            variant,
+            &Tallied {
+                value,
+                tally: self.tally,
+            },
+        )
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        let inner = self.inner.serialize_seq(len)?;
+        Ok(Compound::new(inner, self.tally, Kind::Array))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        let inner = self.inner.serialize_tuple(len)?;
+        Ok(Compound::new(inner, self.tally, Kind::Array))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        let inner = self.inner.serialize_tuple_struct(name, len)?;
+        Ok(Compound::new(inner, self.tally, Kind::Array))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        add(self.tally, self::variant(variant));
+        let inner = self
+            .inner
+            .serialize_tuple_variant(name, variant_index, variant, len)?;
+        Ok(Compound::new(inner, self.tally, Kind::Array))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        let inner = self.inner.serialize_map(len)?;
+        Ok(Compound::new(inner, self.tally, Kind::Object))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        let inner = self.inner.serialize_struct(name, len)?;
+        Ok(Compound::new(inner, self.tally, Kind::Object))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        add(self.tally, self::variant(variant));
+        let inner = self
+            .inner
+            .serialize_struct_variant(name, variant_index, variant, len)?;
+        Ok(Compound::new(inner, self.tally, Kind::Object))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Array,
+    Object,
+}
+
+/// The sequences, maps and structs of a [`SizingSerializer`], charging their own node once the
+/// number of elements or members is known.
+pub struct Compound<'a, C> {
+    inner: C,
+    tally: &'a Cell<usize>,
+    kind: Kind,
+    len: usize,
+}
+
+impl<'a, C> Compound<'a, C> {
+    fn new(inner: C, tally: &'a Cell<usize>, kind: Kind) -> Self {
+        Self {
+            inner,
+            tally,
+            kind,
+            len: 0,
+        }
+    }
+
+    fn sized<'v, T: ?Sized>(&self, value: &'v T) -> Tallied<'v, T>
+    where
+        'a: 'v,
+    {
+        Tallied {
+            value,
+            tally: self.tally,
+        }
+    }
+
+    fn finish(&self) {
+        let overhead = match self.kind {
+            Kind::Array => ESTIMATOR.array_overhead(self.len, self.len),
+            Kind::Object => ESTIMATOR.map_overhead(self.len),
+        };
+        add(self.tally, node() + overhead);
+    }
+
+    fn field(&mut self, key_len: usize) {
+        self.len += 1;
+        add(self.tally, ESTIMATOR.entry_overhead_for_len(key_len));
+    }
+}
+
+impl<C: ser::SerializeSeq> ser::SerializeSeq for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.len += 1;
+        let value = self.sized(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.finish();
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeTuple> ser::SerializeTuple for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.len += 1;
+        let value = self.sized(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.finish();
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeTupleStruct> ser::SerializeTupleStruct for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.len += 1;
+        let value = self.sized(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.finish();
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeTupleVariant> ser::SerializeTupleVariant for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.len += 1;
+        let value = self.sized(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.finish();
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeMap> ser::SerializeMap for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+        // The key may be serialized only once, so its length is recorded on the way through.
+        let len = Cell::new(0);
+        self.inner.serialize_key(&KeyTallied { key, len: &len })?;
+        self.field(len.get());
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.sized(value);
+        self.inner.serialize_value(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.finish();
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeStruct> ser::SerializeStruct for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.field(key.len());
+        let value = self.sized(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.finish();
+        self.inner.end()
+    }
+}
+
+impl<C: ser::SerializeStructVariant> ser::SerializeStructVariant for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.field(key.len());
+        let value = self.sized(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.finish();
+        self.inner.end()
+    }
+}
+
+/// A map key serialized through a [`KeySerializer`] recording its length in `len`.
+struct KeyTallied<'a, T: ?Sized> {
+    key: &'a T,
+    len: &'a Cell<usize>,
+}
+
+impl<T: Serialize + ?Sized> Serialize for KeyTallied<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.key.serialize(KeySerializer {
+            inner: serializer,
+            len: self.len,
+        })
+    }
+}
+
+/// A `Serializer` forwarding a map key to `inner` while recording the length of the string
+/// `serde_json` would turn it into. Keys `serde_json` rejects are forwarded as is.
+struct KeySerializer<'a, S> {
+    inner: S,
+    len: &'a Cell<usize>,
+}
+
+macro_rules! forward_key {
+    ($($method:ident: $ty:ty)*) => {$(
+        fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+            self.len.set(v.to_string().len());
+            self.inner.$method(v)
+        }
+    )*};
+}
+
+impl<S: Serializer> Serializer for KeySerializer<'_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = S::SerializeSeq;
+    type SerializeTuple = S::SerializeTuple;
+    type SerializeTupleStruct = S::SerializeTupleStruct;
+    type SerializeTupleVariant = S::SerializeTupleVariant;
+    type SerializeMap = S::SerializeMap;
+    type SerializeStruct = S::SerializeStruct;
+    type SerializeStructVariant = S::SerializeStructVariant;
+
+    forward_key! {
+        serialize_bool: bool serialize_i8: i8 serialize_i16: i16 serialize_i32: i32
+        serialize_i64: i64 serialize_i128: i128 serialize_u8: u8 serialize_u16: u16
+        serialize_u32: u32 serialize_u64: u64 serialize_u128: u128 serialize_f32: f32
+        serialize_f64: f64 serialize_char: char
+    }
+
+    fn serialize_str(self, v: &str) -> Result<S::Ok, S::Error> {
+        self.len.set(v.len());
+        self.inner.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_some(value)
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.len.set(variant.len());
+        self.inner
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_newtype_struct(
+            name,
+            &KeyTallied {
+                key: value,
+                len: self.len,
+            },
+        )
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner
+            .serialize_newtype_variant(name, variant_index, variant, value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<S::SerializeSeq, S::Error> {
+        self.inner.serialize_seq(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<S::SerializeTuple, S::Error> {
+        self.inner.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<S::SerializeTupleStruct, S::Error> {
+        self.inner.serialize_tuple_struct(name, len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<S::SerializeTupleVariant, S::Error> {
+        self.inner
+            .serialize_tuple_variant(name, variant_index, variant, len)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<S::SerializeMap, S::Error> {
+        self.inner.serialize_map(len)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<S::SerializeStruct, S::Error> {
+        self.inner.serialize_struct(name, len)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<S::SerializeStructVariant, S::Error> {
+        self.inner
+            .serialize_struct_variant(name, variant_index, variant, len)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+/// A writer counting the bytes written through it, to tally the output size of a transcode.
+#[derive(Debug, Default)]
+pub struct CountingWriter<W> {
+    inner: W,
+    count: usize,
+}
+
+impl<W> CountingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    pub fn bytes_written(&self) -> usize {
+        self.count
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sizeof_val;
+    use serde_json::json;
+
+    fn tally<T: Serialize>(value: &T) -> usize {
+        let tally = Cell::new(0);
+        value
+            .serialize(SizingSerializer::new(serde_json::value::Serializer, &tally))
+            .unwrap();
+        tally.get()
+    }
+
+    #[test]
+    fn test_transcode_matches_parsed_size() {
+        let val = json!({"a": [1, -2, 3.5, null, true], "b": {"c": "text", "d": []}, "e": {}});
+        let input = val.to_string();
+        let mut out = CountingWriter::new(Vec::new());
+        let (_, size) = transcode_sized(
+            &mut serde_json::Deserializer::from_str(&input),
+            &mut serde_json::Serializer::new(&mut out),
+        )
+        .unwrap();
+        assert_eq!(size, sizeof_val(&val));
+        assert_eq!(out.bytes_written(), input.len());
+        assert_eq!(out.into_inner(), input.into_bytes());
+    }
+
+    #[test]
+    fn test_rust_types_match_to_value() {
+        #[derive(serde::Serialize)]
+        enum Shape {
+            Point,
+            Circle(f64),
+            Line(u8, u8),
+            Rect { w: u32, h: u32 },
+        }
+        #[derive(serde::Serialize)]
+        struct Drawing {
+            name: String,
+            shapes: Vec<Shape>,
+            layers: std::collections::BTreeMap<u32, Option<&'static str>>,
+            raw: serde_bytes_like::Bytes,
+        }
+        mod serde_bytes_like {
+            pub struct Bytes(pub Vec<u8>);
+            impl serde::Serialize for Bytes {
+                fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                    s.serialize_bytes(&self.0)
+                }
+            }
+        }
+        let drawing = Drawing {
+            name: "sketch".into(),
+            shapes: vec![
+                Shape::Point,
+                Shape::Circle(1.5),
+                Shape::Line(1, 2),
+                Shape::Rect { w: 3, h: 4 },
+            ],
+            layers: [(1, Some("x")), (20, None)].into_iter().collect(),
+            raw: serde_bytes_like::Bytes(vec![1, 2, 3]),
+        };
+        let value = serde_json::to_value(&drawing).unwrap();
+        assert_eq!(tally(&drawing), sizeof_val(&value));
+    }
+}
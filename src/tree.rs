@@ -0,0 +1,287 @@
+use crate::{entry_overhead, own_size, JsonPath, PathSegment};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::{self, Write};
+
+/// The JSON type of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueKind {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl ValueKind {
+    pub fn of(v: &Value) -> Self {
+        match v {
+            Value::Null => Self::Null,
+            Value::Bool(_) => Self::Bool,
+            Value::Number(_) => Self::Number,
+            Value::String(_) => Self::String,
+            Value::Array(_) => Self::Array,
+            Value::Object(_) => Self::Object,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool => "bool",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Array => "array",
+            Self::Object => "object",
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A tree mirroring a `serde_json::Value`, with the estimated size of every node.
+///
+/// `own_size` is what the node costs by itself: the `Value`, for strings the string buffer, for
+/// arrays the spare capacity of their buffer, and for objects the map's bookkeeping. For object
+/// members it also includes the key and any per-entry map overhead. `cumulative_size` adds
+/// the sizes of all descendants, so the root's `cumulative_size` equals [`crate::sizeof_val`].
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::{sizeof_val, SizeTree};
+///
+/// let val = json!({"users": [{"name": "ada"}]});
+/// let tree = SizeTree::new(&val);
+/// assert_eq!(tree.cumulative_size, sizeof_val(&val));
+/// println!("{}", tree.to_csv());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeTree {
+    pub path: JsonPath,
+    #[serde(rename = "type")]
+    pub kind: ValueKind,
+    pub own_size: usize,
+    pub cumulative_size: usize,
+    pub children: Vec<SizeTree>,
+}
+
+impl SizeTree {
+    /// Builds the size tree of `v`.
+    pub fn new(v: &Value) -> Self {
+        build(v, JsonPath::root(), 0)
+    }
+
+    /// Iterates over all nodes in depth-first pre-order, starting with `self`.
+    pub fn iter(&self) -> SizeTreeIter<'_> {
+        SizeTreeIter { stack: vec![self] }
+    }
+
+    /// Exports every node as a flat JSON array of
+    /// `{"path", "own_size", "cumulative_size", "type"}` records, in depth-first order.
+    pub fn to_json(&self) -> String {
+        let records: Vec<Record<'_>> = self.iter().map(Record::from).collect();
+        serde_json::to_string(&records).expect("size records always serialize")
+    }
+
+    /// Exports every node as CSV with a `path,own_size,cumulative_size,type` header, in
+    /// depth-first order.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("path,own_size,cumulative_size,type\n");
+        for node in self.iter() {
+            let path = node.path.to_string();
+            let _ = writeln!(
+                out,
+                "{},{},{},{}",
+                csv_field(&path),
+                node.own_size,
+                node.cumulative_size,
+                node.kind
+            );
+        }
+        out
+    }
+
+    /// Exports every node as a folded stack line, `root;users;0;avatar 52340`, for flamegraph
+    /// tools such as `inferno-flamegraph`.
+    ///
+    /// Each line carries the node's `own_size`, so the width of a frame in the flamegraph is its
+    /// `cumulative_size`. Semicolons and line breaks in keys are replaced with `_`, as they would
+    /// split the frame or the line.
+    ///
+    /// ## Example
+    /// ```
+    /// use serde_json::json;
+    /// use json_size::SizeTree;
+    ///
+    /// let tree = SizeTree::new(&json!({"users": [{"avatar": "iVBORw0KGgo"}]}));
+    /// let folded = tree.to_folded();
+    /// assert!(folded.lines().any(|line| line.starts_with("root;users;0;avatar ")));
+    /// ```
+    pub fn to_folded(&self) -> String {
+        let mut out = String::new();
+        for node in self.iter() {
+            out.push_str("root");
+            for segment in node.path.segments() {
+                out.push(';');
+                match segment {
+                    PathSegment::Key(k) => out.extend(k.chars().map(|c| {
+                        if matches!(c, ';' | '\n' | '\r') {
+                            '_'
+                        } else {
+                            c
+                        }
+                    })),
+                    PathSegment::Index(i) => {
+                        let _ = write!(out, "{i}");
+                    }
+                }
+            }
+            let _ = writeln!(out, " {}", node.own_size);
+        }
+        out
+    }
+}
+
+fn build(v: &Value, path: JsonPath, extra: usize) -> SizeTree {
+    let children: Vec<SizeTree> = match v {
+        Value::Array(a) => a
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let mut child = path.clone();
+                child.push_index(i);
+                build(item, child, 0)
+            })
+            .collect(),
+        Value::Object(o) => o
+            .iter()
+            .map(|(k, item)| {
+                let mut child = path.clone();
+                child.push_key(k.as_str());
+                build(item, child, entry_overhead(k))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let own_size = own_size(v) + extra;
+    SizeTree {
+        path,
+        kind: ValueKind::of(v),
+        own_size,
+        cumulative_size: children
+            .iter()
+            .fold(own_size, |acc, c| acc.saturating_add(c.cumulative_size)),
+        children,
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    path: &'a JsonPath,
+    own_size: usize,
+    cumulative_size: usize,
+    #[serde(rename = "type")]
+    kind: ValueKind,
+}
+
+impl<'a> From<&'a SizeTree> for Record<'a> {
+    fn from(node: &'a SizeTree) -> Self {
+        Self {
+            path: &node.path,
+            own_size: node.own_size,
+            cumulative_size: node.cumulative_size,
+            kind: node.kind,
+        }
+    }
+}
+
+/// Depth-first iterator over the nodes of a [`SizeTree`], created by [`SizeTree::iter`].
+pub struct SizeTreeIter<'a> {
+    stack: Vec<&'a SizeTree>,
+}
+
+impl<'a> Iterator for SizeTreeIter<'a> {
+    type Item = &'a SizeTree;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children.iter().rev());
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sizeof_val;
+    use serde_json::json;
+
+    #[test]
+    fn test_tree_sizes_add_up() {
+        let val = json!({"a": [1, "two"], "b": {"c": null}});
+        let tree = SizeTree::new(&val);
+        assert_eq!(tree.cumulative_size, sizeof_val(&val));
+        for node in tree.iter() {
+            let children: usize = node.children.iter().map(|c| c.cumulative_size).sum();
+            assert_eq!(node.cumulative_size, node.own_size + children);
+        }
+    }
+
+    #[test]
+    fn test_iter_is_preorder() {
+        let tree = SizeTree::new(&json!({"a": [1, 2], "b": 3}));
+        let paths: Vec<String> = tree.iter().map(|n| n.path.to_string()).collect();
+        assert_eq!(paths, ["", "/a", "/a/0", "/a/1", "/b"]);
+    }
+
+    #[test]
+    fn test_to_json_records() {
+        let tree = SizeTree::new(&json!({"k": "v"}));
+        let records: Value = serde_json::from_str(&tree.to_json()).unwrap();
+        assert_eq!(records[1]["path"], "/k");
+        assert_eq!(records[1]["type"], "string");
+        assert_eq!(records[0]["cumulative_size"], tree.cumulative_size);
+    }
+
+    #[test]
+    fn test_to_csv_quotes_paths() {
+        let tree = SizeTree::new(&json!({"a,\"b\"": 1}));
+        let csv = tree.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("path,own_size,cumulative_size,type"));
+        assert!(lines.next().unwrap().ends_with(",object"));
+        assert!(lines.next().unwrap().starts_with("\"/a,\"\"b\"\"\","));
+    }
+
+    #[test]
+    fn test_to_folded_stacks() {
+        let val = json!({"a;b": [1], "c": "d"});
+        let tree = SizeTree::new(&val);
+        let folded = tree.to_folded();
+        let lines: Vec<&str> = folded.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], format!("root {}", tree.own_size));
+        assert!(lines[1].starts_with("root;a_b "));
+        assert!(lines[2].starts_with("root;a_b;0 "));
+        let total: usize = lines
+            .iter()
+            .map(|line| line.rsplit_once(' ').unwrap().1.parse::<usize>().unwrap())
+            .sum();
+        assert_eq!(total, sizeof_val(&val));
+    }
+}
@@ -0,0 +1,527 @@
+use crate::sizeof_val;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, Visitor};
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::Cell;
+use std::fmt;
+use std::mem::size_of;
+
+/// Bookkeeping charged per map entry of the typed value: the map type is not known, so this is a
+/// flat three words.
+const MAP_ENTRY_OVERHEAD: usize = 3 * size_of::<usize>();
+
+/// The size of a document as a `Value` next to the size of the same data deserialized into a
+/// strongly-typed `T`, created by [`compare_with_typed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TypedComparison {
+    pub value_size: usize,
+    /// `size_of::<T>()` plus the heap buffers allocated while deserializing `T`.
+    pub typed_size: usize,
+}
+
+impl TypedComparison {
+    /// How many times larger the `Value` is than the typed representation.
+    pub fn bloat_factor(&self) -> f64 {
+        self.value_size as f64 / self.typed_size.max(1) as f64
+    }
+
+    /// Bytes saved by deserializing into `T` instead of keeping the `Value`, zero if the typed
+    /// representation is larger.
+    pub fn saved(&self) -> usize {
+        self.value_size.saturating_sub(self.typed_size)
+    }
+}
+
+/// Deserializes `v` into `T` and compares the estimated footprint of both representations.
+///
+/// The typed size is measured while deserializing: sequence elements and map entries count
+/// `size_of` their Rust type, map entries add three words of bookkeeping, and owned strings and
+/// byte buffers count their length. Heap indirections that deserialize transparently, like `Box`
+/// or `Arc`, are not visible and are not counted. Fails if `v` does not deserialize into `T`.
+///
+/// ## Example
+/// ```
+/// use json_size::compare_with_typed;
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     id: u64,
+///     name: String,
+///     admin: bool,
+/// }
+///
+/// let v = json!({"id": 7, "name": "ada", "admin": false});
+/// let cmp = compare_with_typed::<User>(&v).unwrap();
+/// assert_eq!(cmp.typed_size, std::mem::size_of::<User>() + 3);
+/// assert!(cmp.bloat_factor() > 1.0);
+/// ```
+pub fn compare_with_typed<T: DeserializeOwned>(
+    v: &Value,
+) -> Result<TypedComparison, serde_json::Error> {
+    let heap = Cell::new(0);
+    let _typed: T = T::deserialize(Sizing {
+        inner: v,
+        heap: &heap,
+    })?;
+    Ok(TypedComparison {
+        value_size: sizeof_val(v),
+        typed_size: size_of::<T>().saturating_add(heap.get()),
+    })
+}
+
+fn add(heap: &Cell<usize>, bytes: usize) {
+    heap.set(heap.get().saturating_add(bytes));
+}
+
+/// Wraps a deserializer to count the heap buffers of the value being built.
+struct Sizing<'a, D> {
+    inner: D,
+    heap: &'a Cell<usize>,
+}
+
+impl<'a, D> Sizing<'a, D> {
+    /// Wraps `visitor`. `on_heap` says whether sequence elements and map entries get their own
+    /// allocation, `owned` whether strings and bytes are copied into one.
+    fn visitor<V>(&self, visitor: V, on_heap: bool, owned: bool) -> SizingVisitor<'a, V> {
+        SizingVisitor {
+            inner: visitor,
+            heap: self.heap,
+            on_heap,
+            owned,
+        }
+    }
+}
+
+macro_rules! forward_plain {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+            let visitor = self.visitor(visitor, false, false);
+            self.inner.$method(visitor)
+        }
+    )*};
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for Sizing<'_, D> {
+    type Error = D::Error;
+
+    forward_plain! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_bytes deserialize_option deserialize_unit deserialize_identifier
+        deserialize_ignored_any
+    }
+
+    /// Self-describing targets such as `Value` own everything they hold.
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, true, true);
+        self.inner.deserialize_any(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, false, true);
+        self.inner.deserialize_string(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, false, true);
+        self.inner.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, true, false);
+        self.inner.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, true, false);
+        self.inner.deserialize_map(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, false, false);
+        self.inner.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, false, false);
+        self.inner.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, false, false);
+        self.inner.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, false, false);
+        self.inner.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, false, false);
+        self.inner.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = self.visitor(visitor, false, false);
+        self.inner.deserialize_enum(name, variants, visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+/// Passes a [`Sizing`] deserializer to the wrapped seed.
+struct SizingSeed<'a, S> {
+    inner: S,
+    heap: &'a Cell<usize>,
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for SizingSeed<'_, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        self.inner.deserialize(Sizing {
+            inner: deserializer,
+            heap: self.heap,
+        })
+    }
+}
+
+struct SizingVisitor<'a, V> {
+    inner: V,
+    heap: &'a Cell<usize>,
+    on_heap: bool,
+    owned: bool,
+}
+
+impl<V> SizingVisitor<'_, V> {
+    fn owned_bytes(&self, len: usize) {
+        if self.owned {
+            add(self.heap, len);
+        }
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method:ident: $ty:ty)*) => {$(
+        fn $method<E: de::Error>(self, v: $ty) -> Result<V::Value, E> {
+            self.inner.$method(v)
+        }
+    )*};
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for SizingVisitor<'_, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    forward_visit! {
+        visit_bool: bool visit_i8: i8 visit_i16: i16 visit_i32: i32 visit_i64: i64
+        visit_i128: i128 visit_u8: u8 visit_u16: u16 visit_u32: u32 visit_u64: u64
+        visit_u128: u128 visit_f32: f32 visit_f64: f64 visit_char: char
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<V::Value, E> {
+        self.owned_bytes(v.len());
+        self.inner.visit_str(v)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<V::Value, E> {
+        self.owned_bytes(v.len());
+        self.inner.visit_borrowed_str(v)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<V::Value, E> {
+        self.owned_bytes(v.len());
+        self.inner.visit_string(v)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<V::Value, E> {
+        self.owned_bytes(v.len());
+        self.inner.visit_bytes(v)
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<V::Value, E> {
+        self.owned_bytes(v.len());
+        self.inner.visit_borrowed_bytes(v)
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<V::Value, E> {
+        self.owned_bytes(v.len());
+        self.inner.visit_byte_buf(v)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        self.inner.visit_some(Sizing {
+            inner: deserializer,
+            heap: self.heap,
+        })
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<V::Value, D::Error> {
+        self.inner.visit_newtype_struct(Sizing {
+            inner: deserializer,
+            heap: self.heap,
+        })
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        self.inner.visit_seq(SizingAccess {
+            inner: seq,
+            heap: self.heap,
+            on_heap: self.on_heap,
+        })
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        self.inner.visit_map(SizingAccess {
+            inner: map,
+            heap: self.heap,
+            on_heap: self.on_heap,
+        })
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.inner.visit_enum(SizingAccess {
+            inner: data,
+            heap: self.heap,
+            on_heap: false,
+        })
+    }
+}
+
+/// Wraps the sequence, map, enum and variant accessors handed to a visitor.
+struct SizingAccess<'a, A> {
+    inner: A,
+    heap: &'a Cell<usize>,
+    on_heap: bool,
+}
+
+impl<'a, A> SizingAccess<'a, A> {
+    fn seed<S>(&self, seed: S) -> SizingSeed<'a, S> {
+        SizingSeed {
+            inner: seed,
+            heap: self.heap,
+        }
+    }
+
+    fn element<T>(&self, overhead: usize) {
+        if self.on_heap {
+            add(self.heap, size_of::<T>() + overhead);
+        }
+    }
+}
+
+impl<'de, A: de::SeqAccess<'de>> de::SeqAccess<'de> for SizingAccess<'_, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        let element = self.inner.next_element_seed(self.seed(seed))?;
+        if element.is_some() {
+            self.element::<S::Value>(0);
+        }
+        Ok(element)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, A: de::MapAccess<'de>> de::MapAccess<'de> for SizingAccess<'_, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        let key = self.inner.next_key_seed(self.seed(seed))?;
+        if key.is_some() {
+            self.element::<K::Value>(MAP_ENTRY_OVERHEAD);
+        }
+        Ok(key)
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, A::Error> {
+        self.element::<S::Value>(0);
+        self.inner.next_value_seed(self.seed(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, 'de, A: de::EnumAccess<'de>> de::EnumAccess<'de> for SizingAccess<'a, A> {
+    type Error = A::Error;
+    type Variant = SizingAccess<'a, A::Variant>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), A::Error> {
+        let (value, variant) = self.inner.variant_seed(seed)?;
+        Ok((
+            value,
+            SizingAccess {
+                inner: variant,
+                heap: self.heap,
+                on_heap: false,
+            },
+        ))
+    }
+}
+
+impl<'de, A: de::VariantAccess<'de>> de::VariantAccess<'de> for SizingAccess<'_, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        let visitor = SizingVisitor {
+            inner: visitor,
+            heap: self.heap,
+            on_heap: false,
+            owned: false,
+        };
+        self.inner.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        let visitor = SizingVisitor {
+            inner: visitor,
+            heap: self.heap,
+            on_heap: false,
+            owned: false,
+        };
+        self.inner.struct_variant(fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    struct Event {
+        id: u32,
+        kind: Kind,
+        tags: Vec<String>,
+        note: Option<String>,
+        point: (u8, u8),
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    enum Kind {
+        Click,
+        Scroll { dy: i32 },
+    }
+
+    fn typed_size<T: DeserializeOwned>(v: Value) -> usize {
+        compare_with_typed::<T>(&v).unwrap().typed_size
+    }
+
+    #[test]
+    fn test_struct_fields_are_inline() {
+        let v = json!({
+            "id": 1,
+            "kind": {"Scroll": {"dy": -3}},
+            "tags": ["a", "bcd"],
+            "note": "hello",
+            "point": [1, 2],
+        });
+        let expected = size_of::<Event>() + 2 * size_of::<String>() + 4 + 5;
+        assert_eq!(typed_size::<Event>(v), expected);
+    }
+
+    #[test]
+    fn test_map_entries() {
+        let v = json!({"ab": 1, "c": 2});
+        let entry = size_of::<String>() + size_of::<u64>() + MAP_ENTRY_OVERHEAD;
+        let expected = size_of::<BTreeMap<String, u64>>() + 2 * entry + 3;
+        assert_eq!(typed_size::<BTreeMap<String, u64>>(v.clone()), expected);
+        let expected = size_of::<HashMap<String, u64>>() + 2 * entry + 3;
+        assert_eq!(typed_size::<HashMap<String, u64>>(v), expected);
+    }
+
+    #[test]
+    fn test_value_as_target_matches_sizeof_val_shape() {
+        let v = json!(["abc", 1]);
+        let expected = size_of::<Value>() + 2 * size_of::<Value>() + 3;
+        assert_eq!(typed_size::<Value>(v), expected);
+    }
+
+    #[test]
+    fn test_compare_with_typed() {
+        let v = json!({"id": 3, "kind": "Click", "tags": ["x"], "note": null, "point": [0, 0]});
+        let cmp = compare_with_typed::<Event>(&v).unwrap();
+        assert_eq!(cmp.value_size, sizeof_val(&v));
+        assert!(cmp.typed_size < cmp.value_size);
+        assert_eq!(cmp.saved(), cmp.value_size - cmp.typed_size);
+        assert!(compare_with_typed::<Event>(&json!([1])).is_err());
+    }
+}
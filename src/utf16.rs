@@ -0,0 +1,152 @@
+use serde_json::Value;
+use std::io;
+
+/// Per-node costs of a JavaScript engine heap, used by [`JsHeapModel::estimate`].
+///
+/// The defaults approximate a 64-bit engine without pointer compression: 8-byte slots, 16-byte
+/// object, array and string headers, and boxed numbers for values that are not small integers.
+/// Keys are assumed to be interned and shared through hidden classes, so each property only
+/// costs its slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JsHeapModel {
+    /// Bytes per property or array element slot.
+    pub slot: usize,
+    /// Fixed bytes per object.
+    pub object_header: usize,
+    /// Fixed bytes per array, including its backing store header.
+    pub array_header: usize,
+    /// Fixed bytes per string.
+    pub string_header: usize,
+    /// Bytes per UTF-16 code unit of string content.
+    pub bytes_per_code_unit: usize,
+    /// Bytes per number that does not fit a 31-bit small integer.
+    pub heap_number: usize,
+}
+
+impl Default for JsHeapModel {
+    fn default() -> Self {
+        Self {
+            slot: 8,
+            object_header: 16,
+            array_header: 16,
+            string_header: 16,
+            bytes_per_code_unit: 2,
+            heap_number: 16,
+        }
+    }
+}
+
+impl JsHeapModel {
+    /// Estimates the JavaScript heap footprint of `v` once parsed by a JS engine.
+    pub fn estimate(&self, v: &Value) -> usize {
+        match v {
+            Value::Null | Value::Bool(_) => 0,
+            Value::Number(n) => match n.as_i64() {
+                Some(i) if (-(1 << 30)..(1 << 30)).contains(&i) => 0,
+                _ => self.heap_number,
+            },
+            Value::String(s) => self.string_header + self.bytes_per_code_unit * utf16_len(s),
+            Value::Array(a) => a.iter().fold(self.array_header, |acc, item| {
+                acc.saturating_add(self.slot + self.estimate(item))
+            }),
+            Value::Object(o) => o.values().fold(self.object_header, |acc, item| {
+                acc.saturating_add(self.slot + self.estimate(item))
+            }),
+        }
+    }
+}
+
+/// Estimates the footprint of `v` in a JavaScript engine, using the default [`JsHeapModel`].
+///
+/// Strings cost 2 bytes per UTF-16 code unit, so non-BMP characters such as emoji cost 4 bytes
+/// regardless of their UTF-8 length.
+///
+/// ## Example
+/// ```
+/// use json_size::{utf16_size, JsHeapModel};
+/// use serde_json::json;
+///
+/// let val = json!({"greeting": "héllo 👋"});
+/// assert_eq!(utf16_size(&val), JsHeapModel::default().estimate(&val));
+/// ```
+pub fn utf16_size(v: &Value) -> usize {
+    JsHeapModel::default().estimate(v)
+}
+
+/// Length in UTF-16 code units of the compact JSON serialization of `v`, i.e. what
+/// `JSON.stringify(v).length` returns in JavaScript.
+///
+/// This is the number browsers compare against `localStorage` quotas.
+///
+/// ## Example
+/// ```
+/// use json_size::utf16_serialized_len;
+/// use serde_json::json;
+///
+/// // `["👋"]`: two brackets, two quotes and a surrogate pair.
+/// assert_eq!(utf16_serialized_len(&json!(["👋"])), 6);
+/// ```
+pub fn utf16_serialized_len(v: &Value) -> usize {
+    let mut w = Utf16Counter(0);
+    serde_json::to_writer(&mut w, v).expect("serializing into a counter cannot fail");
+    w.0
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+/// Counts the UTF-16 code units of the UTF-8 text written to it.
+struct Utf16Counter(usize);
+
+impl io::Write for Utf16Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Counting lead bytes works even if a character is split across writes: four-byte
+        // sequences need a surrogate pair, everything else one code unit.
+        for &b in buf {
+            self.0 += match b {
+                0x80..=0xBF => 0,
+                0xF0..=0xFF => 2,
+                _ => 1,
+            };
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_strings_count_code_units() {
+        let m = JsHeapModel::default();
+        assert_eq!(m.estimate(&json!("abc")), 16 + 6);
+        assert_eq!(m.estimate(&json!("é")), 16 + 2);
+        assert_eq!(m.estimate(&json!("👋")), 16 + 4);
+    }
+
+    #[test]
+    fn test_containers_and_numbers() {
+        let m = JsHeapModel::default();
+        assert_eq!(m.estimate(&json!([1, 2.5, null])), 16 + 3 * 8 + 16);
+        assert_eq!(
+            m.estimate(&json!({"a": true, "b": 5_000_000_000u64})),
+            16 + 2 * 8 + 16
+        );
+    }
+
+    #[test]
+    fn test_serialized_len_matches_encode_utf16() {
+        let val = json!({"text": "mixed ascii, ümlauts and 🎉🎉", "n": [1, 2]});
+        assert_eq!(
+            utf16_serialized_len(&val),
+            val.to_string().encode_utf16().count()
+        );
+    }
+}
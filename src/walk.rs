@@ -0,0 +1,267 @@
+use crate::{JsonPath, SizeEstimator};
+use serde_json::{Map, Value};
+use std::ops::ControlFlow;
+
+/// Walks `v` and calls `f` with the path, value and estimated size of every node.
+///
+/// Nodes are visited in depth-first post-order, so each node is reported after all of its
+/// descendants, with the size of its whole subtree. As everywhere in this crate, the size of an
+/// object member includes its key and map entry overhead. The root is visited last, with the same
+/// size [`crate::sizeof_val`] returns.
+///
+/// Returning `ControlFlow::Break(())` from `f` stops the walk immediately, and `walk_sizes`
+/// returns `Break(())`. Otherwise it returns `Continue` with the total size.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::walk_sizes;
+/// use std::ops::ControlFlow;
+///
+/// let val = json!({"users": [{"name": "ada", "avatar": "iVBORw0KGgoAAAANSUhEUgAA"}]});
+/// let mut strings = 0;
+/// walk_sizes(&val, |_path, v, size| {
+///     if v.is_string() {
+///         strings += size;
+///     }
+///     ControlFlow::Continue(())
+/// });
+/// assert!(strings > 0);
+/// ```
+pub fn walk_sizes<F>(v: &Value, f: F) -> ControlFlow<(), usize>
+where
+    F: FnMut(&JsonPath, &Value, usize) -> ControlFlow<()>,
+{
+    SizeEstimator::DEFAULT.walk_sizes(v, f)
+}
+
+/// Returns every path whose subtree is estimated at more than `threshold` bytes, heaviest first.
+///
+/// Ancestors of an oversized node are oversized too, so they are all included, down to the root.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::paths_over;
+///
+/// let val = json!({"small": 1, "a": "x".repeat(500), "b": "y".repeat(600)});
+/// let paths: Vec<String> = paths_over(&val, 400)
+///     .iter()
+///     .map(|(path, _)| path.to_string())
+///     .collect();
+/// assert_eq!(paths, ["", "/b", "/a"]);
+/// ```
+pub fn paths_over(v: &Value, threshold: usize) -> Vec<(JsonPath, usize)> {
+    SizeEstimator::DEFAULT.paths_over(v, threshold)
+}
+
+/// Returns the path and estimated size of the largest scalar in `v`: a string, number, boolean or
+/// `null`. Oversized documents are often one embedded blob, and this points straight at it.
+///
+/// The size of an object member includes its key and map entry overhead. Ties go to the first
+/// leaf in document order. Returns `None` if `v` holds no scalar at all, e.g. `[]`.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::largest_leaf;
+///
+/// let val = json!({"users": [{"name": "ada", "avatar": "iVBORw0KGgo".repeat(1000)}]});
+/// let (path, size) = largest_leaf(&val).unwrap();
+/// assert_eq!(path.to_string(), "/users/0/avatar");
+/// assert!(size > 11_000);
+/// ```
+pub fn largest_leaf(v: &Value) -> Option<(JsonPath, usize)> {
+    SizeEstimator::DEFAULT.largest_leaf(v)
+}
+
+/// Returns the keys of `map` with the estimated size of their member, heaviest first.
+///
+/// The size of a member includes its key and map entry overhead. Every node is visited once.
+///
+/// ## Example
+/// ```
+/// use serde_json::json;
+/// use json_size::keys_by_size;
+///
+/// let val = json!({"id": 1, "avatar": "iVBORw0KGgoAAAANSUhEUgAA", "name": "ada"});
+/// let keys: Vec<&str> = keys_by_size(val.as_object().unwrap())
+///     .into_iter()
+///     .map(|(key, _)| key)
+///     .collect();
+/// assert_eq!(keys, ["avatar", "name", "id"]);
+/// ```
+pub fn keys_by_size(map: &Map<String, Value>) -> Vec<(&str, usize)> {
+    SizeEstimator::DEFAULT.keys_by_size(map)
+}
+
+impl SizeEstimator {
+    /// Same as [`keys_by_size`], with sizes computed by this estimator.
+    pub fn keys_by_size<'a>(&self, map: &'a Map<String, Value>) -> Vec<(&'a str, usize)> {
+        let mut keys: Vec<(&str, usize)> = map
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.as_str(),
+                    self.entry_overhead(k).saturating_add(self.estimate(v)),
+                )
+            })
+            .collect();
+        keys.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        keys
+    }
+
+    /// Same as [`largest_leaf`], with sizes computed by this estimator.
+    pub fn largest_leaf(&self, v: &Value) -> Option<(JsonPath, usize)> {
+        let mut largest: Option<(JsonPath, usize)> = None;
+        let _ = self.walk_sizes(v, |path, node, size| {
+            let leaf = !node.is_array() && !node.is_object();
+            if leaf && largest.as_ref().is_none_or(|&(_, max)| size > max) {
+                largest = Some((path.clone(), size));
+            }
+            ControlFlow::Continue(())
+        });
+        largest
+    }
+
+    /// Same as [`paths_over`], with sizes computed by this estimator.
+    pub fn paths_over(&self, v: &Value, threshold: usize) -> Vec<(JsonPath, usize)> {
+        let mut over = Vec::new();
+        let _ = self.walk_sizes(v, |path, _, size| {
+            if size > threshold {
+                over.push((path.clone(), size));
+            }
+            ControlFlow::Continue(())
+        });
+        over.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        over
+    }
+
+    /// Same as [`walk_sizes`], with sizes computed by this estimator.
+    pub fn walk_sizes<F>(&self, v: &Value, mut f: F) -> ControlFlow<(), usize>
+    where
+        F: FnMut(&JsonPath, &Value, usize) -> ControlFlow<()>,
+    {
+        self.walk(v, &mut JsonPath::root(), 0, &mut f)
+    }
+
+    fn walk<F>(
+        &self,
+        v: &Value,
+        path: &mut JsonPath,
+        extra: usize,
+        f: &mut F,
+    ) -> ControlFlow<(), usize>
+    where
+        F: FnMut(&JsonPath, &Value, usize) -> ControlFlow<()>,
+    {
+        let mut size = self.own_size(v) + extra;
+        match v {
+            Value::Array(a) => {
+                for (i, item) in a.iter().enumerate() {
+                    path.push_index(i);
+                    let child = self.walk(item, path, 0, f);
+                    path.pop();
+                    size = size.saturating_add(child?);
+                }
+            }
+            Value::Object(o) => {
+                for (k, item) in o {
+                    path.push_key(k.as_str());
+                    let child = self.walk(item, path, self.entry_overhead(k), f);
+                    path.pop();
+                    size = size.saturating_add(child?);
+                }
+            }
+            _ => {}
+        }
+        #[cfg(feature = "tracing")]
+        self.trace_node(path, size);
+        f(path, v, size)?;
+        ControlFlow::Continue(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sizeof_val, SizeTree};
+    use serde_json::json;
+
+    #[test]
+    fn test_sizes_match_tree() {
+        let val = json!({"a": [1, "two"], "b": {"c": null}});
+        let tree = SizeTree::new(&val);
+        let mut seen = Vec::new();
+        let total = walk_sizes(&val, |path, _, size| {
+            seen.push((path.to_string(), size));
+            ControlFlow::Continue(())
+        });
+        assert_eq!(total, ControlFlow::Continue(sizeof_val(&val)));
+        for node in tree.iter() {
+            assert!(seen.contains(&(node.path.to_string(), node.cumulative_size)));
+        }
+        assert_eq!(seen.len(), tree.iter().count());
+    }
+
+    #[test]
+    fn test_post_order() {
+        let mut paths = Vec::new();
+        let _ = walk_sizes(&json!({"a": [1, 2], "b": 3}), |path, _, _| {
+            paths.push(path.to_string());
+            ControlFlow::Continue(())
+        });
+        assert_eq!(paths, ["/a/0", "/a/1", "/a", "/b", ""]);
+    }
+
+    #[test]
+    fn test_paths_over_threshold() {
+        let val = json!({"items": ["a".repeat(100), "b".repeat(100), "c"]});
+        let over = paths_over(&val, 100);
+        let paths: Vec<String> = over.iter().map(|(p, _)| p.to_string()).collect();
+        assert_eq!(paths, ["", "/items", "/items/0", "/items/1"]);
+        assert_eq!(over[0].1, sizeof_val(&val));
+        assert!(over.iter().all(|&(_, size)| size > 100));
+        assert!(paths_over(&val, usize::MAX).is_empty());
+    }
+
+    #[test]
+    fn test_largest_leaf() {
+        let val = json!({"a": ["x".repeat(10), "y".repeat(10)], "b": {"c": 1}});
+        let (path, size) = largest_leaf(&val).unwrap();
+        assert_eq!(path.to_string(), "/a/0");
+        assert_eq!(size, sizeof_val(&json!("x".repeat(10))));
+        assert_eq!(
+            largest_leaf(&json!(true)),
+            Some((JsonPath::root(), std::mem::size_of::<Value>()))
+        );
+        assert_eq!(largest_leaf(&json!([[], {}])), None);
+    }
+
+    #[test]
+    fn test_keys_by_size_sums_to_object() {
+        let val = json!({"a": [1, 2, 3], "bb": {"c": "dddd"}, "e": null});
+        let map = val.as_object().unwrap();
+        let keys = keys_by_size(map);
+        assert_eq!(keys[0].0, "bb");
+        assert!(keys.windows(2).all(|w| w[0].1 >= w[1].1));
+        let members: usize = keys.iter().map(|&(_, size)| size).sum();
+        let object = std::mem::size_of::<Value>() + crate::map_overhead(map.len());
+        assert_eq!(members + object, sizeof_val(&val));
+    }
+
+    #[test]
+    fn test_break_stops_walk() {
+        let mut visited = 0;
+        let result = walk_sizes(&json!([1, 2, 3, 4]), |_, _, _| {
+            visited += 1;
+            if visited == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(result, ControlFlow::Break(()));
+        assert_eq!(visited, 2);
+    }
+}
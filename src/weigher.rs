@@ -0,0 +1,186 @@
+//! Size functions for caches of `serde_json::Value`, enabled with the `moka` and `lru` features.
+//!
+//! They work with owned values and with shared ones (`Arc<Value>`, `Rc<Value>`), which is how
+//! values usually end up in caches.
+
+use serde_json::Value;
+#[cfg(feature = "moka")]
+use std::borrow::Borrow;
+#[cfg(feature = "lru")]
+use std::hash::{BuildHasher, Hash};
+#[cfg(feature = "lru")]
+use std::mem::size_of;
+
+/// A weigher for `moka` caches, computing the estimated size of the cached value.
+///
+/// The key is not weighed, as its type is arbitrary; add its size yourself if it matters.
+/// Sizes beyond `u32::MAX` saturate.
+///
+/// ## Example
+/// ```
+/// use json_size::weigher::moka_weigher;
+/// use moka::sync::Cache;
+/// use serde_json::{json, Value};
+/// use std::sync::Arc;
+///
+/// let cache: Cache<String, Arc<Value>> = Cache::builder()
+///     .max_capacity(64 * 1024 * 1024)
+///     .weigher(moka_weigher)
+///     .build();
+/// cache.insert("user:1".into(), Arc::new(json!({"name": "ada"})));
+/// ```
+#[cfg(feature = "moka")]
+pub fn moka_weigher<K, V: Borrow<Value>>(_key: &K, value: &V) -> u32 {
+    u32::try_from(crate::sizeof_val(value.borrow())).unwrap_or(u32::MAX)
+}
+
+/// A value stored in an `lru::LruCache`: an owned `Value`, or one shared through `Arc` or `Rc`.
+#[cfg(feature = "lru")]
+pub trait CachedValue {
+    /// Bytes of the allocation holding the `Value` outside the cache entry: none for an owned
+    /// `Value`, the `Value` and the reference counts for a shared one.
+    const OUT_OF_LINE: usize;
+
+    fn value(&self) -> &Value;
+}
+
+#[cfg(feature = "lru")]
+impl CachedValue for Value {
+    const OUT_OF_LINE: usize = 0;
+
+    fn value(&self) -> &Value {
+        self
+    }
+}
+
+#[cfg(feature = "lru")]
+impl<P: crate::SharedValue> CachedValue for P {
+    const OUT_OF_LINE: usize = size_of::<Value>() + P::ALLOCATION_OVERHEAD;
+
+    fn value(&self) -> &Value {
+        crate::SharedValue::value(self)
+    }
+}
+
+/// Bookkeeping per `lru::LruCache` entry: the list node's two links, the boxed node pointer and
+/// the hash table's key reference.
+#[cfg(feature = "lru")]
+const LRU_ENTRY_OVERHEAD: usize = 4 * size_of::<usize>();
+
+/// Estimated size of one `lru::LruCache` entry: the inline key, the value including its heap
+/// buffers, and the cache's per-entry bookkeeping.
+///
+/// A shared value is counted in full, with the allocation holding it, as if the cache were its
+/// only owner. Heap memory owned by the key (e.g. a `String` buffer) is not included.
+#[cfg(feature = "lru")]
+pub fn lru_size_of<K, V: CachedValue>(_key: &K, value: &V) -> usize {
+    // `sizeof_val` counts the `Value` itself, which `size_of::<V>()` and `OUT_OF_LINE` cover.
+    crate::sizeof_val(value.value()) - size_of::<Value>()
+        + size_of::<K>()
+        + size_of::<V>()
+        + V::OUT_OF_LINE
+        + LRU_ENTRY_OVERHEAD
+}
+
+/// Evicts least recently used entries until `total`, the caller's running sum of the entries'
+/// [`lru_size_of`], is at most `max_bytes`, returning the evicted entries, oldest first.
+///
+/// `lru::LruCache` bounds only the number of entries. To bound it by estimated bytes as well,
+/// add the `lru_size_of` of every entry put in the cache to `total` and subtract that of every
+/// entry leaving it otherwise, such as the old value `put` returns; this subtracts the evicted
+/// entries itself. Only the evicted entries are sized, so trimming costs nothing when the cache
+/// is within budget.
+///
+/// ## Example
+/// ```
+/// use json_size::weigher::{lru_size_of, lru_trim_to};
+/// use lru::LruCache;
+/// use serde_json::json;
+///
+/// let mut cache = LruCache::unbounded();
+/// let mut total = 0;
+/// for (k, v) in [(1, json!("x".repeat(1000))), (2, json!("small"))] {
+///     total += lru_size_of(&k, &v);
+///     cache.put(k, v);
+/// }
+/// let evicted = lru_trim_to(&mut cache, &mut total, 500);
+/// assert_eq!(evicted[0].0, 1);
+/// assert!(total <= 500);
+/// ```
+#[cfg(feature = "lru")]
+pub fn lru_trim_to<K, V, S>(
+    cache: &mut lru::LruCache<K, V, S>,
+    total: &mut usize,
+    max_bytes: usize,
+) -> Vec<(K, V)>
+where
+    K: Hash + Eq,
+    V: CachedValue,
+    S: BuildHasher,
+{
+    let mut evicted = Vec::new();
+    while *total > max_bytes {
+        let Some((k, v)) = cache.pop_lru() else {
+            break;
+        };
+        *total = total.saturating_sub(lru_size_of(&k, &v));
+        evicted.push((k, v));
+    }
+    evicted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[cfg(feature = "moka")]
+    #[test]
+    fn test_moka_weigher_matches_sizeof_val() {
+        let val = json!({"a": [1, 2, 3]});
+        assert_eq!(moka_weigher(&(), &val) as usize, crate::sizeof_val(&val));
+        assert_eq!(
+            moka_weigher(&(), &Arc::new(val.clone())) as usize,
+            crate::sizeof_val(&val)
+        );
+    }
+
+    #[cfg(feature = "lru")]
+    #[test]
+    fn test_lru_shared_values_count_their_allocation() {
+        let val = json!("text");
+        let owned = lru_size_of(&0u64, &val);
+        // The pointer in the entry, and the strong and weak counts next to the `Value`.
+        let allocation = 3 * size_of::<usize>();
+        assert_eq!(
+            lru_size_of(&0u64, &Arc::new(val.clone())),
+            owned + allocation
+        );
+        assert_eq!(
+            lru_size_of(&0u64, &std::rc::Rc::new(val)),
+            owned + allocation
+        );
+    }
+
+    #[cfg(feature = "lru")]
+    #[test]
+    fn test_lru_trim_to_respects_recency() {
+        let mut cache = lru::LruCache::unbounded();
+        let mut total = 0;
+        for (k, v) in [("a", "x"), ("b", "y"), ("c", "z")] {
+            let v = json!(v.repeat(100));
+            total += lru_size_of(&k, &v);
+            cache.put(k, v);
+        }
+        cache.get(&"a");
+        let one = lru_size_of(&"a", cache.peek(&"a").unwrap());
+        let evicted = lru_trim_to(&mut cache, &mut total, 2 * one);
+        let keys: Vec<_> = evicted.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, ["b"]);
+        assert_eq!(cache.len(), 2);
+        let remaining: usize = cache.iter().map(|(k, v)| lru_size_of(k, v)).sum();
+        assert_eq!(total, remaining);
+        assert!(lru_trim_to(&mut cache, &mut total, 2 * one).is_empty());
+    }
+}
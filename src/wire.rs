@@ -0,0 +1,417 @@
+use crate::escape_inflation;
+use serde::Serialize;
+use serde_json::{Number, Value};
+use std::fmt;
+
+/// An encoding compared by [`wire_size_comparison`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    Json,
+    PrettyJson,
+    Cbor,
+    MessagePack,
+    Bson,
+}
+
+impl WireFormat {
+    pub const ALL: [WireFormat; 5] = [
+        Self::Json,
+        Self::PrettyJson,
+        Self::Cbor,
+        Self::MessagePack,
+        Self::Bson,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::PrettyJson => "pretty JSON",
+            Self::Cbor => "CBOR",
+            Self::MessagePack => "MessagePack",
+            Self::Bson => "BSON",
+        }
+    }
+}
+
+/// Encoded sizes of one document in several wire formats, created by [`wire_size_comparison`].
+///
+/// Displaying a comparison prints a table of the sizes and their difference from compact JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FormatComparison {
+    /// Compact JSON, as computed by [`crate::serialized_size`].
+    pub json: usize,
+    /// JSON indented by two spaces, as written by `serde_json::to_string_pretty`.
+    pub pretty_json: usize,
+    /// CBOR with definite lengths and floats in the shortest width that holds them exactly, as
+    /// written by ciborium.
+    pub cbor: usize,
+    /// MessagePack with integers in their shortest form and floats as 64 bits, as written by
+    /// rmp-serde.
+    pub messagepack: usize,
+    /// BSON with integers as int64, as written by the bson crate, or `None` if the document
+    /// cannot be encoded: the root is not an object, a key holds a NUL byte, or an integer
+    /// exceeds `i64::MAX`.
+    pub bson: Option<usize>,
+}
+
+impl FormatComparison {
+    /// The size in `format`, `None` if the document cannot be encoded in it.
+    pub fn size(&self, format: WireFormat) -> Option<usize> {
+        match format {
+            WireFormat::Json => Some(self.json),
+            WireFormat::PrettyJson => Some(self.pretty_json),
+            WireFormat::Cbor => Some(self.cbor),
+            WireFormat::MessagePack => Some(self.messagepack),
+            WireFormat::Bson => self.bson,
+        }
+    }
+
+    /// How much larger the document is in `format` than as compact JSON, in percent; negative if
+    /// it is smaller.
+    pub fn delta_percent(&self, format: WireFormat) -> Option<f64> {
+        let size = self.size(format)?;
+        Some(100.0 * (size as f64 - self.json as f64) / self.json as f64)
+    }
+
+    /// The format with the smallest encoding, and its size. Ties go to the format listed first
+    /// in [`WireFormat::ALL`].
+    pub fn smallest(&self) -> (WireFormat, usize) {
+        WireFormat::ALL
+            .into_iter()
+            .filter_map(|format| Some((format, self.size(format)?)))
+            .min_by_key(|&(_, size)| size)
+            .expect("JSON is always available")
+    }
+}
+
+impl fmt::Display for FormatComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<12} {:>12} {:>9}", "format", "bytes", "vs JSON")?;
+        for format in WireFormat::ALL {
+            match (self.size(format), self.delta_percent(format)) {
+                (Some(size), Some(delta)) => {
+                    write!(f, "\n{:<12} {size:>12} {delta:>+8.1}%", format.name())?
+                }
+                _ => write!(f, "\n{:<12} {:>12} {:>9}", format.name(), "n/a", "")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Computes the encoded size of `v` as compact JSON, pretty JSON, CBOR, MessagePack and BSON in
+/// a single traversal, without encoding anything.
+///
+/// The binary sizes follow the encodings' specifications and the choices of the usual serde
+/// crates, described on the fields of [`FormatComparison`], without depending on them.
+///
+/// ## Example
+/// ```
+/// use json_size::{serialized_size, wire_size_comparison, WireFormat};
+/// use serde_json::json;
+///
+/// let val = json!({"id": 7, "scores": [1, 2, 3], "name": "ada"});
+/// let sizes = wire_size_comparison(&val);
+/// assert_eq!(sizes.json, serialized_size(&val));
+/// assert_eq!(sizes.pretty_json, serde_json::to_string_pretty(&val).unwrap().len());
+/// assert_eq!(sizes.smallest().0, WireFormat::Cbor);
+/// assert!(sizes.delta_percent(WireFormat::MessagePack).unwrap() < -30.0);
+/// println!("{sizes}");
+/// ```
+pub fn wire_size_comparison(v: &Value) -> FormatComparison {
+    let sizes = measure(v, 0);
+    FormatComparison {
+        json: sizes.json,
+        pretty_json: sizes.pretty_json,
+        cbor: sizes.cbor,
+        messagepack: sizes.messagepack,
+        // Only documents can be encoded at the root.
+        bson: sizes.bson.filter(|_| v.is_object()),
+    }
+}
+
+/// The sizes of one node; `bson` is the size of its value in a BSON element.
+struct Sizes {
+    json: usize,
+    pretty_json: usize,
+    cbor: usize,
+    messagepack: usize,
+    bson: Option<usize>,
+}
+
+impl Sizes {
+    fn scalar(json: usize, cbor: usize, messagepack: usize, bson: Option<usize>) -> Self {
+        Self {
+            json,
+            pretty_json: json,
+            cbor,
+            messagepack,
+            bson,
+        }
+    }
+}
+
+fn measure(v: &Value, depth: usize) -> Sizes {
+    match v {
+        Value::Null => Sizes::scalar(4, 1, 1, Some(0)),
+        Value::Bool(b) => Sizes::scalar(if *b { 4 } else { 5 }, 1, 1, Some(1)),
+        Value::Number(n) => number(n),
+        Value::String(s) => Sizes::scalar(
+            json_string(s),
+            cbor_header(s.len() as u64) + s.len(),
+            msgpack_str_header(s.len()) + s.len(),
+            Some(4 + s.len() + 1),
+        ),
+        Value::Array(a) => container(
+            a.len(),
+            a.iter()
+                .enumerate()
+                .map(|(i, item)| (None, Some(decimal_len(i)), measure(item, depth + 1))),
+            depth,
+        ),
+        Value::Object(o) => container(
+            o.len(),
+            o.iter().map(|(k, item)| {
+                let key = Key {
+                    json: json_string(k),
+                    cbor: cbor_header(k.len() as u64) + k.len(),
+                    messagepack: msgpack_str_header(k.len()) + k.len(),
+                };
+                let bson_name = (!k.contains('\0')).then_some(k.len());
+                (Some(key), bson_name, measure(item, depth + 1))
+            }),
+            depth,
+        ),
+    }
+}
+
+/// The encoded sizes of an object key.
+struct Key {
+    json: usize,
+    cbor: usize,
+    messagepack: usize,
+}
+
+/// Sums the sizes of an array or object of `len` children. Each child comes with its key,
+/// absent for array elements, and the length of its BSON element name, `None` if it cannot be
+/// one.
+fn container(
+    len: usize,
+    children: impl Iterator<Item = (Option<Key>, Option<usize>, Sizes)>,
+    depth: usize,
+) -> Sizes {
+    // Brackets, and the separators between children.
+    let mut sizes = Sizes {
+        json: 2 + len.saturating_sub(1),
+        pretty_json: 2 + len.saturating_sub(1),
+        cbor: cbor_header(len as u64),
+        messagepack: msgpack_container_header(len),
+        // Length prefix and terminator of the document.
+        bson: Some(4 + 1),
+    };
+    if len > 0 {
+        // A line break and indentation before each child and before the closing bracket.
+        sizes.pretty_json += len * (1 + 2 * (depth + 1)) + 1 + 2 * depth;
+    }
+    for (key, bson_name, child) in children {
+        if let Some(key) = key {
+            sizes.json += key.json + 1;
+            // `": "` rather than `:`.
+            sizes.pretty_json += key.json + 2;
+            sizes.cbor += key.cbor;
+            sizes.messagepack += key.messagepack;
+        }
+        sizes.json = sizes.json.saturating_add(child.json);
+        sizes.pretty_json = sizes.pretty_json.saturating_add(child.pretty_json);
+        sizes.cbor = sizes.cbor.saturating_add(child.cbor);
+        sizes.messagepack = sizes.messagepack.saturating_add(child.messagepack);
+        // Element type, NUL-terminated name, and value.
+        sizes.bson = match (sizes.bson, bson_name, child.bson) {
+            (Some(total), Some(name), Some(value)) => {
+                Some(total.saturating_add(1 + name + 1 + value))
+            }
+            _ => None,
+        };
+    }
+    sizes
+}
+
+fn number(n: &Number) -> Sizes {
+    let json = n.to_string().len();
+    if let Some(u) = n.as_u64() {
+        let bson = (u <= i64::MAX as u64).then_some(8);
+        Sizes::scalar(json, cbor_header(u), msgpack_uint(u), bson)
+    } else if let Some(i) = n.as_i64() {
+        // A CBOR negative integer stores `-1 - i`.
+        let cbor = cbor_header(!i as u64);
+        Sizes::scalar(json, cbor, msgpack_int(i), Some(8))
+    } else {
+        let f = n.as_f64().unwrap_or(f64::NAN);
+        Sizes::scalar(json, cbor_float(f), 9, Some(8))
+    }
+}
+
+/// Length of `s` as a JSON string, quotes included.
+fn json_string(s: &str) -> usize {
+    2 + s.len() + escape_inflation(s)
+}
+
+fn decimal_len(i: usize) -> usize {
+    i.checked_ilog10().unwrap_or(0) as usize + 1
+}
+
+/// Initial byte and argument of a CBOR item whose argument is `arg`.
+fn cbor_header(arg: u64) -> usize {
+    match arg {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+/// A float in the shortest of half, single and double precision that holds it exactly.
+fn cbor_float(f: f64) -> usize {
+    if fits_f16(f) {
+        3
+    } else if f64::from(f as f32) == f {
+        5
+    } else {
+        9
+    }
+}
+
+fn fits_f16(f: f64) -> bool {
+    let a = f.abs();
+    if a == 0.0 || !a.is_finite() {
+        return true;
+    }
+    if a > 65504.0 {
+        return false;
+    }
+    let exponent = ((a.to_bits() >> 52) & 0x7ff) as i32 - 1023;
+    // Normal halves have 10 fraction bits; subnormals are multiples of 2^-24.
+    let scale = if exponent >= -14 { 10 - exponent } else { 24 };
+    exponent >= -24 && (a * 2f64.powi(scale)).fract() == 0.0
+}
+
+fn msgpack_uint(u: u64) -> usize {
+    match u {
+        0..=0x7f => 1,
+        0x80..=0xff => 2,
+        0x100..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+fn msgpack_int(i: i64) -> usize {
+    match i {
+        0.. => msgpack_uint(i as u64),
+        -32..=-1 => 1,
+        -0x80..=-33 => 2,
+        -0x8000..=-0x81 => 3,
+        -0x8000_0000..=-0x8001 => 5,
+        _ => 9,
+    }
+}
+
+fn msgpack_str_header(len: usize) -> usize {
+    match len {
+        0..=31 => 1,
+        32..=0xff => 2,
+        0x100..=0xffff => 3,
+        _ => 5,
+    }
+}
+
+fn msgpack_container_header(len: usize) -> usize {
+    match len {
+        0..=15 => 1,
+        16..=0xffff => 3,
+        _ => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialized_size;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_sizes_match_serde_json() {
+        let val = json!({
+            "a": [1, -2, 3.5, null, true, false, [], {}, [[{"x": "y"}]]],
+            "esc\"aped": "line\nbreak \u{1}é",
+            "nested": {"deep": {"deeper": [1e300, u64::MAX, i64::MIN]}},
+        });
+        let sizes = wire_size_comparison(&val);
+        assert_eq!(sizes.json, serialized_size(&val));
+        assert_eq!(
+            sizes.pretty_json,
+            serde_json::to_string_pretty(&val).unwrap().len()
+        );
+        // u64::MAX has no BSON integer type.
+        assert_eq!(sizes.bson, None);
+    }
+
+    #[test]
+    fn test_binary_sizes() {
+        // CBOR: a1 61 6b 83 01 18 c8 f9 3e 00; MessagePack: 81 a1 6b 93 01 cc c8 cb and 8 bytes.
+        let val = json!({"k": [1, 200, 1.5]});
+        let sizes = wire_size_comparison(&val);
+        assert_eq!(sizes.cbor, 10);
+        assert_eq!(sizes.messagepack, 16);
+        // Outer document, array element named "k" and three elements named "0" to "2".
+        assert_eq!(sizes.bson, Some(5 + (1 + 2) + 5 + 3 * (1 + 2 + 8)));
+        assert_eq!(wire_size_comparison(&json!([1])).bson, None);
+        assert_eq!(wire_size_comparison(&json!({"a\u{0}": 1})).bson, None);
+    }
+
+    #[test]
+    fn test_integer_and_float_widths() {
+        let cbor = |v: Value| wire_size_comparison(&v).cbor;
+        let msgpack = |v: Value| wire_size_comparison(&v).messagepack;
+        assert_eq!(
+            [23, 24, 256, 65536, 1 << 32].map(|n: u64| cbor(json!(n))),
+            [1, 2, 3, 5, 9]
+        );
+        assert_eq!(
+            [-1, -24, -25, -257, -65537].map(|n: i64| cbor(json!(n))),
+            [1, 1, 2, 3, 5]
+        );
+        assert_eq!(
+            [0.5, 65504.0, f64::from(0.1f32), 0.1, 5.960464477539063e-8].map(|f| cbor(json!(f))),
+            [3, 3, 5, 9, 3]
+        );
+        assert_eq!(
+            [127, 128, 256, -32, -33, -129, -32769].map(|n: i64| msgpack(json!(n))),
+            [1, 2, 3, 1, 2, 3, 5]
+        );
+        assert_eq!(msgpack(json!(0.5)), 9);
+        assert_eq!(msgpack(json!("x".repeat(32))), 2 + 32);
+        assert_eq!(msgpack(json!(vec![0; 16])), 3 + 16);
+    }
+
+    #[test]
+    fn test_deltas_and_display() {
+        let sizes = wire_size_comparison(&json!({"id": 1}));
+        assert_eq!(sizes.delta_percent(WireFormat::Json), Some(0.0));
+        assert!(sizes.delta_percent(WireFormat::PrettyJson).unwrap() > 0.0);
+        assert_eq!(sizes.smallest(), (WireFormat::Cbor, 5));
+        let text = sizes.to_string();
+        assert_eq!(text.lines().count(), 6);
+        assert!(
+            text.contains("CBOR                    5    -37.5%"),
+            "{text}"
+        );
+        let scalar = wire_size_comparison(&json!(1)).to_string();
+        assert!(
+            scalar.ends_with("BSON                  n/a          "),
+            "{scalar}"
+        );
+    }
+}